@@ -0,0 +1,543 @@
+//! pytest-compatible command line parsing
+
+use std::{path::PathBuf, str::FromStr};
+
+use crate::{Error, config::split_args, module::python_str};
+
+/// Command line arguments, parsed in the same way as pytest does.
+///
+/// Usage:
+/// ```rust
+/// use pt::Args;
+/// let args = Args::parse(["-x", "--tb=short", "tests"]).unwrap();
+/// assert_eq!(Some(1), args.maxfail);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub paths: Vec<PathBuf>,
+    pub help: bool,
+    /// `-x` is equivalent to `--maxfail=1`
+    pub maxfail: Option<usize>,
+    /// incremented by `-v`, decremented by `-q`
+    pub verbosity: i8,
+    /// `-r` chars, `-rA` etc. Defaults to `fE`, as pytest
+    pub report_chars: String,
+    pub tb: TracebackStyle,
+    /// `-l`, show locals in tracebacks
+    pub showlocals: bool,
+    /// `-k` expression
+    pub keyword: Option<String>,
+    /// `-m` expression
+    pub markexpr: Option<String>,
+    /// `-p name`, plugins to load. pt loads no plugins, so these are ignored with a warning
+    pub plugins: Vec<String>,
+    /// `-p no:name`, plugins to disable. pt loads no plugins, so only `cacheprovider` has an effect
+    pub disabled_plugins: Vec<String>,
+    /// `-W` warning filters, `action:message:category:module:lineno` as python's `-W`
+    pub pythonwarnings: Vec<String>,
+    pub last_failed: bool,
+    pub failed_first: bool,
+    pub new_first: bool,
+    pub cache_clear: bool,
+    /// `--cache-show` with an optional glob, defaults to `*`
+    pub cache_show: Option<String>,
+    /// `-s` is equivalent to `--capture=no`
+    pub capture: Capture,
+    pub collect_only: bool,
+    /// `--continue-on-collection-errors`, run the tests which could be collected
+    pub continue_on_collection_errors: bool,
+    /// `--strict`, collection warnings are errors
+    pub strict: bool,
+    /// `--strict-markers`, marks not registered in the `markers` ini option are errors
+    pub strict_markers: bool,
+    /// `--basetemp`, directory for `tmp_path` fixtures, emptied at the start of the run
+    pub basetemp: Option<PathBuf>,
+    /// `--durations`, accepted for compatibility: pt does not time tests
+    pub durations: Option<usize>,
+    pub import_mode: ImportMode,
+    /// `--runner`, how test modules are run. Not a pytest option.
+    pub runner: RunnerMode,
+    /// `--python`, interpreter to run tests with. Not a pytest option.
+    pub python: Option<PathBuf>,
+    /// `--pytest-shim`, use pt's own `pytest` module even if pytest is installed. Not a pytest
+    /// option.
+    pub pytest_shim: bool,
+}
+
+/// `--tb` choices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TracebackStyle {
+    #[default]
+    Auto,
+    Long,
+    Short,
+    Line,
+    Native,
+    No,
+}
+
+impl FromStr for TracebackStyle {
+    type Err = Error;
+    fn from_str(style: &str) -> Result<Self, Self::Err> {
+        match style {
+            "auto" => Ok(Self::Auto),
+            "long" => Ok(Self::Long),
+            "short" => Ok(Self::Short),
+            "line" => Ok(Self::Line),
+            "native" => Ok(Self::Native),
+            "no" => Ok(Self::No),
+            _ => Err(Error::InvalidArgument(format!(
+                "argument --tb: invalid choice: '{style}' (choose from auto, long, short, line, native, no)"
+            ))),
+        }
+    }
+}
+
+/// `--capture` choices.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    #[default]
+    Fd,
+    Sys,
+    TeeSys,
+    No,
+}
+
+impl FromStr for Capture {
+    type Err = Error;
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method {
+            "fd" => Ok(Self::Fd),
+            "sys" => Ok(Self::Sys),
+            "tee-sys" => Ok(Self::TeeSys),
+            "no" => Ok(Self::No),
+            _ => Err(Error::InvalidArgument(format!(
+                "argument --capture: invalid choice: '{method}' (choose from fd, sys, no, tee-sys)"
+            ))),
+        }
+    }
+}
+
+/// `--import-mode` choices: how the directory containing a test module (or its top-level package)
+/// is added to `sys.path`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    #[default]
+    Prepend,
+    Append,
+    /// `sys.path` is not changed
+    Importlib,
+}
+
+impl FromStr for ImportMode {
+    type Err = Error;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "prepend" => Ok(Self::Prepend),
+            "append" => Ok(Self::Append),
+            "importlib" => Ok(Self::Importlib),
+            _ => Err(Error::InvalidArgument(format!(
+                "argument --import-mode: invalid choice: '{mode}' (choose from prepend, append, importlib)"
+            ))),
+        }
+    }
+}
+
+/// `--runner` choices: how the tests in a module are run by python.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RunnerMode {
+    /// The test source, followed by code to run each test, is executed as a script
+    #[default]
+    Inline,
+    /// The test module is imported from its file and each test run by a generic harness
+    Import,
+}
+
+impl FromStr for RunnerMode {
+    type Err = Error;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "inline" => Ok(Self::Inline),
+            "import" => Ok(Self::Import),
+            _ => Err(Error::InvalidArgument(format!(
+                "argument --runner: invalid choice: '{mode}' (choose from inline, import)"
+            ))),
+        }
+    }
+}
+
+impl Args {
+    pub const USAGE: &str = "\
+usage: pt [options] [file_or_dir] [file_or_dir] [...]
+
+positional arguments:
+  file_or_dir
+
+general:
+  -k EXPRESSION         Only run tests which match the given substring expression
+  -m MARKEXPR           Only run tests matching given mark expression
+  -x, --exitfirst       Exit instantly on first error or failed test
+  --maxfail=num         Exit after first num failures or errors
+  -p name               Early-load given plugin module name (ignored with a warning, pt loads no
+                        plugins). `-p no:name` disables a plugin, only `no:cacheprovider` has an
+                        effect.
+  --basetemp=dir        Base temporary directory for this test run. (Warning: this directory is
+                        removed if it exists.)
+  --python=PATH         Python interpreter to run tests with (default: the active virtualenv,
+                        .venv in rootdir, uv's interpreter for the project, or python on PATH)
+  --pytest-shim         Import pt's own `pytest` module, even if pytest is installed (default: only
+                        if it is not)
+  -h, --help            Show help message and exit
+
+reporting:
+  -v, --verbose         Increase verbosity
+  -q, --quiet           Decrease verbosity
+  -r chars              Show extra test summary info as specified by chars: (f)ailed, (E)rror,
+                        (a)ll except passed, (A)ll, (N)one to reset the list. (default: 'fE')
+  --tb=style            Traceback print mode (auto/long/short/line/native/no)
+  -l, --showlocals      Show locals in tracebacks (disabled by default)
+  --no-showlocals       Hide locals in tracebacks (negate --showlocals passed through addopts)
+  --durations=N         Accepted for compatibility, pt does not report test durations
+
+collection:
+  --co, --collect-only  Only collect tests, don't execute them
+  --continue-on-collection-errors
+                        Force test execution even if collection errors occur
+  --strict              Treat collection warnings (redefined, shadowed or nested tests, unknown
+                        marks) as collection errors
+  --strict-markers      Markers not registered in the `markers` section of the configuration file
+                        raise errors
+  --import-mode={prepend,append,importlib}
+                        Prepend/append to sys.path when importing test modules and conftest
+                        files. Default: prepend.
+  --runner={inline,import}
+                        Run each test module's source as a script with the tests appended
+                        (inline), or import the module from its file (import). Default: inline.
+
+cache:
+  --lf, --last-failed   Rerun only the tests that failed at the last run
+  --ff, --failed-first  Run all tests, but run the last failures first
+  --nf, --new-first     Run tests from new files first, then the rest of the tests
+  --cache-show=[CACHESHOW]
+                        Show cache contents, don't perform collection or tests. Optional argument:
+                        glob (default: '*').
+  --cache-clear         Remove all cache contents at start of test run
+
+capture:
+  --capture=method      Per-test capturing method: one of fd|sys|no|tee-sys
+  -s                    Shortcut for --capture=no
+
+warnings:
+  -W PYTHONWARNINGS, --pythonwarnings=PYTHONWARNINGS
+                        Set which warnings to report, see -W option of Python itself
+
+Options are also read from the `addopts` ini option and the PYTEST_ADDOPTS environment variable.
+";
+
+    /// Parse `cli_args`, following the `addopts` ini option and the `PYTEST_ADDOPTS` environment
+    /// variable, as pytest.
+    pub fn parse_with_addopts(
+        addopts: &[String],
+        env_addopts: &str,
+        cli_args: &[String],
+    ) -> Result<Self, Error> {
+        Self::parse(
+            addopts
+                .iter()
+                .cloned()
+                .chain(split_args(env_addopts))
+                .chain(cli_args.iter().cloned()),
+        )
+    }
+
+    /// Parse command line arguments, *excluding* the program name.
+    pub fn parse<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Result<Self, Error> {
+        let mut parsed = Self {
+            report_chars: "fE".to_string(),
+            ..Self::default()
+        };
+        let mut args = args.into_iter().map(|arg| arg.as_ref().to_string());
+        let mut only_paths = false;
+
+        while let Some(arg) = args.next() {
+            if only_paths || arg == "-" || !arg.starts_with('-') {
+                parsed.paths.push(arg.into());
+                continue;
+            }
+
+            if arg == "--" {
+                only_paths = true;
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (name, inline_value) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (long, None),
+                };
+                let mut value = || {
+                    inline_value.clone().or_else(|| args.next()).ok_or_else(|| {
+                        Error::InvalidArgument(format!("argument --{name}: expected one argument"))
+                    })
+                };
+                match name {
+                    "help" => parsed.help = true,
+                    "exitfirst" => parsed.maxfail = Some(1),
+                    // as pytest, `--maxfail=0` is no limit
+                    "maxfail" => {
+                        parsed.maxfail = Some(parse_number("--maxfail", &value()?)?)
+                            .filter(|&maxfail| maxfail > 0)
+                    }
+                    "verbose" => parsed.verbosity += 1,
+                    "quiet" => parsed.verbosity -= 1,
+                    "tb" => parsed.tb = value()?.parse()?,
+                    "showlocals" => parsed.showlocals = true,
+                    "no-showlocals" => parsed.showlocals = false,
+                    "durations" => parsed.durations = Some(parse_number("--durations", &value()?)?),
+                    "lf" | "last-failed" => parsed.last_failed = true,
+                    "ff" | "failed-first" => parsed.failed_first = true,
+                    "nf" | "new-first" => parsed.new_first = true,
+                    "cache-clear" => parsed.cache_clear = true,
+                    "cache-show" => {
+                        parsed.cache_show = Some(inline_value.unwrap_or("*".to_string()))
+                    }
+                    "capture" => parsed.capture = value()?.parse()?,
+                    "pythonwarnings" => parsed.pythonwarnings.push(value()?),
+                    "co" | "collect-only" | "collectonly" => parsed.collect_only = true,
+                    "continue-on-collection-errors" => parsed.continue_on_collection_errors = true,
+                    "strict" => parsed.strict = true,
+                    "strict-markers" => parsed.strict_markers = true,
+                    "basetemp" => parsed.basetemp = Some(value()?.into()),
+                    "import-mode" => parsed.import_mode = value()?.parse()?,
+                    "python" => parsed.python = Some(value()?.into()),
+                    "runner" => parsed.runner = value()?.parse()?,
+                    "pytest-shim" => parsed.pytest_shim = true,
+                    _ => {
+                        return Err(Error::InvalidArgument(format!(
+                            "unrecognized arguments: {arg}"
+                        )));
+                    }
+                }
+            } else {
+                // Short options may be combined (`-vvx`) and take values directly (`-rA`, `-kfoo`)
+                for (idx, flag) in arg[1..].char_indices() {
+                    let rest = &arg[1 + idx + flag.len_utf8()..];
+                    let mut value = || {
+                        if rest.is_empty() {
+                            args.next().ok_or_else(|| {
+                                Error::InvalidArgument(format!(
+                                    "argument -{flag}: expected one argument"
+                                ))
+                            })
+                        } else {
+                            Ok(rest.to_string())
+                        }
+                    };
+                    match flag {
+                        'h' => parsed.help = true,
+                        'x' => parsed.maxfail = Some(1),
+                        'v' => parsed.verbosity += 1,
+                        'q' => parsed.verbosity -= 1,
+                        's' => parsed.capture = Capture::No,
+                        'l' => parsed.showlocals = true,
+                        'r' => {
+                            parsed.report_chars = value()?;
+                            break;
+                        }
+                        'k' => {
+                            parsed.keyword = Some(value()?);
+                            break;
+                        }
+                        'm' => {
+                            parsed.markexpr = Some(value()?);
+                            break;
+                        }
+                        'p' => {
+                            let plugin = value()?;
+                            match plugin.strip_prefix("no:") {
+                                Some(name) => parsed.disabled_plugins.push(name.to_string()),
+                                None => parsed.plugins.push(plugin),
+                            }
+                            break;
+                        }
+                        'W' => {
+                            parsed.pythonwarnings.push(value()?);
+                            break;
+                        }
+                        _ => {
+                            return Err(Error::InvalidArgument(format!(
+                                "unrecognized arguments: {arg}"
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Values for `request.config.getoption()`, by pytest's option names, as python expressions.
+    pub fn options(&self) -> Vec<(String, String)> {
+        let string = |value: &str| python_str(value);
+        let boolean = |value: bool| if value { "True" } else { "False" }.to_string();
+        let tbstyle = match self.tb {
+            TracebackStyle::Auto => "auto",
+            TracebackStyle::Long => "long",
+            TracebackStyle::Short => "short",
+            TracebackStyle::Line => "line",
+            TracebackStyle::Native => "native",
+            TracebackStyle::No => "no",
+        };
+        let importmode = match self.import_mode {
+            ImportMode::Prepend => "prepend",
+            ImportMode::Append => "append",
+            ImportMode::Importlib => "importlib",
+        };
+        [
+            ("verbose", self.verbosity.to_string()),
+            ("maxfail", self.maxfail.unwrap_or_default().to_string()),
+            ("reportchars", string(&self.report_chars)),
+            ("tbstyle", string(tbstyle)),
+            ("showlocals", boolean(self.showlocals)),
+            (
+                "keyword",
+                string(self.keyword.as_deref().unwrap_or_default()),
+            ),
+            (
+                "markexpr",
+                string(self.markexpr.as_deref().unwrap_or_default()),
+            ),
+            ("lf", boolean(self.last_failed)),
+            ("failedfirst", boolean(self.failed_first)),
+            ("newfirst", boolean(self.new_first)),
+            ("cacheclear", boolean(self.cache_clear)),
+            ("capture", string(self.capture.method().unwrap_or("no"))),
+            ("collectonly", boolean(self.collect_only)),
+            (
+                "continue_on_collection_errors",
+                boolean(self.continue_on_collection_errors),
+            ),
+            ("strict", boolean(self.strict)),
+            ("strict_markers", boolean(self.strict_markers)),
+            (
+                "basetemp",
+                self.basetemp
+                    .as_ref()
+                    .map_or("None".to_string(), |path| string(&path.to_string_lossy())),
+            ),
+            (
+                "durations",
+                self.durations
+                    .map_or("None".to_string(), |durations| durations.to_string()),
+            ),
+            ("importmode", string(importmode)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<usize, Error> {
+    value.parse().map_err(|_| {
+        Error::InvalidArgument(format!("argument {flag}: invalid int value: '{value}'"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combined_short_flags() {
+        let args = Args::parse([
+            "-vvxl",
+            "-rA",
+            "-k",
+            "not slow",
+            "-Werror",
+            "--pythonwarnings",
+            "ignore::DeprecationWarning",
+            "tests/test_one.py",
+        ])
+        .unwrap();
+        assert_eq!(2, args.verbosity);
+        assert_eq!(Some(1), args.maxfail);
+        assert!(args.showlocals);
+        assert_eq!("A", args.report_chars);
+        assert_eq!(Some("not slow".to_string()), args.keyword);
+        assert_eq!(
+            vec!["error", "ignore::DeprecationWarning"],
+            args.pythonwarnings
+        );
+        assert_eq!(vec![PathBuf::from("tests/test_one.py")], args.paths);
+    }
+
+    #[test]
+    fn long_flags() {
+        let args = Args::parse([
+            "--maxfail",
+            "3",
+            "--tb=line",
+            "--co",
+            "-q",
+            "--lf",
+            "-s",
+            "--python",
+            ".venv/bin/python",
+            "--import-mode=append",
+            "--runner=import",
+            "--continue-on-collection-errors",
+            "--strict",
+            "--strict-markers",
+            "--basetemp=/tmp/pt",
+            "--pytest-shim",
+            "-p",
+            "no:cacheprovider",
+            "-pxdist",
+            "--durations=10",
+        ])
+        .unwrap();
+        assert_eq!(Some(3), args.maxfail);
+        assert_eq!(TracebackStyle::Line, args.tb);
+        assert!(args.collect_only);
+        assert!(args.last_failed);
+        assert_eq!(-1, args.verbosity);
+        assert_eq!(Capture::No, args.capture);
+        assert_eq!(Some(PathBuf::from(".venv/bin/python")), args.python);
+        assert_eq!(ImportMode::Append, args.import_mode);
+        assert_eq!(RunnerMode::Import, args.runner);
+        assert!(args.continue_on_collection_errors);
+        assert!(args.strict);
+        assert!(args.strict_markers);
+        assert_eq!(Some(PathBuf::from("/tmp/pt")), args.basetemp);
+        assert!(args.pytest_shim);
+        assert_eq!(vec!["cacheprovider"], args.disabled_plugins);
+        assert_eq!(vec!["xdist"], args.plugins);
+        assert_eq!(Some(10), args.durations);
+        assert_eq!("fE", args.report_chars);
+    }
+
+    #[test]
+    fn maxfail_zero_is_unlimited() {
+        assert_eq!(None, Args::parse(["--maxfail=0"]).unwrap().maxfail);
+        assert_eq!(None, Args::parse(["-x", "--maxfail", "0"]).unwrap().maxfail);
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert!(matches!(
+            Args::parse(["--no-such-flag"]),
+            Err(Error::InvalidArgument(msg)) if msg == "unrecognized arguments: --no-such-flag"
+        ));
+        assert!(matches!(
+            Args::parse(["--tb=fancy"]),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Args::parse(["--maxfail"]),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Args::parse(["--durations=slow"]),
+            Err(Error::InvalidArgument(msg)) if msg == "argument --durations: invalid int value: 'slow'"
+        ));
+    }
+}
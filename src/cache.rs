@@ -0,0 +1,198 @@
+//! `.pytest_cache` compatible persistent cache of test results
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{TestStatus, config::fnmatch};
+
+const README: &str = "# pytest cache directory #
+
+This directory contains data from the pytest's cache plugin,
+which provides the `--lf` and `--ff` options, as well as the `cache` fixture.
+
+**Do not** commit this to version control.
+
+See [the docs](https://docs.pytest.org/en/stable/how-to/cache.html) for more information.
+";
+
+const CACHEDIR_TAG: &str = "Signature: 8a477f597d28d172789f06886806bc55
+# This file is a cache directory tag created by pytest.
+# For information about cache directory tags, see:
+#\thttps://bford.info/cachedir/spec.html
+";
+
+/// Test results from previous runs, read from & written to `<rootdir>/.pytest_cache`.
+///
+/// Usage:
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pt::{Cache, TestStatus};
+/// let rootdir = Path::new(".");
+/// let mut cache = Cache::load(rootdir);
+/// let nodeid = Cache::nodeid(rootdir, Path::new("test_it.py"), "test_passes");
+/// cache.update(nodeid, &TestStatus::Pass);
+/// cache.save().unwrap();
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+    /// node ids of tests which failed when last run
+    pub lastfailed: BTreeMap<String, bool>,
+    /// node ids of all tests which have been collected
+    pub nodeids: BTreeSet<String>,
+}
+
+impl Cache {
+    /// Load the cache for `rootdir`. Missing or invalid cache files are treated as empty.
+    pub fn load(rootdir: &Path) -> Self {
+        let dir = rootdir.join(".pytest_cache");
+        let read = |key: &str| fs::read_to_string(dir.join("v").join(key)).ok();
+        Self {
+            lastfailed: read("cache/lastfailed")
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            nodeids: read("cache/nodeids")
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            dir,
+        }
+    }
+
+    /// pytest node id for a test: path relative to `rootdir`, using `/` as separator, and test name.
+    pub fn nodeid(rootdir: &Path, path: &Path, testname: &str) -> String {
+        let relative = match (rootdir.canonicalize(), path.canonicalize()) {
+            (Ok(rootdir), Ok(path)) => path
+                .strip_prefix(rootdir)
+                .map(Path::to_path_buf)
+                .unwrap_or(path),
+            _ => path.to_path_buf(),
+        };
+        let relative: Vec<_> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        format!("{}::{testname}", relative.join("/"))
+    }
+
+    /// Record the result of a test. Tests which did not run do not affect the last failures, as in
+    /// pytest skipped, xfailed and xpassed tests are not failures.
+    pub fn update(&mut self, nodeid: String, status: &TestStatus) {
+        match status {
+            TestStatus::Pass
+            | TestStatus::Skip(_)
+            | TestStatus::XFail(_)
+            | TestStatus::XPass(_) => {
+                self.lastfailed.remove(&nodeid);
+            }
+            TestStatus::Fail(_, _) => {
+                self.lastfailed.insert(nodeid.clone(), true);
+            }
+            TestStatus::NoRun | TestStatus::Running | TestStatus::Interrupted(_) => (),
+        }
+        self.nodeids.insert(nodeid);
+    }
+
+    /// Did the test fail when last run?
+    pub fn failed(&self, nodeid: &str) -> bool {
+        self.lastfailed.contains_key(nodeid)
+    }
+
+    /// Is this a test which has not been collected before?
+    pub fn is_new(&self, nodeid: &str) -> bool {
+        !self.nodeids.contains(nodeid)
+    }
+
+    /// Remove all cached values, and the cache directory.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.lastfailed.clear();
+        self.nodeids.clear();
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the cache to disk, creating the cache directory if required.
+    pub fn save(&self) -> io::Result<()> {
+        let values = self.dir.join("v").join("cache");
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir)?;
+            fs::write(self.dir.join("README.md"), README)?;
+            fs::write(
+                self.dir.join(".gitignore"),
+                "# Created by pytest automatically.\n*\n",
+            )?;
+            fs::write(self.dir.join("CACHEDIR.TAG"), CACHEDIR_TAG)?;
+        }
+        fs::create_dir_all(&values)?;
+        fs::write(values.join("lastfailed"), to_json(&self.lastfailed))?;
+        fs::write(values.join("nodeids"), to_json(&self.nodeids))?;
+        Ok(())
+    }
+
+    /// Contents of the cache, for `--cache-show`, filtered to keys matching `glob`.
+    pub fn show(&self, glob: &str) -> String {
+        let mut report = format!("cachedir: {}\n", self.dir.display());
+        report.push_str(&format!(
+            "--------------------- cache values for '{glob}' ---------------------\n"
+        ));
+        let values = [
+            ("cache/lastfailed", to_json(&self.lastfailed)),
+            ("cache/nodeids", to_json(&self.nodeids)),
+        ];
+        for (key, json) in values {
+            if fnmatch(glob, key) {
+                report.push_str(&format!("{key} contains:\n"));
+                for line in json.lines() {
+                    report.push_str(&format!("  {line}\n"));
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Same format as pytest: sorted keys, 2 space indentation
+fn to_json(value: &impl serde::Serialize) -> String {
+    serde_json::to_string_pretty(value).expect("cache values are always valid json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Exception, Traceback};
+
+    #[test]
+    fn update() {
+        let mut cache = Cache::default();
+        let exception = Exception {
+            name: "AssertionError".to_string(),
+            message: String::new(),
+        };
+        let failed = TestStatus::Fail(exception, Traceback::default());
+        cache.update("test_it.py::test_one".to_string(), &failed);
+        cache.update("test_it.py::test_two".to_string(), &failed);
+        cache.update("test_it.py::test_three".to_string(), &TestStatus::NoRun);
+        cache.update("test_it.py::test_two".to_string(), &TestStatus::Pass);
+        assert!(cache.failed("test_it.py::test_one"));
+        assert!(!cache.failed("test_it.py::test_two"));
+        assert!(!cache.is_new("test_it.py::test_three"));
+        assert_eq!(
+            "{\n  \"test_it.py::test_one\": true\n}",
+            to_json(&cache.lastfailed)
+        );
+    }
+
+    #[test]
+    fn nodeid() {
+        let rootdir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let path = rootdir.join("tests/fixtures/basic/src.py");
+        assert_eq!(
+            "tests/fixtures/basic/src.py::test_fails",
+            Cache::nodeid(rootdir, &path, "test_fails")
+        );
+    }
+}
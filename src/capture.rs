@@ -0,0 +1,203 @@
+//! Capturing output written by tests, as pytest's `--capture` and `capsys`, `capfd` & `caplog`
+
+use crate::{Capture, fixtures::push_definition, multiline::MultilineMut};
+
+/// Replaces `sys.stdout` & `sys.stderr`, and with `fd` the file descriptors 1 & 2, while started.
+/// Also the value of the `capsys` and `capfd` fixtures.
+const CAPTURE: &str = r#"_PtCaptureResult = _pt_collections.namedtuple("CaptureResult", ["out", "err"])
+
+
+class _PtTee(_pt_io.StringIO):
+    def __init__(self, stream):
+        super().__init__()
+        self._stream = stream
+
+    def write(self, text):
+        self._stream.write(text)
+        return super().write(text)
+
+
+class _PtCapture:
+    def __init__(self, method):
+        self._method = method
+
+    def start(self):
+        if self._method == "fd":
+            self._files = [_pt_tempfile.TemporaryFile(buffering=0) for _ in range(2)]
+            self._streams = [
+                _pt_io.TextIOWrapper(
+                    _pt_io.FileIO(fd, "w", closefd=False),
+                    encoding="utf-8",
+                    errors="replace",
+                    write_through=True,
+                )
+                for fd in (1, 2)
+            ]
+        elif self._method == "tee-sys":
+            self._streams = [_PtTee(sys.stdout), _PtTee(sys.stderr)]
+        else:
+            self._streams = [_pt_io.StringIO(), _pt_io.StringIO()]
+        self._resume()
+
+    def _resume(self):
+        sys.stdout.flush()
+        sys.stderr.flush()
+        self._saved = (sys.stdout, sys.stderr)
+        if self._method == "fd":
+            self._saved_fds = [_pt_os.dup(1), _pt_os.dup(2)]
+            for fd, file in zip((1, 2), self._files):
+                _pt_os.dup2(file.fileno(), fd)
+        sys.stdout, sys.stderr = self._streams
+
+    def _suspend(self):
+        for stream in self._streams:
+            stream.flush()
+        sys.stdout, sys.stderr = self._saved
+        if self._method == "fd":
+            for fd, saved in zip((1, 2), self._saved_fds):
+                _pt_os.dup2(saved, fd)
+                _pt_os.close(saved)
+
+    def readouterr(self):
+        captured = []
+        if self._method == "fd":
+            for stream, file in zip(self._streams, self._files):
+                stream.flush()
+                file.seek(0)
+                captured.append(file.read().decode("utf-8", "replace"))
+                file.seek(0)
+                file.truncate()
+        else:
+            for stream in self._streams:
+                captured.append(stream.getvalue())
+                stream.seek(0)
+                stream.truncate()
+        return _PtCaptureResult(*captured)
+
+    @_pt_contextlib.contextmanager
+    def disabled(self):
+        self._suspend()
+        try:
+            yield
+        finally:
+            self._resume()
+
+    def stop(self):
+        captured = self.readouterr()
+        self._suspend()
+        if self._method == "fd":
+            for file in self._files:
+                file.close()
+        return captured
+
+    def close(self):
+        # output the test did not read is passed on, to be reported if the test fails
+        out, err = self.stop()
+        sys.stdout.write(out)
+        sys.stderr.write(err)
+
+
+def _pt_print_captured(prefix, captured):
+    for section, text in zip(("stdout", "stderr"), captured):
+        if text:
+            print(prefix, "CAPTURED", section)
+            print(text, end="" if text.endswith("\n") else "\n")"#;
+
+/// Records log messages while a test runs, the value of the `caplog` fixture.
+const LOG_CAPTURE: &str = r#"class _PtLogCaptureHandler(_pt_logging.Handler):
+    def __init__(self):
+        super().__init__()
+        self.setFormatter(
+            _pt_logging.Formatter("%(levelname)-8s %(name)s:%(filename)s:%(lineno)d %(message)s")
+        )
+        self.reset()
+
+    def emit(self, record):
+        self.records.append(record)
+        self.stream.write(self.format(record) + "\n")
+
+    def reset(self):
+        self.records = []
+        self.stream = _pt_io.StringIO()
+
+
+class _PtLogCaptureFixture:
+    def __init__(self):
+        self.handler = _PtLogCaptureHandler()
+        self._initial_levels = {}
+        _pt_logging.getLogger().addHandler(self.handler)
+
+    @property
+    def records(self):
+        return self.handler.records
+
+    @property
+    def text(self):
+        return self.handler.stream.getvalue()
+
+    @property
+    def record_tuples(self):
+        return [(record.name, record.levelno, record.getMessage()) for record in self.records]
+
+    @property
+    def messages(self):
+        return [record.getMessage() for record in self.records]
+
+    def clear(self):
+        self.handler.reset()
+
+    def set_level(self, level, logger=None):
+        logger_obj = _pt_logging.getLogger(logger)
+        self._initial_levels.setdefault(logger, logger_obj.level)
+        logger_obj.setLevel(level)
+        self.handler.setLevel(level)
+
+    @_pt_contextlib.contextmanager
+    def at_level(self, level, logger=None):
+        logger_obj = _pt_logging.getLogger(logger)
+        logger_level, handler_level = logger_obj.level, self.handler.level
+        logger_obj.setLevel(level)
+        self.handler.setLevel(level)
+        try:
+            yield
+        finally:
+            logger_obj.setLevel(logger_level)
+            self.handler.setLevel(handler_level)
+
+    def close(self):
+        _pt_logging.getLogger().removeHandler(self.handler)
+        for logger, level in self._initial_levels.items():
+            _pt_logging.getLogger(logger).setLevel(level)"#;
+
+impl Capture {
+    /// The python `_PtCapture` method for each test, `None` if output is not captured.
+    pub(crate) fn method(&self) -> Option<&'static str> {
+        match self {
+            Self::Fd => Some("fd"),
+            Self::Sys => Some("sys"),
+            Self::TeeSys => Some("tee-sys"),
+            Self::No => None,
+        }
+    }
+}
+
+/// Push the definitions needed to capture output, once before any tests.
+pub(crate) fn push_capture_definitions(runner: &mut String, indent: usize) {
+    runner.push_python_line(
+        indent,
+        [
+            "import collections as _pt_collections, contextlib as _pt_contextlib, io as _pt_io, \
+             os as _pt_os, tempfile as _pt_tempfile",
+        ],
+    );
+    push_definition(runner, indent, CAPTURE);
+}
+
+/// Push the definitions needed by `caplog`, once before any tests.
+pub(crate) fn push_log_capture_definitions(runner: &mut String, indent: usize) {
+    runner.push_python_line(
+        indent,
+        ["import contextlib as _pt_contextlib, io as _pt_io, logging as _pt_logging"],
+    );
+    push_definition(runner, indent, LOG_CAPTURE);
+}
@@ -0,0 +1,345 @@
+//! Errors collecting the tests in a module
+
+use std::fmt;
+
+use ruff_python_ast::{
+    Expr, Stmt, StmtFunctionDef,
+    visitor::{Visitor, walk_expr, walk_stmt},
+};
+use ruff_python_parser::ParseError;
+
+use crate::{Error, Exception, Traceback, multiline::MultilineMut, pytests::mark};
+
+/// A test module whose tests could not be collected, reported as `ERROR collecting <path>`.
+///
+/// Usage:
+/// ```rust
+/// use pt::{CollectionError, Traceback};
+/// let stderr = "Traceback (most recent call last):
+///   File \"/tests/test_it.py\", line 1, in <module>
+///     import nosuch
+/// ModuleNotFoundError: No module named 'nosuch'
+/// ";
+/// let error = CollectionError::Import(Traceback::from(stderr));
+/// assert_eq!(
+///     "ERROR tests/test_it.py - ModuleNotFoundError: No module named 'nosuch'\n",
+///     error.short_summary("tests/test_it.py")
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub enum CollectionError {
+    /// The module is not valid python
+    Syntax {
+        /// the invalid line, numbered, with carets under the error; see `Error::diagnostic()`
+        source: String,
+        message: String,
+    },
+    /// An exception was raised importing the module, with the traceback python printed
+    Import(Traceback),
+    /// `--strict` is set and collecting the module raised warnings
+    Warnings(Vec<CollectionWarning>),
+    /// A `@pytest.mark.parametrize` or fixture `params` is invalid, or cannot be expanded by pt
+    Parametrize(String),
+}
+
+impl CollectionError {
+    /// A syntax error in `src`.
+    pub fn syntax(src: &str, err: ParseError) -> Self {
+        let message = err.error.to_string();
+        let diagnostic = Error::InvalidPython(err).diagnostic(src);
+        // the first line locates the error, as the report's title does
+        let source = diagnostic.split_once('\n').map_or("", |(_, source)| source);
+        Self::Syntax {
+            source: source.to_string(),
+            message,
+        }
+    }
+
+    /// The exception which prevented collection.
+    pub fn exception(&self) -> Exception {
+        match self {
+            Self::Syntax { message, .. } => Exception {
+                name: "SyntaxError".to_string(),
+                message: message.clone(),
+            },
+            Self::Import(traceback) => Exception::try_from(traceback.as_text()).unwrap_or_default(),
+            Self::Warnings(warnings) => Exception {
+                name: CollectionWarning::NAME.to_string(),
+                message: warnings
+                    .first()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            },
+            // as pytest, which reports these with `pytest.fail()`
+            Self::Parametrize(message) => Exception {
+                name: "Failed".to_string(),
+                message: message.clone(),
+            },
+        }
+    }
+
+    /// `ERROR collecting` section for the module at `location`: the failing source line for a
+    /// syntax error, or each frame outside python's import machinery for an import error.
+    pub fn report(&self, location: &str) -> String {
+        let mut report = String::new();
+        report.push_line(0, ["==== ERROR collecting ", location, " ===="]);
+        match self {
+            Self::Syntax { source, message } => {
+                report.push_str(source);
+                report.push_line(0, ["SyntaxError: ", message.as_str()]);
+            }
+            Self::Import(traceback) => match traceback
+                .chain()
+                .ok()
+                .and_then(|chain| chain.into_iter().last())
+            {
+                Some(raised) => {
+                    // `<string>` is the launcher, `<frozen importlib...>` the import system
+                    for frame in raised
+                        .frames
+                        .iter()
+                        .filter(|frame| !frame.header.file_name.starts_with('<'))
+                    {
+                        let line_number = frame.header.line_number.to_string();
+                        report.push_line(
+                            0,
+                            [
+                                frame.header.file_name,
+                                ":",
+                                &line_number,
+                                ": in ",
+                                frame.header.function_name,
+                            ],
+                        );
+                        if let Some(source) = frame.source {
+                            report.push_line(0, [source]);
+                        }
+                    }
+                    raised
+                        .exception
+                        .iter()
+                        .for_each(|line| report.push_line(0, [*line]));
+                }
+                // e.g. a `SyntaxError` only python reports, which has no ordinary frames
+                None => traceback
+                    .as_text()
+                    .lines()
+                    .filter(|line| !line.starts_with("  File \"<"))
+                    .for_each(|line| report.push_line(0, [line])),
+            },
+            Self::Warnings(warnings) => warnings
+                .iter()
+                .for_each(|warning| report.push_str(&warning.report(location))),
+            Self::Parametrize(message) => report.push_line(0, [message.as_str()]),
+        }
+        report
+    }
+
+    /// Line for the short test summary: `ERROR <location> - <exception>`.
+    pub fn short_summary(&self, location: &str) -> String {
+        let mut summary = String::new();
+        summary.push_line(0, ["ERROR ", location, " - ", &self.exception().summary()]);
+        summary
+    }
+}
+
+/// A likely mistake in a test module, which means a test is not run as it appears it would be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollectionWarning {
+    /// `name` is defined again at `line`, so the definition at `first_line` never runs
+    Redefined {
+        name: String,
+        first_line: usize,
+        line: usize,
+    },
+    /// `name` is replaced by an assignment, import or class at `line`, so is not collected
+    Shadowed { name: String, line: usize },
+    /// `name` is defined inside another function, at `line`, so is never collected
+    Nested { name: String, line: usize },
+    /// `pytest.mark.<name>`, at `line`, is not registered in the `markers` ini option
+    UnknownMark { name: String, line: usize },
+    /// fixture `name` is given `scope` at `line`, which pt cannot keep its value for
+    IgnoredScope {
+        name: String,
+        scope: String,
+        line: usize,
+    },
+}
+
+impl CollectionWarning {
+    /// Category shown in reports, as python's warning class names.
+    pub const NAME: &str = "PtCollectionWarning";
+
+    /// Line of the module at which the mistake was made.
+    pub fn line(&self) -> usize {
+        match self {
+            Self::Redefined { line, .. }
+            | Self::Shadowed { line, .. }
+            | Self::Nested { line, .. }
+            | Self::UnknownMark { line, .. }
+            | Self::IgnoredScope { line, .. } => *line,
+        }
+    }
+
+    /// `location:line: PtCollectionWarning: message`, for the warnings summary.
+    pub fn report(&self, location: &str) -> String {
+        let mut report = String::new();
+        let line = self.line().to_string();
+        let message = self.to_string();
+        report.push_line(0, [location, ":", &line, ": ", Self::NAME, ": ", &message]);
+        report
+    }
+}
+
+impl fmt::Display for CollectionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Redefined {
+                name, first_line, ..
+            } => write!(
+                f,
+                "{name} is redefined, the test defined at line {first_line} will not run"
+            ),
+            Self::Shadowed { name, .. } => {
+                write!(
+                    f,
+                    "test {name} is replaced by a later assignment or import and will not run"
+                )
+            }
+            Self::Nested { name, .. } => write!(
+                f,
+                "{name} is defined inside another function and will not be collected"
+            ),
+            Self::UnknownMark { name, .. } => write!(
+                f,
+                "Unknown pytest.mark.{name} - is this a typo?  You can register custom marks to \
+                 avoid this warning - for details, see https://docs.pytest.org/en/stable/how-to/mark.html"
+            ),
+            Self::IgnoredScope { name, scope, .. } if scope == "dynamic" => write!(
+                f,
+                "fixture {name} has scope '{scope}', which pt ignores: it is set up again for each \
+                 test which uses it"
+            ),
+            Self::IgnoredScope { name, scope, .. } => write!(
+                f,
+                "fixture {name} has scope '{scope}', which pt treats as 'module': it is set up again \
+                 for each test module"
+            ),
+        }
+    }
+}
+
+/// Names bound by `stmt`, other than by a `def`, which would replace a test of the same name.
+pub(crate) fn bound_names(stmt: &Stmt) -> Vec<&str> {
+    match stmt {
+        Stmt::Assign(assign) => assign.targets.iter().flat_map(target_names).collect(),
+        Stmt::AnnAssign(assign) => target_names(&assign.target),
+        Stmt::Import(import) => import
+            .names
+            .iter()
+            .map(|alias| match &alias.asname {
+                Some(asname) => asname.as_str(),
+                // `import a.b` binds `a`
+                None => alias.name.split('.').next().unwrap_or_default(),
+            })
+            .collect(),
+        Stmt::ImportFrom(import) => import
+            .names
+            .iter()
+            .map(|alias| alias.asname.as_ref().unwrap_or(&alias.name).as_str())
+            .collect(),
+        Stmt::ClassDef(class) => vec![class.name.as_str()],
+        _ => Vec::new(),
+    }
+}
+
+fn target_names(target: &Expr) -> Vec<&str> {
+    match target {
+        Expr::Name(name) => vec![name.id.as_str()],
+        Expr::Tuple(tuple) => tuple.elts.iter().flat_map(target_names).collect(),
+        Expr::List(list) => list.elts.iter().flat_map(target_names).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Finds functions, at any depth, with names which make them look like tests
+pub(crate) struct NestedTests<'a, IsTest: Fn(&str) -> bool> {
+    pub(crate) is_test: IsTest,
+    pub(crate) found: Vec<&'a StmtFunctionDef>,
+}
+
+impl<'a, IsTest: Fn(&str) -> bool> Visitor<'a> for NestedTests<'a, IsTest> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        if let Stmt::FunctionDef(function) = stmt
+            && (self.is_test)(function.name.as_str())
+        {
+            self.found.push(function);
+        }
+        walk_stmt(self, stmt);
+    }
+}
+
+/// Finds `pytest.mark.<name>` expressions, anywhere in the module, for marks which are not
+/// registered
+pub(crate) struct UnknownMarks<'a, IsRegistered: Fn(&str) -> bool> {
+    pub(crate) is_registered: IsRegistered,
+    /// `mark` is bound at module level, e.g. by `from pytest import mark`, so `mark.<name>` is a
+    /// mark rather than an attribute of a local `mark`
+    pub(crate) bare_mark: bool,
+    pub(crate) found: Vec<(&'a str, &'a Expr)>,
+}
+
+impl<'a, IsRegistered: Fn(&str) -> bool> Visitor<'a> for UnknownMarks<'a, IsRegistered> {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        // a call to a mark is visited again as its `func`
+        if let Expr::Attribute(attribute) = expr
+            && (self.bare_mark || !matches!(&*attribute.value, Expr::Name(_)))
+            && let Some((name, _)) = mark(expr)
+            && !(self.is_registered)(name)
+        {
+            self.found.push((name, expr));
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_error_report() {
+        let stderr = r#"Traceback (most recent call last):
+  File "<string>", line 8, in <module>
+  File "<frozen importlib._bootstrap_external>", line 940, in exec_module
+  File "/tests/test_it.py", line 1, in <module>
+    import nosuch
+ModuleNotFoundError: No module named 'nosuch'
+"#;
+        let error = CollectionError::Import(Traceback::from(stderr));
+        let expected = r#"==== ERROR collecting tests/test_it.py ====
+/tests/test_it.py:1: in <module>
+    import nosuch
+ModuleNotFoundError: No module named 'nosuch'
+"#;
+        assert_eq!(expected, error.report("tests/test_it.py"));
+    }
+
+    #[test]
+    fn syntax_error_report() {
+        let error = CollectionError::Syntax {
+            source: "12: def test_it(\n        ^\n".to_string(),
+            message: "Expected ')', found newline".to_string(),
+        };
+        let expected = r#"==== ERROR collecting test_it.py ====
+12: def test_it(
+        ^
+SyntaxError: Expected ')', found newline
+"#;
+        assert_eq!(expected, error.report("test_it.py"));
+        assert_eq!(
+            "ERROR test_it.py - SyntaxError: Expected ')', found newline\n",
+            error.short_summary("test_it.py")
+        );
+    }
+}
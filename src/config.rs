@@ -0,0 +1,547 @@
+//! Locating and reading pytest configuration (`pytest.ini`, `pyproject.toml`, `tox.ini`, `setup.cfg`)
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Error,
+    multiline::{Location, Multiline},
+};
+
+/// pytest configuration, located in the same way as pytest does.
+///
+/// Construct with `Config::discover(args, invocation_dir)` or use `Config::default()` for pytest's
+/// defaults with the current directory as rootdir.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub rootdir: PathBuf,
+    pub configfile: Option<PathBuf>,
+    pub testpaths: Vec<String>,
+    pub python_files: Vec<String>,
+    pub python_classes: Vec<String>,
+    pub python_functions: Vec<String>,
+    pub addopts: Vec<String>,
+    /// registered marks, `name: description` or just `name`
+    pub markers: Vec<String>,
+    pub norecursedirs: Vec<String>,
+    pub filterwarnings: Vec<String>,
+    /// relative to `rootdir`
+    pub pythonpath: Vec<PathBuf>,
+    pub xfail_strict: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let strings = |strs: &[&str]| strs.iter().map(|s| s.to_string()).collect();
+        Self {
+            rootdir: PathBuf::from("."),
+            configfile: None,
+            testpaths: Vec::new(),
+            python_files: strings(&["test_*.py", "*_test.py"]),
+            python_classes: strings(&["Test"]),
+            python_functions: strings(&["test"]),
+            addopts: Vec::new(),
+            markers: Vec::new(),
+            norecursedirs: strings(&[
+                "*.egg",
+                ".*",
+                "_darcs",
+                "build",
+                "CVS",
+                "dist",
+                "node_modules",
+                "venv",
+                "{arch}",
+            ]),
+            filterwarnings: Vec::new(),
+            pythonpath: Vec::new(),
+            xfail_strict: false,
+        }
+    }
+}
+
+/// A single `key = value` pair from a config file, before interpretation.
+enum RawValue {
+    /// ini-style files only provide strings
+    Ini(String),
+    Toml(toml::Value),
+}
+
+impl Config {
+    /// Locate rootdir & configfile based upon the paths given on the command line, falling back to
+    /// `invocation_dir` if none of the paths exist.
+    pub fn discover(args: &[PathBuf], invocation_dir: &Path) -> Result<Self, Error> {
+        let ancestor = common_ancestor(args, invocation_dir);
+
+        for dir in ancestor.ancestors() {
+            for candidate in [
+                "pytest.ini",
+                ".pytest.ini",
+                "pyproject.toml",
+                "tox.ini",
+                "setup.cfg",
+            ] {
+                let path = dir.join(candidate);
+                if let Some(config) = Self::load(&path)? {
+                    return Ok(config);
+                }
+            }
+        }
+
+        let rootdir = ancestor
+            .ancestors()
+            .find(|dir| dir.join("setup.py").is_file() || dir.join("pyproject.toml").is_file())
+            .unwrap_or(&ancestor)
+            .to_path_buf();
+        Ok(Self {
+            rootdir,
+            ..Default::default()
+        })
+    }
+
+    /// Load configuration from `path`, if it contains a pytest section.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let Ok(text) = fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let values: Option<Vec<(String, RawValue)>> = match filename {
+            "pyproject.toml" => {
+                let mut table: toml::Table = toml::from_str(&text).map_err(|err| {
+                    let line = err
+                        .span()
+                        .map(|span| text.as_str().line_no(&Location::Offset(span.start)));
+                    Error::InvalidConfig(err.message().to_string()).in_file(path, line)
+                })?;
+                let ini_options = table
+                    .remove("tool")
+                    .and_then(|mut tool| tool.as_table_mut()?.remove("pytest"))
+                    .and_then(|mut pytest| pytest.as_table_mut()?.remove("ini_options"));
+                match ini_options {
+                    Some(toml::Value::Table(options)) => Some(
+                        options
+                            .into_iter()
+                            .map(|(key, value)| (key, RawValue::Toml(value)))
+                            .collect(),
+                    ),
+                    Some(_) => {
+                        return Err(Error::InvalidConfig(
+                            "[tool.pytest.ini_options] must be a table".to_string(),
+                        )
+                        .in_file(path, None));
+                    }
+                    None => None,
+                }
+            }
+            "setup.cfg" => ini_section(&text, "tool:pytest"),
+            "tox.ini" => ini_section(&text, "pytest"),
+            // pytest.ini is always used, even without a [pytest] section
+            _ => Some(ini_section(&text, "pytest").unwrap_or_default()),
+        };
+        let Some(values) = values else {
+            return Ok(None);
+        };
+
+        let mut config = Self {
+            rootdir: path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            configfile: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        for (key, value) in values {
+            config.set(&key, value);
+        }
+        Ok(Some(config))
+    }
+
+    fn set(&mut self, key: &str, value: RawValue) {
+        match key {
+            "testpaths" => self.testpaths = value.args(),
+            "python_files" => self.python_files = value.args(),
+            "python_classes" => self.python_classes = value.args(),
+            "python_functions" => self.python_functions = value.args(),
+            "addopts" => self.addopts = value.args(),
+            "markers" => self.markers = value.linelist(),
+            "norecursedirs" => self.norecursedirs = value.args(),
+            "filterwarnings" => self.filterwarnings = value.linelist(),
+            "pythonpath" => self.pythonpath = value.args().into_iter().map(PathBuf::from).collect(),
+            "xfail_strict" => self.xfail_strict = value.bool(),
+            _ => (),
+        }
+    }
+
+    /// Is `name` one of pytest's own marks, or registered in the `markers` ini option?
+    pub fn is_registered_marker(&self, name: &str) -> bool {
+        const BUILTIN: [&str; 8] = [
+            "filterwarnings",
+            "parametrize",
+            "skip",
+            "skipif",
+            "tryfirst",
+            "trylast",
+            "usefixtures",
+            "xfail",
+        ];
+        BUILTIN.contains(&name)
+            || self.markers.iter().any(|marker| {
+                marker
+                    .split([':', '('])
+                    .next()
+                    .is_some_and(|registered| registered.trim() == name)
+            })
+    }
+
+    /// Does `name` match one of the `python_functions` patterns?
+    pub fn is_test_function(&self, name: &str) -> bool {
+        self.python_functions
+            .iter()
+            .any(|pattern| name_matches(pattern, name))
+    }
+
+    /// Does `name` match one of the `python_classes` patterns?
+    pub fn is_test_class(&self, name: &str) -> bool {
+        self.python_classes
+            .iter()
+            .any(|pattern| name_matches(pattern, name))
+    }
+
+    /// Does the file at `path` match one of the `python_files` patterns?
+    pub fn is_test_file(&self, path: &Path) -> bool {
+        let Some(filename) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        self.python_files.iter().any(|pattern| {
+            if pattern.contains('/') {
+                path.to_str().is_some_and(|path| fnmatch(pattern, path))
+            } else {
+                fnmatch(pattern, filename)
+            }
+        })
+    }
+
+    /// Collect all test files from `paths`, or from `testpaths` if no paths are given. Files given
+    /// explicitly are always collected, directories are searched recursively for `python_files`,
+    /// skipping `norecursedirs`.
+    pub fn collect_files(&self, paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+        let paths: Vec<PathBuf> = if paths.is_empty() {
+            self.testpaths
+                .iter()
+                .map(|testpath| self.rootdir.join(testpath))
+                .collect()
+        } else {
+            paths.to_vec()
+        };
+
+        let mut files = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                self.collect_dir(&path, &mut files)?;
+            } else {
+                // Let the caller report missing files
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    /// The `conftest.py` files applying to the test file at `path`: those in `rootdir` and each
+    /// directory down to the file's own, outermost first.
+    pub fn conftests(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(dir) = path.parent().and_then(|dir| dir.canonicalize().ok()) else {
+            return Vec::new();
+        };
+        let rootdir = self.rootdir.canonicalize().unwrap_or(self.rootdir.clone());
+        let mut conftests = Vec::new();
+        for dir in dir.ancestors() {
+            let conftest = dir.join("conftest.py");
+            if conftest.is_file() {
+                conftests.push(conftest);
+            }
+            // a file outside rootdir only uses the conftest next to it
+            if dir == rootdir || !dir.starts_with(&rootdir) {
+                break;
+            }
+        }
+        conftests.reverse();
+        conftests
+    }
+
+    fn collect_dir(&self, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                let dirname = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default();
+                if !self
+                    .norecursedirs
+                    .iter()
+                    .any(|pattern| fnmatch(pattern, dirname))
+                {
+                    self.collect_dir(&path, files)?;
+                }
+            } else if self.is_test_file(&path) {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RawValue {
+    /// Whitespace separated (ini, with shell quoting) or a list (toml)
+    fn args(self) -> Vec<String> {
+        match self {
+            Self::Ini(text) => split_args(&text),
+            Self::Toml(toml::Value::Array(values)) => values.into_iter().map(toml_string).collect(),
+            Self::Toml(toml::Value::String(text)) => split_args(&text),
+            Self::Toml(other) => vec![toml_string(other)],
+        }
+    }
+
+    /// One entry per non-empty line (ini) or a list (toml)
+    fn linelist(self) -> Vec<String> {
+        match self {
+            Self::Ini(text) => text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Self::Toml(toml::Value::Array(values)) => values.into_iter().map(toml_string).collect(),
+            Self::Toml(other) => vec![toml_string(other)],
+        }
+    }
+
+    fn bool(self) -> bool {
+        match self {
+            Self::Toml(toml::Value::Boolean(value)) => value,
+            Self::Ini(text) | Self::Toml(toml::Value::String(text)) => {
+                matches!(
+                    text.trim().to_lowercase().as_str(),
+                    "true" | "1" | "yes" | "on"
+                )
+            }
+            Self::Toml(_) => false,
+        }
+    }
+}
+
+fn toml_string(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+/// Deepest directory containing all existing `args`, or `invocation_dir` if none exist.
+fn common_ancestor(args: &[PathBuf], invocation_dir: &Path) -> PathBuf {
+    let mut existing = args
+        .iter()
+        .map(|arg| invocation_dir.join(arg))
+        .filter(|path| path.exists())
+        .map(|path| {
+            if path.is_dir() {
+                path
+            } else {
+                path.parent().unwrap_or(invocation_dir).to_path_buf()
+            }
+        });
+    let Some(first) = existing.next() else {
+        return invocation_dir.to_path_buf();
+    };
+    existing.fold(first, |ancestor, path| {
+        ancestor
+            .ancestors()
+            .find(|candidate| path.starts_with(candidate))
+            .unwrap_or(Path::new("/"))
+            .to_path_buf()
+    })
+}
+
+/// Key-value pairs from `[section]` of an ini-style file, or `None` if the section is not present.
+/// Indented lines continue the previous value.
+fn ini_section(text: &str, section: &str) -> Option<Vec<(String, RawValue)>> {
+    let mut values: Option<Vec<(String, String)>> = None;
+    let mut in_section = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            in_section = header.trim() == section;
+            if in_section {
+                values.get_or_insert_default();
+            }
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let values = values.get_or_insert_default();
+        if line.starts_with(char::is_whitespace)
+            && let Some((_, value)) = values.last_mut()
+        {
+            value.push('\n');
+            value.push_str(trimmed);
+        } else if let Some((key, value)) = trimmed.split_once(['=', ':']) {
+            values.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    values.map(|values| {
+        values
+            .into_iter()
+            .map(|(key, value)| (key, RawValue::Ini(value)))
+            .collect()
+    })
+}
+
+/// Split a string into arguments, following (simplified) shell quoting rules.
+pub(crate) fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for char in text.chars() {
+        match (quote, char) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_default().push(c),
+            (None, '"' | '\'') => {
+                quote = Some(char);
+                current.get_or_insert_default();
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_default().push(c),
+        }
+    }
+    args.extend(current);
+    args
+}
+
+/// pytest treats `python_functions` and `python_classes` as prefixes unless they contain glob
+/// characters.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    if pattern.contains(['*', '?', '[']) {
+        fnmatch(pattern, name)
+    } else {
+        name.starts_with(pattern)
+    }
+}
+
+/// Unix shell-style wildcard matching, as python's `fnmatch`: `*`, `?` and `[seq]` / `[!seq]`.
+pub(crate) fn fnmatch(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    fnmatch_chars(&pattern, &name)
+}
+
+fn fnmatch_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| fnmatch_chars(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && fnmatch_chars(rest, &name[1..]),
+        Some(('[', rest)) if let Some(close) = rest.iter().skip(1).position(|c| *c == ']') => {
+            let Some((first, name_rest)) = name.split_first() else {
+                return false;
+            };
+            let (negate, set) = match &rest[..=close] {
+                ['!', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    matched |= (set[i]..=set[i + 2]).contains(first);
+                    i += 3;
+                } else {
+                    matched |= set[i] == *first;
+                    i += 1;
+                }
+            }
+            matched != negate && fnmatch_chars(&rest[close + 2..], name_rest)
+        }
+        Some((c, rest)) => name.first() == Some(c) && fnmatch_chars(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnmatch_patterns() {
+        assert!(fnmatch("test_*.py", "test_one.py"));
+        assert!(fnmatch("*_test.py", "one_test.py"));
+        assert!(!fnmatch("test_*.py", "one_test.py"));
+        assert!(fnmatch(".*", ".venv"));
+        assert!(fnmatch("{arch}", "{arch}"));
+        assert!(fnmatch("check_[a-c]?.py", "check_b1.py"));
+        assert!(!fnmatch("check_[!a-c]?.py", "check_b1.py"));
+    }
+
+    #[test]
+    fn ini_options() {
+        let ini = r"[metadata]
+name = other
+
+[tool:pytest]
+testpaths = tests integration
+python_functions = check_* test
+markers =
+    slow: marks tests as slow
+    network
+xfail_strict = true
+";
+        let values = ini_section(ini, "tool:pytest").unwrap();
+        let mut config = Config::default();
+        for (key, value) in values {
+            config.set(&key, value);
+        }
+        assert_eq!(vec!["tests", "integration"], config.testpaths);
+        assert_eq!(vec!["slow: marks tests as slow", "network"], config.markers);
+        assert!(config.xfail_strict);
+        assert!(config.is_registered_marker("slow"));
+        assert!(config.is_registered_marker("network"));
+        assert!(config.is_registered_marker("parametrize"));
+        assert!(!config.is_registered_marker("slwo"));
+        assert!(config.is_test_function("check_it"));
+        assert!(config.is_test_function("test_it"));
+        assert!(!config.is_test_function("helper"));
+        assert!(ini_section(ini, "pytest").is_none());
+    }
+
+    #[test]
+    fn conftests_from_rootdir() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let conftest = root.join("autouse/conftest.py").canonicalize().unwrap();
+        let config = Config {
+            rootdir: root.clone(),
+            ..Default::default()
+        };
+        let test_file = root.join("autouse/test_autouse.py");
+        assert_eq!(vec![conftest.clone()], config.conftests(&test_file));
+        let config = Config {
+            rootdir: root.join("basic"),
+            ..Default::default()
+        };
+        assert_eq!(vec![conftest], config.conftests(&test_file));
+        assert!(config.conftests(&root.join("basic/src.py")).is_empty());
+    }
+
+    #[test]
+    fn addopts_quoting() {
+        assert_eq!(
+            vec!["-ra", "-k", "not slow", "--tb=short"],
+            split_args(r#"-ra -k "not slow" --tb=short"#)
+        );
+    }
+}
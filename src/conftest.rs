@@ -0,0 +1,71 @@
+//! `conftest.py` files, whose fixtures are available to every test module in and below their
+//! directory
+
+use std::{fs, path::Path};
+
+use indexmap::IndexMap;
+use ruff_python_ast::Stmt;
+use ruff_python_parser::parse_module;
+
+use crate::{
+    CollectionWarning, Error, TestModule,
+    fixtures::{UserFixture, ignored_scope},
+    multiline::{Location, Multiline},
+};
+
+/// A `conftest.py` and the fixtures it defines. The conftests applying to a test module are found
+/// with `Config::conftests()`.
+///
+/// Usage:
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pt::{Config, Conftest, TestSuite};
+/// let conftest = Conftest::try_from(Path::new("tests/conftest.py")).unwrap();
+/// let src = String::from("def test_uses_fixture(db):\n    assert db\n");
+/// let tests = TestSuite::try_from((src, &Config::default(), &[conftest][..])).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conftest {
+    pub module: TestModule,
+    /// by the name tests request them with
+    pub(crate) fixtures: IndexMap<String, UserFixture>,
+    warnings: Vec<CollectionWarning>,
+}
+
+impl TryFrom<&Path> for Conftest {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let module = TestModule::try_from(path)?;
+        let src = fs::read_to_string(&module.path)
+            .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))?;
+        let mut fixtures = IndexMap::new();
+        let mut warnings = Vec::new();
+        for stmt in parse_module(&src)?.into_suite() {
+            if let Stmt::FunctionDef(function) = stmt
+                && let Some(fixture) = UserFixture::from_definition(&src, &function)?
+            {
+                if let Some((scope, offset)) = ignored_scope(&function) {
+                    warnings.push(CollectionWarning::IgnoredScope {
+                        name: fixture.name.clone(),
+                        scope,
+                        line: src.as_str().line_no(&Location::Offset(offset.into())),
+                    });
+                }
+                fixtures.insert(fixture.name.clone(), fixture);
+            }
+        }
+        Ok(Self {
+            module,
+            fixtures,
+            warnings,
+        })
+    }
+}
+
+impl Conftest {
+    /// Likely mistakes found while loading the conftest, e.g. a fixture scope pt ignores.
+    pub fn warnings(&self) -> &[CollectionWarning] {
+        &self.warnings
+    }
+}
@@ -0,0 +1,153 @@
+//! `-k` and `-m` selection expressions
+
+use crate::Error;
+
+/// A parsed `-k` or `-m` expression, following pytest's grammar:
+///
+/// ```text
+/// expr:     and_expr ('or' and_expr)*
+/// and_expr: not_expr ('and' not_expr)*
+/// not_expr: 'not' not_expr | '(' expr ')' | ident
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Expression {
+    Ident(String),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    /// An empty expression matches everything
+    Empty,
+}
+
+impl TryFrom<&str> for Expression {
+    type Error = Error;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        let tokens = tokenize(text);
+        if tokens.is_empty() {
+            return Ok(Self::Empty);
+        }
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let expression = parser.expr();
+        match (expression, parser.tokens.get(parser.position)) {
+            (Some(expression), None) => Ok(expression),
+            _ => Err(Error::InvalidArgument(format!(
+                "Wrong expression passed to '-k' or '-m': {text}"
+            ))),
+        }
+    }
+}
+
+impl Expression {
+    /// Evaluate the expression, using `matches` to determine whether an identifier is true.
+    pub(crate) fn evaluate(&self, matches: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Self::Ident(ident) => matches(ident),
+            Self::Not(expr) => !expr.evaluate(matches),
+            Self::And(left, right) => left.evaluate(matches) && right.evaluate(matches),
+            Self::Or(left, right) => left.evaluate(matches) || right.evaluate(matches),
+            Self::Empty => true,
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (idx, char) in text.char_indices() {
+        if char.is_whitespace() || char == '(' || char == ')' {
+            if let Some(start) = start.take() {
+                tokens.push(&text[start..idx]);
+            }
+            if !char.is_whitespace() {
+                tokens.push(&text[idx..idx + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(idx);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+struct Parser<'tokens> {
+    tokens: &'tokens [&'tokens str],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn accept(&mut self, token: &str) -> bool {
+        let accepted = self.tokens.get(self.position) == Some(&token);
+        if accepted {
+            self.position += 1;
+        }
+        accepted
+    }
+
+    fn expr(&mut self) -> Option<Expression> {
+        let mut expr = self.and_expr()?;
+        while self.accept("or") {
+            expr = Expression::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Some(expr)
+    }
+
+    fn and_expr(&mut self) -> Option<Expression> {
+        let mut expr = self.not_expr()?;
+        while self.accept("and") {
+            expr = Expression::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Some(expr)
+    }
+
+    fn not_expr(&mut self) -> Option<Expression> {
+        if self.accept("not") {
+            return Some(Expression::Not(Box::new(self.not_expr()?)));
+        }
+        if self.accept("(") {
+            let expr = self.expr()?;
+            return self.accept(")").then_some(expr);
+        }
+        let token = self.tokens.get(self.position)?;
+        if matches!(*token, "and" | "or" | ")") {
+            return None;
+        }
+        self.position += 1;
+        Some(Expression::Ident(token.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyword_expression() {
+        let expr = Expression::try_from("not slow and (fails or passes)").unwrap();
+        for (name, selected) in [
+            ("test_fails", true),
+            ("test_passes", true),
+            ("test_slow_fails", false),
+            ("test_seven_is_six", false),
+        ] {
+            assert_eq!(
+                selected,
+                expr.evaluate(&|ident| name.contains(ident)),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_expression() {
+        assert!(Expression::try_from("slow and").is_err());
+        assert!(Expression::try_from("(slow").is_err());
+        assert!(Expression::try_from("slow fast").is_err());
+        assert_eq!(Expression::Empty, Expression::try_from("  ").unwrap());
+    }
+}
@@ -1,9 +1,9 @@
 //! Parsing and storing the output from failed tests
 
 use base_traits::AsStr;
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use crate::Error;
+use crate::{Error, fixtures::push_definition};
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Traceback {
@@ -25,8 +25,212 @@ impl From<&str> for Traceback {
 }
 
 impl Traceback {
-    pub(crate) fn lines(&'_ self) -> impl Iterator<Item = Result<TracebackLine<'_>, Error>> {
-        self.text.lines().map(TracebackLine::try_from)
+    /// The traceback as printed by python.
+    pub fn as_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Parse into the chain of exceptions, in the order python prints them: the final exception
+    /// last.
+    pub(crate) fn chain(&'_ self) -> Result<Vec<TracebackException<'_>>, Error> {
+        parse_chain(self.text.lines())
+    }
+}
+
+/// Split on "During handling ..." & "The above exception ..." messages and parse each exception.
+fn parse_chain<'tb>(
+    lines: impl IntoIterator<Item = &'tb str>,
+) -> Result<Vec<TracebackException<'tb>>, Error> {
+    let mut chain = Vec::new();
+    let mut segment = Vec::new();
+    let mut cause = None;
+    for line in lines {
+        match ChainCause::try_from(line) {
+            Ok(next) => {
+                push_exceptions(&mut chain, &segment, cause)?;
+                segment.clear();
+                cause = Some(next);
+            }
+            Err(_) => segment.push(line),
+        }
+    }
+    push_exceptions(&mut chain, &segment, cause)?;
+    Ok(chain)
+}
+
+/// Parse a single exception (& traceback) from a chain. An `ExceptionGroup` may bring its own
+/// chain, so this extends `chain` rather than returning a single exception.
+fn push_exceptions<'tb>(
+    chain: &mut Vec<TracebackException<'tb>>,
+    segment: &[&'tb str],
+    cause: Option<ChainCause>,
+) -> Result<(), Error> {
+    // Chain messages are surrounded by blank lines
+    let start = segment.iter().position(|line| !line.is_empty());
+    let end = segment.iter().rposition(|line| !line.is_empty());
+    let (Some(start), Some(end)) = (start, end) else {
+        return Ok(());
+    };
+    let segment = &segment[start..=end];
+    let group_column = segment[0]
+        .trim_start()
+        .starts_with("+ Exception Group Traceback")
+        .then(|| segment[0].len() - segment[0].trim_start().len());
+    let exceptions = match group_column {
+        Some(column) => parse_group(segment, column)?,
+        None => vec![parse_exception(segment)?],
+    };
+    let first = chain.len();
+    chain.extend(exceptions);
+    if let Some(exception) = chain.get_mut(first) {
+        exception.cause = cause;
+    }
+    Ok(())
+}
+
+/// Parse the output for an `ExceptionGroup`, drawn with its own lines prefixed by `| ` at `column`
+/// and each sub-exception separated by `+---- n ----` and drawn 2 columns further right:
+///
+/// ```text
+///   + Exception Group Traceback (most recent call last):
+///   |   File "<string>", line 3, in <module>
+///   | ExceptionGroup: eg (2 sub-exceptions)
+///   +-+---------------- 1 ----------------
+///     | ValueError: 1
+///     +---------------- 2 ----------------
+///     | TypeError: 2
+///     +------------------------------------
+/// ```
+fn parse_group<'tb>(
+    lines: &[&'tb str],
+    column: usize,
+) -> Result<Vec<TracebackException<'tb>>, Error> {
+    let mut own_lines = Vec::new();
+    let mut members: Vec<Vec<&str>> = Vec::new();
+    let mut in_member = false;
+    for line in lines {
+        let own = line.get(column..).unwrap_or_default();
+        let member = line.get(column + 2..).unwrap_or_default();
+        if own.starts_with("+-+") {
+            members.push(Vec::new());
+            in_member = true;
+        } else if own.starts_with("| ") || own.starts_with("+ ") || own == "|" {
+            own_lines.push(own.get(2..).unwrap_or_default());
+        } else if member.starts_with("+--") {
+            // `+---- n ----` starts the next sub-exception, `+--------` ends the group
+            in_member = member.trim_end_matches('-') != "+";
+            if in_member {
+                members.push(Vec::new());
+            }
+        } else if in_member && let Some(member_lines) = members.last_mut() {
+            member_lines.push(line);
+        } else {
+            return Err(Error::InvalidTraceback(line.to_string()));
+        }
+    }
+
+    let mut chain = parse_chain(own_lines)?;
+    let group = chain
+        .last_mut()
+        .ok_or(Error::InvalidTraceback(lines.join("\n")))?;
+    group.exceptions = members
+        .iter()
+        .map(|member| parse_group(member, column + 2))
+        .collect::<Result<_, _>>()?;
+    Ok(chain)
+}
+
+/// Parse a single traceback into frames and the exception.
+fn parse_exception<'tb>(lines: &[&'tb str]) -> Result<TracebackException<'tb>, Error> {
+    let mut parsed = TracebackException::default();
+    for text in lines {
+        // Everything after the start of the exception is its message, even if it looks like a
+        // frame
+        if !parsed.exception.is_empty() {
+            parsed.exception.push(text);
+            continue;
+        }
+        match TracebackLine::try_from(*text)? {
+            TracebackLine::TracebackHeader => (),
+            TracebackLine::FrameHeader(header) => parsed.frames.push(Frame {
+                header,
+                ..Default::default()
+            }),
+            TracebackLine::FrameContents { text } => {
+                let frame = parsed
+                    .frames
+                    .last_mut()
+                    .ok_or(Error::InvalidTraceback(text.to_string()))?;
+                frame.push_contents(text);
+            }
+            TracebackLine::Exception { text } => parsed.exception.push(text),
+        }
+    }
+    Ok(parsed)
+}
+
+/// How an exception relates to the one printed before it in a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChainCause {
+    /// `raise ... from ...`
+    Cause,
+    /// raised while handling the previous exception
+    Context,
+}
+
+impl AsStr for ChainCause {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Cause => "The above exception was the direct cause of the following exception:",
+            Self::Context => "During handling of the above exception, another exception occurred:",
+        }
+    }
+}
+
+impl TryFrom<&str> for ChainCause {
+    type Error = Error;
+
+    fn try_from(line: &str) -> Result<Self, Self::Error> {
+        [Self::Cause, Self::Context]
+            .into_iter()
+            .find(|cause| cause.as_str() == line)
+            .ok_or(Error::InvalidTraceback(line.to_string()))
+    }
+}
+
+/// One exception from a traceback: its frames, the exception line(s), how it is chained to the
+/// previous exception and, for an `ExceptionGroup`, the chain of each sub-exception.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct TracebackException<'tb> {
+    pub(crate) frames: Vec<Frame<'tb>>,
+    pub(crate) exception: Vec<&'tb str>,
+    pub(crate) cause: Option<ChainCause>,
+    pub(crate) exceptions: Vec<Vec<TracebackException<'tb>>>,
+}
+
+/// A single frame from a traceback, split into the parts python prints.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Frame<'tb> {
+    pub(crate) header: FrameHeader<'tb>,
+    /// failing source line, as printed by python (dedented, then indented by 4 spaces)
+    pub(crate) source: Option<&'tb str>,
+    /// `~~~^^^` lines highlighting the failing expression in `source`
+    pub(crate) carets: Vec<&'tb str>,
+    /// `name = repr` lines, present as the runner captures locals
+    pub(crate) locals: Vec<&'tb str>,
+}
+
+impl<'tb> Frame<'tb> {
+    /// The first line is always the source, carets only ever follow the source.
+    fn push_contents(&mut self, text: &'tb str) {
+        let trimmed = text.trim();
+        if !trimmed.is_empty() && trimmed.chars().all(|c| matches!(c, '~' | '^')) {
+            self.carets.push(text);
+        } else if self.source.is_none() && self.carets.is_empty() && self.locals.is_empty() {
+            self.source = Some(text);
+        } else {
+            self.locals.push(text);
+        }
     }
 }
 
@@ -34,8 +238,13 @@ impl Traceback {
 pub(crate) enum TracebackLine<'line> {
     TracebackHeader,
     FrameHeader(FrameHeader<'line>),
-    FrameContents { text: &'line str },
-    Exception(Exception),
+    FrameContents {
+        text: &'line str,
+    },
+    /// first line of the exception, `name: message`
+    Exception {
+        text: &'line str,
+    },
 }
 
 impl<'line> TryFrom<&'line str> for TracebackLine<'line> {
@@ -44,12 +253,15 @@ impl<'line> TryFrom<&'line str> for TracebackLine<'line> {
     fn try_from(line: &'line str) -> Result<Self, Self::Error> {
         match line.split_whitespace().next() {
             Some("Traceback") => Ok(Self::TracebackHeader),
+            Some("Exception") if line == "Exception Group Traceback (most recent call last):" => {
+                Ok(Self::TracebackHeader)
+            }
             Some("File") => Ok(Self::FrameHeader(line.try_into()?)),
             _ => {
                 if line.starts_with("    ") {
                     Ok(Self::FrameContents { text: line })
                 } else {
-                    Ok(Self::Exception(Exception::try_from(line)?))
+                    Ok(Self::Exception { text: line })
                 }
             }
         }
@@ -58,20 +270,31 @@ impl<'line> TryFrom<&'line str> for TracebackLine<'line> {
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct FrameHeader<'line> {
-    file_name: &'line str,
+    pub(crate) file_name: &'line str,
     pub(crate) function_name: &'line str,
     pub(crate) line_number: usize,
 }
 
+impl FrameHeader<'_> {
+    /// As printed by python
+    pub(crate) fn to_line(&self) -> String {
+        format!(
+            "  File \"{}\", line {}, in {}",
+            self.file_name, self.line_number, self.function_name
+        )
+    }
+}
+
 impl<'line> TryFrom<&'line str> for FrameHeader<'line> {
     type Error = Error;
 
     fn try_from(line: &'line str) -> Result<FrameHeader<'line>, Error> {
+        // `  File "<file_name>", line <line_number>, in <function_name>`
         let header: Option<_> = try {
-            let mut words = line.split_whitespace();
-            let file_name = words.nth(1)?;
-            let line_number = usize::from_str(words.nth(1)?.trim_end_matches(",")).ok()?;
-            let function_name = words.nth(1)?;
+            let contents = line.trim().strip_prefix("File \"")?;
+            let (file_name, contents) = contents.rsplit_once("\", line ")?;
+            let (line_number, function_name) = contents.split_once(", in ")?;
+            let line_number = usize::from_str(line_number).ok()?;
             FrameHeader {
                 file_name,
                 function_name,
@@ -82,32 +305,256 @@ impl<'line> TryFrom<&'line str> for FrameHeader<'line> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Exception {
-    AssertionError,
-    Other,
+/// The exception which caused a test to fail.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Exception {
+    /// type name, qualified by module for exceptions outside `builtins`, e.g. `mymodule.MyError`
+    pub name: String,
+    /// `str()` of the exception, may span multiple lines, empty if there is no message
+    pub message: String,
 }
 
-impl AsStr for Exception {
-    fn as_str(&self) -> &str {
-        match self {
-            Self::AssertionError => "AssertionError",
-            Self::Other => todo!(),
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message.is_empty() {
+            true => write!(f, "{}", self.name),
+            false => write!(f, "{}: {}", self.name, self.message),
         }
     }
 }
 
+impl Exception {
+    /// The first line of the message, e.g. for short summaries.
+    pub fn summary(&self) -> String {
+        match self.message.lines().next() {
+            Some(line) => format!("{}: {line}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Parses the final exception from a complete traceback.
 impl TryFrom<&str> for Exception {
     type Error = Error;
 
     fn try_from(traceback: &str) -> Result<Exception, Error> {
-        let exception: Option<_> = try {
-            let lastline = traceback.lines().last()?;
-            match lastline {
-                "AssertionError" => Self::AssertionError,
-                _ => Self::Other,
+        match parse_chain(traceback.lines()) {
+            Ok(chain) => {
+                let lines = chain
+                    .last()
+                    .map(|exception| exception.exception.as_slice())
+                    .unwrap_or_default();
+                Exception::try_from(lines)
             }
-        };
-        exception.ok_or(Error::InvalidTraceback(traceback.to_string()))
+            // Output which is not a valid traceback, still make the most of the last line
+            Err(_) => {
+                let lastline = traceback.lines().rfind(|line| !line.is_empty());
+                Exception::try_from(lastline.as_slice())
+            }
+        }
+    }
+}
+
+/// Parses the exception lines of a single `TracebackException`: `name: message` followed by any
+/// further lines of the message. Lines which do not start with an exception name, e.g. from a
+/// failing `__str__`, are reported as the last of them.
+impl TryFrom<&[&str]> for Exception {
+    type Error = Error;
+
+    fn try_from(lines: &[&str]) -> Result<Exception, Error> {
+        let (first, rest) = lines
+            .split_first()
+            .ok_or(Error::InvalidTraceback(String::new()))?;
+        let (name, message) = first.split_once(": ").unwrap_or((first, ""));
+        // a qualified name, e.g. `test_it.<locals>.MyError` for a class defined in a test
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '<' | '>'));
+        if !valid_name {
+            let last = lines.iter().rfind(|line| !line.is_empty()).unwrap_or(first);
+            return Ok(Exception {
+                name: last.to_string(),
+                message: String::new(),
+            });
+        }
+        let message = [message].into_iter().chain(rest.iter().copied());
+        Ok(Exception {
+            name: name.to_string(),
+            message: message.collect::<Vec<_>>().join("\n"),
+        })
+    }
+}
+
+/// `_pt_print_sources(output, exc, module_file)`, for a failed test: the source of each file in
+/// the traceback of `exc`, or an exception chained to it, other than the test module, as python
+/// read it to print the traceback. Each is a `<output> SOURCE` line, the file name, then its lines.
+const SOURCES: &str = r#"def _pt_print_sources(output, exc, module_file):
+    import linecache
+
+    files, pending, seen = [], [exc], set()
+    while pending:
+        exc = pending.pop()
+        if exc is None or id(exc) in seen:
+            continue
+        seen.add(id(exc))
+        tb = exc.__traceback__
+        while tb is not None:
+            file = tb.tb_frame.f_code.co_filename
+            # `<string>` is the runner, when it imports the test module
+            if file not in (module_file, "<string>", "<pt pytest>", *files):
+                files.append(file)
+            tb = tb.tb_next
+        pending.extend((exc.__cause__, exc.__context__, *getattr(exc, "exceptions", ())))
+    for file in files:
+        print(output + " SOURCE")
+        print(file)
+        for line in linecache.getlines(file):
+            print(line.rstrip("\n"))"#;
+
+/// Push the definition of `_pt_print_sources`, once before any tests.
+pub(crate) fn push_sources_definition(runner: &mut String, indent: usize) {
+    push_definition(runner, indent, SOURCES);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let tb = Traceback::from(
+            r#"Traceback (most recent call last):
+  File "/my tests/run.py", line 45, in <module>
+    test_seven_is_six()
+    ~~~~~~~~~~~~~~~~~^^
+    sys = <module 'sys' (built-in)>
+  File "/my tests/run.py", line 18, in test_seven_is_six
+    assert s == 6
+           ^^^^^^
+    s = 7
+AssertionError
+"#,
+        );
+        let chain = tb.chain().unwrap();
+        assert_eq!(1, chain.len());
+        let frames = &chain[0];
+        assert_eq!(None, frames.cause);
+        assert_eq!(2, frames.frames.len());
+        let last = &frames.frames[1];
+        assert_eq!("/my tests/run.py", last.header.file_name);
+        assert_eq!(18, last.header.line_number);
+        assert_eq!("test_seven_is_six", last.header.function_name);
+        assert_eq!(Some("    assert s == 6"), last.source);
+        assert_eq!(vec!["           ^^^^^^"], last.carets);
+        assert_eq!(vec!["    s = 7"], last.locals);
+        assert_eq!(vec!["AssertionError"], frames.exception);
+        assert_eq!(
+            "  File \"/my tests/run.py\", line 18, in test_seven_is_six",
+            last.header.to_line()
+        );
+    }
+
+    #[test]
+    fn chained_exceptions() {
+        let tb = Traceback::from(
+            r#"Traceback (most recent call last):
+  File "<string>", line 4, in inner
+    raise ValueError("inner")
+ValueError: inner
+
+During handling of the above exception, another exception occurred:
+
+Traceback (most recent call last):
+  File "<string>", line 9, in test_it
+    assert False
+AssertionError
+"#,
+        );
+        let chain = tb.chain().unwrap();
+        assert_eq!(2, chain.len());
+        assert_eq!(None, chain[0].cause);
+        assert_eq!(vec!["ValueError: inner"], chain[0].exception);
+        assert_eq!(Some(ChainCause::Context), chain[1].cause);
+        assert_eq!("test_it", chain[1].frames[0].header.function_name);
+        assert_eq!(vec!["AssertionError"], chain[1].exception);
+    }
+
+    #[test]
+    fn exception_group() {
+        let tb = Traceback::from(
+            r#"  + Exception Group Traceback (most recent call last):
+  |   File "<string>", line 17, in test_group
+  |     raise ExceptionGroup("eg", excs)
+  | ExceptionGroup: eg (2 sub-exceptions)
+  +-+---------------- 1 ----------------
+    | Traceback (most recent call last):
+    |   File "<string>", line 4, in inner
+    |     raise ValueError("inner")
+    | ValueError: inner
+    | 
+    | The above exception was the direct cause of the following exception:
+    | 
+    | Traceback (most recent call last):
+    |   File "<string>", line 9, in f
+    |     raise KeyError("k") from e
+    | KeyError: 'k'
+    +---------------- 2 ----------------
+    | ExceptionGroup: nested (1 sub-exception)
+    +-+---------------- 1 ----------------
+      | TypeError: 2
+      +------------------------------------
+"#,
+        );
+        let chain = tb.chain().unwrap();
+        assert_eq!(1, chain.len());
+        let group = &chain[0];
+        assert_eq!("test_group", group.frames[0].header.function_name);
+        assert_eq!(
+            vec!["ExceptionGroup: eg (2 sub-exceptions)"],
+            group.exception
+        );
+        assert_eq!(2, group.exceptions.len());
+
+        let first = &group.exceptions[0];
+        assert_eq!(2, first.len());
+        assert_eq!(vec!["ValueError: inner"], first[0].exception);
+        assert_eq!(Some(ChainCause::Cause), first[1].cause);
+        assert_eq!(vec!["KeyError: 'k'"], first[1].exception);
+
+        let nested = &group.exceptions[1];
+        assert_eq!(1, nested.len());
+        assert!(nested[0].frames.is_empty());
+        assert_eq!(
+            vec!["ExceptionGroup: nested (1 sub-exception)"],
+            nested[0].exception
+        );
+        assert_eq!(vec!["TypeError: 2"], nested[0].exceptions[0][0].exception);
+    }
+
+    #[test]
+    fn exception_message() {
+        let tb = r#"Traceback (most recent call last):
+  File "<string>", line 9, in test_it
+    assert False, "first\n    File \"x.py\", line 1, in y"
+AssertionError: first
+    File "x.py", line 1, in y
+"#;
+        let exception = Exception::try_from(tb).unwrap();
+        assert_eq!("AssertionError", exception.name);
+        assert_eq!("first\n    File \"x.py\", line 1, in y", exception.message);
+        assert_eq!("AssertionError: first", exception.summary());
+        let tb = Traceback::from(tb);
+        let chain = tb.chain().unwrap();
+        assert_eq!(1, chain[0].frames.len());
+        assert_eq!(2, chain[0].exception.len());
+
+        let bare = Exception::try_from("AssertionError\n").unwrap();
+        assert_eq!("AssertionError", bare.to_string());
+        let local = Exception::try_from("test_it.<locals>.MyError: boom\n").unwrap();
+        assert_eq!("test_it.<locals>.MyError", local.name);
+        assert_eq!("boom", local.message);
+        let invalid = Exception::try_from("not an exception\nstill not\n").unwrap();
+        assert_eq!("still not", invalid.to_string());
     }
 }
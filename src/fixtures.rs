@@ -0,0 +1,1130 @@
+//! Fixtures: those pt provides, as pytest's builtin fixtures, and those defined in test modules
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use base_traits::AsStr;
+use ruff_python_ast::{
+    Arguments, Decorator, Expr, StmtFunctionDef,
+    visitor::{Visitor, walk_expr},
+};
+use ruff_text_size::{Ranged, TextSize};
+
+use crate::{
+    Error, capture::push_log_capture_definitions, module::python_str, multiline::MultilineMut,
+    parametrize::Parametrization,
+};
+
+/// Number of numbered base temporary directories kept, including the current run's.
+const KEEP_BASETEMP: usize = 3;
+
+/// The directory under which `tmp_path` and `tmp_path_factory` create temporary directories.
+///
+/// As pytest, unless `--basetemp` is given each run uses a new numbered directory,
+/// `{tmp}/pt-of-{user}/pt-{N}`, and only the directories from the last 3 runs are kept. A
+/// `--basetemp` directory is emptied at the start of each run.
+///
+/// Usage:
+/// ```rust,no_run
+/// use pt::BaseTemp;
+/// let basetemp = BaseTemp::create(None).unwrap();
+/// println!("temporary directories are in {}", basetemp.path.display());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseTemp {
+    pub path: PathBuf,
+}
+
+impl BaseTemp {
+    /// Create the base temporary directory for this run, at `basetemp` if given.
+    pub fn create(basetemp: Option<&Path>) -> io::Result<Self> {
+        if let Some(path) = basetemp {
+            if path.exists() {
+                fs::remove_dir_all(path)?;
+            }
+            fs::create_dir_all(path)?;
+            return Ok(Self {
+                path: path.canonicalize()?,
+            });
+        }
+        let user = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+        let root = env::temp_dir().join(format!("pt-of-{}", sanitized_user(&user)));
+        fs::create_dir_all(&root)?;
+        let numbers: Vec<usize> = fs::read_dir(&root)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                name.to_str()?.strip_prefix("pt-")?.parse().ok()
+            })
+            .collect();
+        let mut number = numbers.iter().max().map_or(0, |max| max + 1);
+        // another run may create the same directory concurrently
+        let path = loop {
+            let path = root.join(format!("pt-{number}"));
+            match fs::create_dir(&path) {
+                Ok(()) => break path,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => number += 1,
+                Err(err) => return Err(err),
+            }
+        };
+        for old in numbers
+            .into_iter()
+            .filter(|old| old + KEEP_BASETEMP <= number)
+        {
+            // a directory in use by another run may not be removable, it is removed later
+            let _ = fs::remove_dir_all(root.join(format!("pt-{old}")));
+        }
+        Ok(Self { path })
+    }
+}
+
+/// Fixtures which pt provides itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    TmpPath,
+    TmpPathFactory,
+    Tmpdir,
+    Monkeypatch,
+    Capsys,
+    Capfd,
+    Caplog,
+}
+
+impl AsStr for Builtin {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::TmpPath => "tmp_path",
+            Self::TmpPathFactory => "tmp_path_factory",
+            Self::Tmpdir => "tmpdir",
+            Self::Monkeypatch => "monkeypatch",
+            Self::Capsys => "capsys",
+            Self::Capfd => "capfd",
+            Self::Caplog => "caplog",
+        }
+    }
+}
+
+impl TryFrom<&str> for Builtin {
+    type Error = Error;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        match name {
+            "tmp_path" => Ok(Self::TmpPath),
+            "tmp_path_factory" => Ok(Self::TmpPathFactory),
+            "tmpdir" => Ok(Self::Tmpdir),
+            "monkeypatch" => Ok(Self::Monkeypatch),
+            "capsys" => Ok(Self::Capsys),
+            "capfd" => Ok(Self::Capfd),
+            "caplog" => Ok(Self::Caplog),
+            _ => Err(Error::FixtureNotFound(name.to_string())),
+        }
+    }
+}
+
+/// `user` with any character which may not be valid in a path replaced by `_`, as pytest; e.g. a
+/// Windows `DOMAIN\user`.
+fn sanitized_user(user: &str) -> String {
+    user.chars()
+        .map(|c| match c.is_alphanumeric() || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+/// Creates numbered temporary directories, as pytest's `TempPathFactory`.
+const TMP_PATH_FACTORY: &str = r#"class _PtTempPathFactory:
+    def __init__(self, basetemp):
+        if basetemp is None:
+            import tempfile
+            basetemp = tempfile.mkdtemp(prefix="pt-")
+        self._basetemp = _pt_pathlib.Path(basetemp)
+
+    def getbasetemp(self):
+        return self._basetemp
+
+    def mktemp(self, basename, numbered=True):
+        if not numbered:
+            path = self._basetemp / basename
+            path.mkdir()
+            return path
+        number = 0
+        while True:
+            path = self._basetemp / f"{basename}{number}"
+            try:
+                path.mkdir()
+                return path
+            except FileExistsError:
+                number += 1"#;
+
+/// Fails any test requesting `tmpdir` when `py.path.local` is not available.
+const NO_LOCAL: &str = r#"def _pt_local(path):
+    raise ImportError("tmpdir is a py.path.local: install py to use it, or request tmp_path")"#;
+
+/// Records changes made during a test so they can be undone, as pytest's `MonkeyPatch`.
+const MONKEYPATCH: &str = r#"class _PtMonkeyPatch:
+    _NOTSET = object()
+
+    def __init__(self):
+        self._setattr = []
+        self._setitem = []
+        self._cwd = None
+        self._savesyspath = None
+
+    @staticmethod
+    def _resolve(import_path, raising):
+        if not isinstance(import_path, str) or "." not in import_path:
+            raise TypeError(f"must be absolute import path string, not {import_path!r}")
+        module_name, _, name = import_path.rpartition(".")
+        parts = module_name.split(".")
+        target = _pt_importlib.import_module(parts[0])
+        for index, part in enumerate(parts[1:], 2):
+            try:
+                target = getattr(target, part)
+            except AttributeError:
+                target = _pt_importlib.import_module(".".join(parts[:index]))
+        if raising and not hasattr(target, name):
+            raise AttributeError(f"{target!r} has no attribute {name!r}")
+        return target, name
+
+    def _save_attr(self, target, name):
+        oldval = getattr(target, name, self._NOTSET)
+        # restore descriptors, e.g. staticmethod, rather than the values they produce
+        if _pt_inspect.isclass(target):
+            oldval = target.__dict__.get(name, self._NOTSET)
+        self._setattr.append((target, name, oldval))
+
+    def setattr(self, target, name, value=_NOTSET, raising=True):
+        if value is self._NOTSET:
+            value = name
+            target, name = self._resolve(target, raising)
+        if raising and not hasattr(target, name):
+            raise AttributeError(f"{target!r} has no attribute {name!r}")
+        self._save_attr(target, name)
+        setattr(target, name, value)
+
+    def delattr(self, target, name=_NOTSET, raising=True):
+        if name is self._NOTSET:
+            target, name = self._resolve(target, raising)
+        if not hasattr(target, name):
+            if raising:
+                raise AttributeError(name)
+            return
+        self._save_attr(target, name)
+        delattr(target, name)
+
+    def setitem(self, dic, name, value):
+        self._setitem.append((dic, name, dic.get(name, self._NOTSET)))
+        dic[name] = value
+
+    def delitem(self, dic, name, raising=True):
+        if name not in dic:
+            if raising:
+                raise KeyError(name)
+            return
+        self._setitem.append((dic, name, dic.get(name, self._NOTSET)))
+        del dic[name]
+
+    def setenv(self, name, value, prepend=None):
+        value = str(value)
+        if prepend and name in _pt_os.environ:
+            value = value + prepend + _pt_os.environ[name]
+        self.setitem(_pt_os.environ, name, value)
+
+    def delenv(self, name, raising=True):
+        self.delitem(_pt_os.environ, name, raising=raising)
+
+    def syspath_prepend(self, path):
+        if self._savesyspath is None:
+            self._savesyspath = sys.path[:]
+        sys.path.insert(0, str(path))
+        _pt_importlib.invalidate_caches()
+
+    def chdir(self, path):
+        if self._cwd is None:
+            self._cwd = _pt_os.getcwd()
+        _pt_os.chdir(path)
+
+    def undo(self):
+        for target, name, value in reversed(self._setattr):
+            if value is self._NOTSET:
+                delattr(target, name)
+            else:
+                setattr(target, name, value)
+        self._setattr.clear()
+        for dic, name, value in reversed(self._setitem):
+            if value is self._NOTSET:
+                dic.pop(name, None)
+            else:
+                dic[name] = value
+        self._setitem.clear()
+        if self._savesyspath is not None:
+            sys.path[:] = self._savesyspath
+            self._savesyspath = None
+        if self._cwd is not None:
+            _pt_os.chdir(self._cwd)
+            self._cwd = None"#;
+
+/// The `request` passed to tests and to each fixture defined in a test module, as pytest's
+/// `FixtureRequest`, with the test's `node` and the run's `config`.
+const REQUEST: &str = r#"class _PtConfig:
+    _NOTSET = object()
+
+    def __init__(self, options):
+        self.option = _pt_types.SimpleNamespace(**options)
+
+    def getoption(self, name, default=_NOTSET, skip=False):
+        dest = name.lstrip("-").replace("-", "_")
+        if hasattr(self.option, dest):
+            return getattr(self.option, dest)
+        if default is not self._NOTSET:
+            return default
+        raise ValueError(f"no option named {name!r}")
+
+
+class _PtNode:
+    def __init__(self, name, nodeid, function, cls, markers):
+        self.name = name
+        self.originalname = name.partition("[")[0]
+        self.nodeid = nodeid
+        self.function = function
+        self.cls = cls
+        self.module = sys.modules.get(function.__module__)
+        self.config = _pt_config
+        self.keywords = dict.fromkeys([name, *markers], True)
+        # fixture functions by name, for fixtures pt could not find before the test ran
+        self.fixtures = {}
+        self._values = {}
+
+    def iter_markers(self, name=None):
+        for owner in (self.function, self.cls, self.module):
+            marks = getattr(owner, "pytestmark", [])
+            for mark in marks if isinstance(marks, list) else [marks]:
+                if name is None or mark.name == name:
+                    yield mark
+
+    def get_closest_marker(self, name, default=None):
+        return next(self.iter_markers(name), default)
+
+
+class _PtFixtureRequest:
+    _NOTSET = object()
+
+    def __init__(self, node, fixturename, fixturenames, param=_NOTSET, scope="function"):
+        if param is not self._NOTSET:
+            self.param = param
+        self.node = node
+        self.fixturename = fixturename
+        self.fixturenames = fixturenames
+        self.config = node.config
+        self.function = node.function
+        self.cls = node.cls
+        self.module = node.module
+        self.scope = scope
+        self._finalizers = []
+
+    def addfinalizer(self, finalizer):
+        self._finalizers.append(finalizer)
+
+    def getfixturevalue(self, argname):
+        if argname == "request":
+            return self
+        if argname in self.fixturenames:
+            return globals()["_pt_" + argname]
+        if argname in self.node._values:
+            return self.node._values[argname]
+        function = self.node.fixtures.get(argname)
+        if function is None:
+            raise LookupError(f"fixture {argname!r} not found")
+        # set up now, with the fixtures it requests, and torn down with this request
+        request = _PtFixtureRequest(self.node, argname, self.fixturenames)
+        self.addfinalizer(request._finish)
+        parameters = _pt_inspect.signature(self._unwrap(function)).parameters.values()
+        kwargs = {
+            parameter.name: request.getfixturevalue(parameter.name)
+            for parameter in parameters
+            if parameter.default is parameter.empty
+            and parameter.kind in (parameter.POSITIONAL_OR_KEYWORD, parameter.KEYWORD_ONLY)
+        }
+        value = self.node._values[argname] = request._execute(function, **kwargs)
+        return value
+
+    @staticmethod
+    def _unwrap(function):
+        # pytest wraps fixture functions so they cannot be called directly
+        if hasattr(function, "_get_wrapped_function"):
+            function = function._get_wrapped_function()
+        wrapped = getattr(function, "__pytest_wrapped__", None)
+        if wrapped is not None:
+            function = wrapped.obj
+        return function
+
+    def _execute(self, function, **kwargs):
+        function = self._unwrap(function)
+        if not _pt_inspect.isgeneratorfunction(function):
+            return function(**kwargs)
+        generator = function(**kwargs)
+        value = next(generator)
+        self.addfinalizer(lambda: self._teardown(generator))
+        return value
+
+    @staticmethod
+    def _teardown(generator):
+        try:
+            next(generator)
+        except StopIteration:
+            return
+        raise ValueError("fixture function has more than one 'yield'")
+
+    def _finish(self):
+        # every finalizer runs, the first exception is raised afterwards
+        exceptions = []
+        while self._finalizers:
+            try:
+                self._finalizers.pop()()
+            except BaseException as exc:
+                exceptions.append(exc)
+        if exceptions:
+            raise exceptions[0]
+
+
+class _PtFixtureCache:
+    # values of fixtures with a wider scope than "function", kept until the last test using them
+    def __init__(self):
+        self._values = {}
+        # those whose last test did not run, e.g. as it was skipped
+        _pt_atexit.register(self._finish_all)
+
+    def get(self, key, request, execute):
+        if key not in self._values:
+            try:
+                self._values[key] = (request, execute(), None)
+            except Exception as exc:
+                # as pytest, each test using the value fails with the same error
+                self._values[key] = (request, None, (exc, exc.__traceback__))
+        request, value, error = self._values[key]
+        if error is not None:
+            exc, traceback = error
+            raise exc.with_traceback(traceback)
+        return value
+
+    def finish(self, key):
+        entry = self._values.pop(key, None)
+        if entry is not None:
+            entry[0]._finish()
+
+    def _finish_all(self):
+        while self._values:
+            self.finish(next(reversed(self._values)))"#;
+
+/// Push python `source`, defining a class or function, at `indent`.
+pub(crate) fn push_definition(runner: &mut String, indent: usize, source: &str) {
+    for line in source.lines() {
+        match line.is_empty() {
+            true => runner.push_newline(),
+            false => runner.push_python_line(indent, [line]),
+        }
+    }
+}
+
+impl Builtin {
+    /// The python variable holding the fixture's value.
+    pub(crate) fn variable(&self) -> String {
+        format!("_pt_{}", self.as_str())
+    }
+
+    /// Fixtures which must be set up before this one.
+    pub(crate) fn requires(&self) -> &'static [Builtin] {
+        match self {
+            Self::TmpPath => &[Self::TmpPathFactory],
+            Self::TmpPathFactory
+            | Self::Monkeypatch
+            | Self::Capsys
+            | Self::Capfd
+            | Self::Caplog => &[],
+            Self::Tmpdir => &[Self::TmpPath],
+        }
+    }
+
+    /// How long pytest keeps the fixture's value.
+    pub(crate) fn scope(&self) -> Scope {
+        match self {
+            Self::TmpPathFactory => Scope::Session,
+            _ => Scope::Function,
+        }
+    }
+
+    /// Whether the fixture captures output, so needs `capture::push_capture_definitions()`.
+    pub(crate) fn captures_output(&self) -> bool {
+        matches!(self, Self::Capsys | Self::Capfd)
+    }
+
+    /// Code, run once before any tests, for session-scoped fixtures and any definitions needed.
+    ///
+    /// Definitions for fixtures which `captures_output()` are shared with the runner's own
+    /// capturing, so are not included.
+    pub(crate) fn push_session_setup(
+        &self,
+        runner: &mut String,
+        indent: usize,
+        basetemp: Option<&Path>,
+    ) {
+        match self {
+            Self::TmpPathFactory => {
+                runner.push_python_line(indent, ["import pathlib as _pt_pathlib"]);
+                push_definition(runner, indent, TMP_PATH_FACTORY);
+                let basetemp = basetemp.map(|path| python_str(&path.to_string_lossy()));
+                runner.push_python_line(
+                    indent,
+                    [
+                        &self.variable(),
+                        " = _PtTempPathFactory(",
+                        basetemp.as_deref().unwrap_or("None"),
+                        ")",
+                    ],
+                );
+            }
+            // `tmpdir` is a `py.path.local`, which is only available with `py` or pytest installed
+            Self::Tmpdir => {
+                runner.push_python_line(indent, ["try:"]);
+                runner.push_python_line(indent + 1, ["from py.path import local as _pt_local"]);
+                runner.push_python_line(indent, ["except ImportError:"]);
+                runner.push_python_line(indent + 1, ["try:"]);
+                runner.push_python_line(
+                    indent + 2,
+                    ["from _pytest._py.path import LocalPath as _pt_local"],
+                );
+                runner.push_python_line(indent + 1, ["except ImportError:"]);
+                push_definition(runner, indent + 2, NO_LOCAL);
+            }
+            Self::Monkeypatch => {
+                runner.push_python_line(
+                    indent,
+                    ["import importlib as _pt_importlib, inspect as _pt_inspect, os as _pt_os"],
+                );
+                push_definition(runner, indent, MONKEYPATCH);
+            }
+            Self::Caplog => push_log_capture_definitions(runner, indent),
+            Self::TmpPath | Self::Capsys | Self::Capfd => (),
+        }
+    }
+
+    /// Code, run before test `testname`, setting `variable()` for a function-scoped fixture.
+    pub(crate) fn push_setup(&self, runner: &mut String, indent: usize, testname: &str) {
+        match self {
+            Self::TmpPath => {
+                // as pytest, named after the test function, not including any class
+                let name = testname.rsplit("::").next().unwrap_or(testname);
+                let basename: String = name
+                    .chars()
+                    .map(|c| match c.is_alphanumeric() {
+                        true => c,
+                        false => '_',
+                    })
+                    .take(30)
+                    .collect();
+                runner.push_python_line(
+                    indent,
+                    [
+                        &self.variable(),
+                        " = ",
+                        &Self::TmpPathFactory.variable(),
+                        ".mktemp(",
+                        &python_str(&basename),
+                        ", numbered=True)",
+                    ],
+                );
+            }
+            Self::Tmpdir => runner.push_python_line(
+                indent,
+                [
+                    &self.variable(),
+                    " = _pt_local(",
+                    &Self::TmpPath.variable(),
+                    ")",
+                ],
+            ),
+            Self::Monkeypatch => {
+                runner.push_python_line(indent, [&self.variable(), " = _PtMonkeyPatch()"])
+            }
+            Self::Capsys | Self::Capfd => {
+                let method = match self {
+                    Self::Capfd => "\"fd\"",
+                    _ => "\"sys\"",
+                };
+                runner.push_python_line(indent, [&self.variable(), " = _PtCapture(", method, ")"]);
+                runner.push_python_line(indent, [&self.variable(), ".start()"]);
+            }
+            Self::Caplog => {
+                runner.push_python_line(indent, [&self.variable(), " = _PtLogCaptureFixture()"])
+            }
+            Self::TmpPathFactory => (),
+        }
+    }
+
+    /// Whether the fixture must be torn down after the test, whether it passes or fails.
+    pub(crate) fn has_teardown(&self) -> bool {
+        matches!(
+            self,
+            Self::Monkeypatch | Self::Capsys | Self::Capfd | Self::Caplog
+        )
+    }
+
+    /// Code, run after a test, to tear down a fixture with `has_teardown()`.
+    pub(crate) fn push_teardown(&self, runner: &mut String, indent: usize) {
+        match self {
+            Self::Monkeypatch => runner.push_python_line(indent, [&self.variable(), ".undo()"]),
+            Self::Capsys | Self::Capfd | Self::Caplog => {
+                runner.push_python_line(indent, [&self.variable(), ".close()"])
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A fixture defined with `@pytest.fixture` or `@fixture`, in a test module, test class or
+/// `conftest.py`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UserFixture {
+    /// Name tests request it by, the `name=` argument if given
+    pub(crate) name: String,
+    pub(crate) defined_in: DefinedIn,
+    /// Name of the python function
+    pub(crate) function: String,
+    /// Parameters, which are the fixtures it requests
+    pub(crate) fixtures: Vec<String>,
+    /// Fixtures it requests with `request.getfixturevalue("name")`
+    pub(crate) dynamic_fixtures: Vec<String>,
+    /// It requests fixtures with `request.getfixturevalue(name)`, where `name` is not a literal
+    pub(crate) runtime_fixtures: bool,
+    /// `params=`, each test using the fixture is run once per param
+    pub(crate) params: Option<Parametrization>,
+    /// `autouse=True`, used by every test it is available to
+    pub(crate) autouse: bool,
+    /// `scope=`, how long its value is kept
+    pub(crate) scope: Scope,
+}
+
+/// How long a fixture's value is kept, from narrowest to widest. Each test module runs in its own
+/// python process, so values with a wider scope than `Module` are kept for the module too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Scope {
+    Function,
+    Class,
+    Module,
+    Package,
+    Session,
+}
+
+impl AsStr for Scope {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Function => "function",
+            Self::Class => "class",
+            Self::Module => "module",
+            Self::Package => "package",
+            Self::Session => "session",
+        }
+    }
+}
+
+/// Where a `UserFixture` is defined, which determines the tests it is available to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DefinedIn {
+    Module,
+    /// The test class of this name, the fixture is a method called on the test's instance
+    Class(String),
+    /// The conftest at this index of those applying to the test module
+    Conftest(usize),
+}
+
+impl UserFixture {
+    /// The fixture defined by `function` in `src`, if it is decorated as one.
+    pub(crate) fn from_definition(
+        src: &str,
+        function: &StmtFunctionDef,
+    ) -> Result<Option<Self>, Error> {
+        let Some(arguments) = fixture_decorator(&function.decorator_list) else {
+            return Ok(None);
+        };
+        let name = arguments
+            .and_then(|arguments| arguments.find_keyword("name"))
+            .and_then(|name| match &name.value {
+                Expr::StringLiteral(name) => Some(name.value.to_str().to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| function.name.to_string());
+        let params = match arguments {
+            Some(arguments) => Parametrization::from_fixture(src, &name, arguments)
+                .map_err(|msg| Error::InvalidParametrize(format!("In fixture {name}: {msg}")))?,
+            None => None,
+        };
+        let autouse = arguments
+            .and_then(|arguments| arguments.find_keyword("autouse"))
+            .is_some_and(
+                |autouse| matches!(&autouse.value, Expr::BooleanLiteral(autouse) if autouse.value),
+            );
+        // a scope only known at runtime is taken as "function", with a warning
+        let scope = match arguments
+            .and_then(|arguments| arguments.find_keyword("scope"))
+            .map(|scope| &scope.value)
+        {
+            Some(Expr::StringLiteral(scope)) => match scope.value.to_str() {
+                "class" => Scope::Class,
+                "module" => Scope::Module,
+                "package" => Scope::Package,
+                "session" => Scope::Session,
+                _ => Scope::Function,
+            },
+            _ => Scope::Function,
+        };
+        Ok(Some(Self {
+            defined_in: DefinedIn::Module,
+            function: function.name.to_string(),
+            fixtures: parameter_names(function),
+            dynamic_fixtures: dynamic_fixtures(function),
+            runtime_fixtures: runtime_fixtures(function),
+            params,
+            autouse,
+            scope,
+            name,
+        }))
+    }
+
+    /// The fixture function, as a python expression. Those defined in the test module are looked
+    /// up with `prefix`, those in a class on the test's instance, `_pt_self`, and those in the nth
+    /// conftest on `_pt_conftest_n`.
+    pub(crate) fn python_function(&self, prefix: &str) -> String {
+        match &self.defined_in {
+            DefinedIn::Module => format!("{prefix}{}", self.function),
+            DefinedIn::Class(_) => format!("_pt_self.{}", self.function),
+            DefinedIn::Conftest(index) => format!("_pt_conftest_{index}.{}", self.function),
+        }
+    }
+}
+
+/// `Some` with any arguments if one of `decorators` is `@pytest.fixture` or `@fixture`.
+fn fixture_decorator(decorators: &[Decorator]) -> Option<Option<&Arguments>> {
+    decorators.iter().find_map(|decorator| {
+        let (expr, arguments) = match &decorator.expression {
+            Expr::Call(call) => (&*call.func, Some(&call.arguments)),
+            expr => (expr, None),
+        };
+        let is_fixture = match expr {
+            Expr::Attribute(fixture) => {
+                fixture.attr.as_str() == "fixture"
+                    && matches!(&*fixture.value, Expr::Name(pytest) if pytest.id.as_str() == "pytest")
+            }
+            Expr::Name(fixture) => fixture.id.as_str() == "fixture",
+            _ => false,
+        };
+        is_fixture.then_some(arguments)
+    })
+}
+
+/// `scope=` of the fixture defined by `function`, and where it is given, if pt cannot keep its
+/// value as long as pytest: `"package"` and `"session"` values are only kept for the test module,
+/// and a fixture whose scope is only known at runtime is set up for each test which uses it.
+pub(crate) fn ignored_scope(function: &StmtFunctionDef) -> Option<(String, TextSize)> {
+    let scope = fixture_decorator(&function.decorator_list)??.find_keyword("scope")?;
+    match &scope.value {
+        Expr::StringLiteral(literal) => match literal.value.to_str() {
+            name @ ("package" | "session") => Some((name.to_string(), scope.start())),
+            _ => None,
+        },
+        // a callable, which pytest calls to determine the scope
+        _ => Some(("dynamic".to_string(), scope.start())),
+    }
+}
+
+/// Names of the parameters of `function` without defaults, which are the fixtures it requests.
+pub(crate) fn parameter_names(function: &StmtFunctionDef) -> Vec<String> {
+    function
+        .parameters
+        .iter_non_variadic_params()
+        .filter(|param| param.default.is_none())
+        .map(|param| param.name().to_string())
+        .collect()
+}
+
+/// Fixtures requested in `function` by `request.getfixturevalue("name")` with a literal name, so
+/// they can be set up in advance.
+pub(crate) fn dynamic_fixtures(function: &StmtFunctionDef) -> Vec<String> {
+    let mut finder = GetFixtureValue::default();
+    finder.visit_body(&function.body);
+    finder.found
+}
+
+/// Whether `function` calls `request.getfixturevalue()` with a name which is only known when it
+/// runs, so every fixture available to it must be ready to set up then.
+pub(crate) fn runtime_fixtures(function: &StmtFunctionDef) -> bool {
+    let mut finder = GetFixtureValue::default();
+    finder.visit_body(&function.body);
+    finder.runtime
+}
+
+/// Finds `getfixturevalue(name)` calls
+#[derive(Default)]
+struct GetFixtureValue {
+    /// literal names
+    found: Vec<String>,
+    /// any other name
+    runtime: bool,
+}
+
+impl<'a> Visitor<'a> for GetFixtureValue {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let Expr::Call(call) = expr
+            && let Expr::Attribute(method) = &*call.func
+            && method.attr.as_str() == "getfixturevalue"
+        {
+            match call.arguments.find_argument_value("argname", 0) {
+                Some(Expr::StringLiteral(name)) => self.found.push(name.value.to_str().to_string()),
+                Some(_) => self.runtime = true,
+                None => (),
+            }
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// A fixture set up for a test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fixture<'a> {
+    Builtin(Builtin),
+    User(&'a UserFixture),
+    /// The test's own `request`, each `User` fixture has another
+    Request,
+}
+
+impl Fixture<'_> {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Self::Builtin(builtin) => builtin.as_str(),
+            Self::User(fixture) => &fixture.name,
+            Self::Request => "request",
+        }
+    }
+
+    /// The python variable holding the fixture's value.
+    pub(crate) fn variable(&self) -> String {
+        format!("_pt_{}", self.name())
+    }
+
+    /// Whether the fixture needs a `request`, so the test's `_pt_node`.
+    pub(crate) fn needs_node(&self) -> bool {
+        matches!(self, Self::User(_) | Self::Request)
+    }
+
+    /// How long pytest keeps the fixture's value.
+    pub(crate) fn scope(&self) -> Scope {
+        match self {
+            Self::Builtin(builtin) => builtin.scope(),
+            Self::User(fixture) => fixture.scope,
+            Self::Request => Scope::Function,
+        }
+    }
+
+    /// How long the value is kept for test `testname`: as pytest, a class-scoped fixture is
+    /// function-scoped for a test outside a class.
+    fn scope_for(&self, testname: &str) -> Scope {
+        match self.scope() {
+            Scope::Class if !testname.contains("::") => Scope::Function,
+            scope => scope,
+        }
+    }
+
+    /// The python expression for the key a user fixture's value is kept under in
+    /// `_pt_fixture_cache`, for test `testname` with `param` as the source of `request.param`.
+    /// `None` if the value is not kept beyond the test.
+    pub(crate) fn cache_key(&self, testname: &str, param: Option<&str>) -> Option<String> {
+        let Self::User(fixture) = self else {
+            return None;
+        };
+        let class = match self.scope_for(testname) {
+            Scope::Function => return None,
+            Scope::Class => testname
+                .rsplit_once("::")
+                .map(|(class, _)| python_str(class)),
+            Scope::Module | Scope::Package | Scope::Session => None,
+        };
+        let function = match &fixture.defined_in {
+            DefinedIn::Module => fixture.function.clone(),
+            DefinedIn::Class(class) => format!("{class}.{}", fixture.function),
+            DefinedIn::Conftest(index) => format!("_pt_conftest_{index}.{}", fixture.function),
+        };
+        Some(format!(
+            "({}, {}, {})",
+            class.as_deref().unwrap_or("None"),
+            python_str(&function),
+            param.map(python_str).as_deref().unwrap_or("None")
+        ))
+    }
+
+    /// Code, run before test `testname`, which must not fail if `has_teardown()`. `before` are the
+    /// fixtures already set up, `param` is the python expression for `request.param`.
+    pub(crate) fn push_setup(
+        &self,
+        runner: &mut String,
+        indent: usize,
+        testname: &str,
+        before: &[Fixture],
+        param: Option<&str>,
+    ) {
+        let names: Vec<String> = before
+            .iter()
+            .map(|fixture| python_str(fixture.name()))
+            .collect();
+        let names = format!("[{}]", names.join(", "));
+        match self {
+            Self::Builtin(builtin) => builtin.push_setup(runner, indent, testname),
+            Self::User(fixture) => {
+                let scope = match self.scope_for(testname) {
+                    Scope::Function => String::new(),
+                    scope => format!(", scope={}", python_str(scope.as_str())),
+                };
+                runner.push_python_line(
+                    indent,
+                    [
+                        "_ptrequest_",
+                        &fixture.name,
+                        " = _PtFixtureRequest(_pt_node, ",
+                        &python_str(&fixture.name),
+                        ", ",
+                        &names,
+                        param.map(|_| ", param=").unwrap_or_default(),
+                        param.unwrap_or_default(),
+                        &scope,
+                        ")",
+                    ],
+                )
+            }
+            Self::Request => runner.push_python_line(
+                indent,
+                [
+                    &self.variable(),
+                    " = _PtFixtureRequest(_pt_node, None, ",
+                    &names,
+                    ")",
+                ],
+            ),
+        }
+    }
+
+    /// Code, following `push_setup()` and within the `try` of a fixture torn down after this test,
+    /// which may fail. Fixture functions are looked up as `UserFixture::python_function()`. A value
+    /// with `cache_key` is only computed by the first test using it.
+    pub(crate) fn push_execute(
+        &self,
+        runner: &mut String,
+        indent: usize,
+        prefix: &str,
+        cache_key: Option<&str>,
+    ) {
+        if let Self::User(fixture) = self {
+            let request = format!("_ptrequest_{}", fixture.name);
+            let arguments: Vec<String> = fixture
+                .fixtures
+                .iter()
+                .map(|name| match name.as_str() {
+                    "request" => format!(", request={request}"),
+                    name => format!(", {name}=_pt_{name}"),
+                })
+                .collect();
+            let execute = format!(
+                "{request}._execute({}{})",
+                fixture.python_function(prefix),
+                arguments.concat()
+            );
+            let value = match cache_key {
+                Some(key) => format!("_pt_fixture_cache.get({key}, {request}, lambda: {execute})"),
+                None => execute,
+            };
+            runner.push_python_line(indent, [&self.variable(), " = ", &value]);
+        }
+    }
+
+    /// Whether the fixture must be torn down after the test, whether it passes or fails.
+    pub(crate) fn has_teardown(&self) -> bool {
+        match self {
+            Self::Builtin(builtin) => builtin.has_teardown(),
+            Self::User(_) | Self::Request => true,
+        }
+    }
+
+    /// Code, run after a test, to tear down a fixture with `has_teardown()`, or one with
+    /// `cache_key` after the last test using its value.
+    pub(crate) fn push_teardown(
+        &self,
+        runner: &mut String,
+        indent: usize,
+        cache_key: Option<&str>,
+    ) {
+        match self {
+            Self::Builtin(builtin) => builtin.push_teardown(runner, indent),
+            Self::User(fixture) => match cache_key {
+                Some(key) => {
+                    runner.push_python_line(indent, ["_pt_fixture_cache.finish(", key, ")"])
+                }
+                None => {
+                    runner.push_python_line(indent, ["_ptrequest_", &fixture.name, "._finish()"])
+                }
+            },
+            Self::Request => runner.push_python_line(indent, [&self.variable(), "._finish()"]),
+        }
+    }
+}
+
+/// Push the definitions needed by `request`, once before any tests. `options` are the values
+/// `request.config.getoption()` returns, as python expressions.
+pub(crate) fn push_request_definitions(
+    runner: &mut String,
+    indent: usize,
+    options: &[(String, String)],
+) {
+    runner.push_python_line(
+        indent,
+        ["import atexit as _pt_atexit, inspect as _pt_inspect, types as _pt_types"],
+    );
+    push_definition(runner, indent, REQUEST);
+    runner.push_python_line(indent, ["_pt_fixture_cache = _PtFixtureCache()"]);
+    let options: Vec<String> = options
+        .iter()
+        .map(|(name, value)| format!("{}: {value}", python_str(name)))
+        .collect();
+    runner.push_python_line(
+        indent,
+        ["_pt_config = _PtConfig({", &options.join(", "), "})"],
+    );
+}
+
+/// The fixtures needed for `requested`, in the order they must be set up, each once. Fixtures
+/// found by `user`, those available to the test, replace builtins of the same name. The test's own
+/// `request` is not included.
+pub(crate) fn resolve<'a>(
+    requested: impl IntoIterator<Item = &'a str>,
+    user: &dyn Fn(&str) -> Option<&'a UserFixture>,
+) -> Result<Vec<Fixture<'a>>, Error> {
+    fn add<'a>(
+        name: &'a str,
+        user: &dyn Fn(&str) -> Option<&'a UserFixture>,
+        resolved: &mut Vec<Fixture<'a>>,
+        resolving: &mut Vec<&'a str>,
+    ) -> Result<(), Error> {
+        if name == "request" || resolved.iter().any(|fixture| fixture.name() == name) {
+            return Ok(());
+        }
+        if resolving.contains(&name) {
+            return Err(Error::RecursiveFixture(name.to_string()));
+        }
+        match user(name) {
+            Some(fixture) => {
+                resolving.push(name);
+                for required in fixture.fixtures.iter().chain(&fixture.dynamic_fixtures) {
+                    add(required, user, resolved, resolving)?;
+                    if let Some(narrower) = resolved.iter().find(|resolved| {
+                        resolved.name() == required && resolved.scope() < fixture.scope
+                    }) {
+                        return Err(Error::ScopeMismatch(format!(
+                            "fixture '{name}' with scope '{}' requests the {} scoped fixture \
+                             '{required}'",
+                            fixture.scope.as_str(),
+                            narrower.scope().as_str()
+                        )));
+                    }
+                }
+                resolving.pop();
+                resolved.push(Fixture::User(fixture));
+            }
+            None => {
+                let builtin = Builtin::try_from(name)?;
+                for required in builtin.requires() {
+                    add(required.as_str(), user, resolved, resolving)?;
+                }
+                resolved.push(Fixture::Builtin(builtin));
+            }
+        }
+        Ok(())
+    }
+    let mut resolved = Vec::new();
+    for name in requested {
+        add(name, user, &mut resolved, &mut Vec::new())?;
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_dependencies() {
+        let user = |_: &str| None;
+        assert_eq!(
+            vec![
+                Fixture::Builtin(Builtin::TmpPathFactory),
+                Fixture::Builtin(Builtin::TmpPath),
+                Fixture::Builtin(Builtin::Tmpdir)
+            ],
+            resolve(["tmpdir", "tmp_path"], &user).unwrap()
+        );
+        assert!(matches!(
+            resolve(["tmp_path", "no_such_fixture"], &user),
+            Err(Error::FixtureNotFound(name)) if name == "no_such_fixture"
+        ));
+
+        let fixture = |name: &str, fixtures: &[&str]| UserFixture {
+            name: name.to_string(),
+            defined_in: DefinedIn::Module,
+            function: name.to_string(),
+            fixtures: fixtures.iter().map(|name| name.to_string()).collect(),
+            dynamic_fixtures: Vec::new(),
+            runtime_fixtures: false,
+            params: None,
+            autouse: false,
+            scope: Scope::Function,
+        };
+        let (a, b) = (fixture("a", &["b"]), fixture("b", &["a"]));
+        let user = |name: &str| match name {
+            "a" => Some(&a),
+            "b" => Some(&b),
+            _ => None,
+        };
+        assert!(matches!(
+            resolve(["a"], &user),
+            Err(Error::RecursiveFixture(name)) if name == "a"
+        ));
+
+        let shared = UserFixture {
+            scope: Scope::Module,
+            ..fixture("shared", &["monkeypatch"])
+        };
+        let user = |name: &str| (name == "shared").then_some(&shared);
+        assert!(matches!(
+            resolve(["shared"], &user),
+            Err(Error::ScopeMismatch(msg))
+                if msg == "fixture 'shared' with scope 'module' requests the function scoped \
+                           fixture 'monkeypatch'"
+        ));
+    }
+
+    #[test]
+    fn user_in_basetemp() {
+        assert_eq!("DOMAIN_user", sanitized_user("DOMAIN\\user"));
+        assert_eq!("a_b__c", sanitized_user("a.b/:c"));
+        assert_eq!("jürgen", sanitized_user("jürgen"));
+    }
+
+    #[test]
+    fn explicit_basetemp() {
+        let root = env::temp_dir().join(format!("pt-basetemp-test-{}", std::process::id()));
+        let basetemp = BaseTemp::create(Some(&root)).unwrap();
+        fs::write(basetemp.path.join("leftover"), "").unwrap();
+        let basetemp = BaseTemp::create(Some(&root)).unwrap();
+        assert!(fs::read_dir(&basetemp.path).unwrap().next().is_none());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+//! Locating the python interpreter used to run tests
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{Error, PythonVersion};
+
+/// A python interpreter and its version.
+///
+/// Usage:
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pt::Interpreter;
+/// let python = Interpreter::find(None, std::env::var_os("VIRTUAL_ENV"), Path::new(".")).unwrap();
+/// println!("python: {} ({})", python.path.display(), python.version);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpreter {
+    pub path: PathBuf,
+    pub version: PythonVersion,
+}
+
+impl Interpreter {
+    /// Find the interpreter to use, in order of preference:
+    /// 1. `explicit`, from `--python`
+    /// 1. an active virtualenv, `virtual_env` should be the value of `$VIRTUAL_ENV`
+    /// 1. `.venv` in `rootdir`
+    /// 1. the interpreter `uv` selects for a project with a `uv.lock` in `rootdir`
+    /// 1. `python` or `python3` on the `PATH`
+    pub fn find(
+        explicit: Option<&Path>,
+        virtual_env: Option<impl Into<PathBuf>>,
+        rootdir: &Path,
+    ) -> Result<Self, Error> {
+        if let Some(path) = explicit {
+            return Self::try_from(path);
+        }
+        if let Some(venv) = virtual_env {
+            return Self::try_from(venv_python(&venv.into()).as_path());
+        }
+        let local_venv = rootdir.join(".venv");
+        if local_venv.is_dir() {
+            return Self::try_from(venv_python(&local_venv).as_path());
+        }
+        if rootdir.join("uv.lock").is_file()
+            && let Some(path) = uv_python(rootdir)
+        {
+            return Self::try_from(path.as_path());
+        }
+        // `python` may be python 2, or a shim which fails, when `python3` is usable
+        let mut unusable = None;
+        for name in ["python", "python3"] {
+            match Self::try_from(Path::new(name)) {
+                Ok(python) => return Ok(python),
+                Err(Error::PythonNotFound(_)) => (),
+                Err(err) => {
+                    unusable.get_or_insert(err);
+                }
+            }
+        }
+        Err(unusable.unwrap_or(Error::PythonNotFound(format!(
+            "No python interpreter found. Activate a virtualenv, create `.venv` in {} or use \
+             `--python PATH`",
+            rootdir.display()
+        ))))
+    }
+
+    /// Command to run this interpreter.
+    pub fn command(&self) -> Command {
+        Command::new(&self.path)
+    }
+}
+
+/// Run `path --version` and check the version is supported.
+impl TryFrom<&Path> for Interpreter {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let output = match Command::new(path).arg("--version").output() {
+            Ok(output) => output,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::PythonNotFound(format!(
+                    "Python interpreter {} not found",
+                    path.display()
+                )));
+            }
+            Err(err) => {
+                return Err(Error::PythonNotFound(format!(
+                    "Unable to run python interpreter {}: {err}",
+                    path.display()
+                )));
+            }
+        };
+        // python < 3.4 prints the version to stderr
+        let version = match output.stdout.is_empty() {
+            true => &output.stderr,
+            false => &output.stdout,
+        };
+        let version = String::from_utf8_lossy(version);
+        let version: PythonVersion = match output.status.success() {
+            true => version.parse().ok(),
+            false => None,
+        }
+        .ok_or_else(|| {
+            Error::UnsupportedPython(format!(
+                "Unable to determine the version of python interpreter {}: {}",
+                path.display(),
+                version.trim()
+            ))
+        })?;
+        if !version.is_supported() {
+            return Err(Error::UnsupportedPython(format!(
+                "Python {version} at {} is not supported, pt requires Python {} to {}",
+                path.display(),
+                PythonVersion::MIN,
+                PythonVersion::MAX
+            )));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            version,
+        })
+    }
+}
+
+/// Location of the interpreter in a virtualenv
+fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/// Ask `uv` which interpreter it would use for the project in `rootdir`
+fn uv_python(rootdir: &Path) -> Option<PathBuf> {
+    let output = Command::new("uv")
+        .args(["python", "find"])
+        .current_dir(rootdir)
+        .output()
+        .ok()?;
+    let path = String::from_utf8(output.stdout).ok()?;
+    (output.status.success() && !path.trim().is_empty()).then(|| PathBuf::from(path.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_interpreter() {
+        assert!(matches!(
+            Interpreter::try_from(Path::new("false")),
+            Err(Error::UnsupportedPython(msg)) if msg.contains("interpreter false")
+        ));
+    }
+
+    #[test]
+    fn missing_interpreter() {
+        let explicit = Path::new("./no/such/python");
+        assert!(matches!(
+            Interpreter::find(Some(explicit), None::<PathBuf>, Path::new(".")),
+            Err(Error::PythonNotFound(msg)) if msg.contains("./no/such/python")
+        ));
+        assert!(matches!(
+            Interpreter::find(None, Some("./no/such/venv"), Path::new(".")),
+            Err(Error::PythonNotFound(msg)) if msg.contains("no/such/venv")
+        ));
+    }
+}
@@ -4,25 +4,85 @@
 //! report / code generation.
 //!
 //! Main entry point is `TestSuite`
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display, ops::Range, path::PathBuf};
 
 use base_traits::AsStr;
 use indexmap::IndexMap;
-use ruff_python_ast::Stmt;
+use ruff_python_ast::{Expr, Stmt, StmtFunctionDef, visitor::Visitor};
 use ruff_python_parser::{ParseError, parse_module};
+use ruff_text_size::{Ranged, TextSize};
+
+mod args;
+pub use args::{Args, Capture, ImportMode, RunnerMode, TracebackStyle};
+
+mod cache;
+pub use cache::Cache;
+
+mod capture;
+use capture::push_capture_definitions;
+
+mod collection;
+pub use collection::{CollectionError, CollectionWarning};
+
+mod config;
+pub use config::Config;
+
+mod conftest;
+pub use conftest::Conftest;
+
+mod expression;
+
+mod fixtures;
+pub use fixtures::BaseTemp;
 
 mod failures;
 pub use failures::{Exception, Traceback};
 
 mod pytests;
-pub use pytests::{PythonTest, TestStatus};
+pub use pytests::{PythonTest, ReportOptions, TestStatus};
+
+mod interpreter;
+pub use interpreter::Interpreter;
+
+mod module;
+use module::python_str;
+pub use module::{MODULE_SKIPPED, TestModule};
 
 mod multiline;
 
-use crate::{multiline::MultilineMut, pytests::TestDetails};
+mod parametrize;
+
+mod python;
+pub use python::PythonVersion;
+
+mod shim;
+
+mod skipping;
+
+mod unittest;
+
+mod warnings;
+pub use warnings::{check_filter, warnings_summary};
+
+use crate::{
+    collection::{NestedTests, UnknownMarks, bound_names},
+    expression::Expression,
+    failures::push_sources_definition,
+    fixtures::{Builtin, DefinedIn, Fixture, UserFixture, ignored_scope, push_request_definitions},
+    multiline::{Location, Multiline, MultilineMut},
+    parametrize::{combinations, parametrizations},
+    pytests::{InheritedMarks, TestDetails},
+    shim::push_outcome_definition,
+    skipping::{push_skipping_definitions, skip_marks},
+    unittest::{is_test_case, is_test_method, push_unittest_definitions},
+    warnings::push_warnings_definitions,
+};
 
 /// A suite of tests from a single python source file.
 ///
+/// Tests are discovered using pytest's default `python_functions` and `python_classes`; use
+/// `TestSuite::try_from((src, &config))` to discover based upon a `Config`.
+///
 /// Usage:
 /// ```rust
 /// use pt::TestSuite;
@@ -44,26 +104,346 @@ use crate::{multiline::MultilineMut, pytests::TestDetails};
 #[derive(Debug, Default, PartialEq)]
 pub struct TestSuite {
     src: String,
-    /// indexed by test name, retains ordering from original python source
+    /// indexed by test name (`Class::method` for tests in classes), retains ordering from original
+    /// python source
     tests: IndexMap<String, TestDetails>,
+    /// fixtures defined in the module, by the name tests request them with, `Class::name` for
+    /// those defined in a class
+    fixtures: IndexMap<String, UserFixture>,
+    conftests: Vec<Conftest>,
+    warnings: Vec<CollectionWarning>,
 }
 
 impl TryFrom<String> for TestSuite {
     type Error = Error;
     fn try_from(src: String) -> Result<Self, Self::Error> {
-        let tests: IndexMap<String, TestDetails> = parse_module(&src)?
-            .into_suite()
-            .into_iter()
-            .filter_map(|stmt| -> Option<(String, TestDetails)> {
-                match stmt {
-                    Stmt::FunctionDef(function) if function.name.as_str().starts_with("test_") => {
-                        Some((function.name.to_string(), function.into()))
-                    }
-                    _ => None,
+        Self::try_from((src, &Config::default()))
+    }
+}
+
+impl TryFrom<(String, &Config)> for TestSuite {
+    type Error = Error;
+    fn try_from((src, config): (String, &Config)) -> Result<Self, Self::Error> {
+        Self::try_from((src, config, &[][..]))
+    }
+}
+
+/// Discover tests based upon a `Config`, with the fixtures of the `conftests` which apply to the
+/// module, outermost first; see `Config::conftests()`.
+impl TryFrom<(String, &Config, &[Conftest])> for TestSuite {
+    type Error = Error;
+    fn try_from(
+        (src, config, conftests): (String, &Config, &[Conftest]),
+    ) -> Result<Self, Self::Error> {
+        let module = parse_module(&src)?.into_suite();
+        let conftests = conftests
+            .iter()
+            .enumerate()
+            .map(|(index, conftest)| {
+                let mut conftest = conftest.clone();
+                for fixture in conftest.fixtures.values_mut() {
+                    fixture.defined_in = DefinedIn::Conftest(index);
                 }
+                conftest
             })
             .collect();
-        Ok(Self { src, tests })
+        let mut suite = Self {
+            src,
+            conftests,
+            ..Default::default()
+        };
+        suite.check_marks(&module, config);
+        let module_marks = InheritedMarks::module(&suite.src, &module);
+        // the unittest `TestCase` classes defined so far, which others may inherit, with their
+        // marks and test methods
+        let mut test_cases: IndexMap<String, (InheritedMarks, IndexMap<String, StmtFunctionDef>)> =
+            IndexMap::new();
+        for stmt in module {
+            suite.check_shadowing(&stmt, "");
+            match stmt {
+                Stmt::FunctionDef(function) => {
+                    suite.check_nested(&function, config);
+                    if let Some(fixture) = UserFixture::from_definition(&suite.src, &function)? {
+                        suite.check_scope(&function, &fixture.name);
+                        suite.fixtures.insert(fixture.name.clone(), fixture);
+                    } else if config.is_test_function(function.name.as_str()) {
+                        let testname = function.name.to_string();
+                        let mut details = TestDetails::from(function);
+                        suite.own_skip_marks(&mut details);
+                        module_marks.apply(&mut details);
+                        details.parametrize =
+                            parametrizations(&suite.src, &testname, &details.ast.decorator_list)?;
+                        suite.collect(testname, details);
+                    }
+                }
+                // as pytest, collected whatever their name, and even with an `__init__`
+                Stmt::ClassDef(class)
+                    if is_test_case(&class, |name| test_cases.contains_key(name)) =>
+                {
+                    // the test methods of bases defined in this module, the first base's taking
+                    // precedence as in the class's MRO
+                    let bases: Vec<_> = class
+                        .bases()
+                        .iter()
+                        .filter_map(|base| match base {
+                            Expr::Name(base) => test_cases.get(base.id.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    let marks = InheritedMarks::class(
+                        &suite.src,
+                        &class,
+                        bases.first().map_or(&module_marks, |(marks, _)| marks),
+                    );
+                    let prefix = format!("{}::", class.name);
+                    let collect = |suite: &mut TestSuite, method: StmtFunctionDef| {
+                        let testname = format!("{prefix}{}", method.name);
+                        let mut details = TestDetails::from(method);
+                        suite.own_skip_marks(&mut details);
+                        marks.apply(&mut details);
+                        // unittest passes no arguments, only `self`
+                        details.fixtures.clear();
+                        details.unittest = true;
+                        suite.collect(testname, details);
+                    };
+                    // inherited methods the class defines again
+                    let overridden: Vec<&str> = class
+                        .body
+                        .iter()
+                        .filter_map(|stmt| match stmt {
+                            Stmt::FunctionDef(method) => Some(method.name.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    let mut methods: IndexMap<String, StmtFunctionDef> = IndexMap::new();
+                    for (_, inherited) in bases.iter().rev() {
+                        methods.extend(inherited.clone());
+                    }
+                    methods.retain(|name, _| !overridden.contains(&name.as_str()));
+                    for method in methods.values() {
+                        collect(&mut suite, method.clone());
+                    }
+                    for stmt in class.body {
+                        suite.check_shadowing(&stmt, &prefix);
+                        if let Stmt::FunctionDef(method) = stmt
+                            && is_test_method(method.name.as_str())
+                        {
+                            suite.check_nested(&method, config);
+                            methods.insert(method.name.to_string(), method.clone());
+                            collect(&mut suite, method);
+                        }
+                    }
+                    test_cases.insert(class.name.to_string(), (marks, methods));
+                }
+                Stmt::ClassDef(class) if config.is_test_class(class.name.as_str()) => {
+                    // pytest does not collect classes with an `__init__`
+                    if class.body.iter().any(|stmt| {
+                        matches!(stmt, Stmt::FunctionDef(method) if method.name.as_str() == "__init__")
+                    }) {
+                        continue;
+                    }
+                    // marks on the method apply before those on the class, then the module
+                    let marks = InheritedMarks::class(&suite.src, &class, &module_marks);
+                    let prefix = format!("{}::", class.name);
+                    for stmt in class.body {
+                        suite.check_shadowing(&stmt, &prefix);
+                        if let Stmt::FunctionDef(method) = stmt {
+                            suite.check_nested(&method, config);
+                            if let Some(mut fixture) =
+                                UserFixture::from_definition(&suite.src, &method)?
+                            {
+                                // `self` is not a fixture
+                                if !fixture.fixtures.is_empty() {
+                                    fixture.fixtures.remove(0);
+                                }
+                                fixture.defined_in = DefinedIn::Class(class.name.to_string());
+                                suite.check_scope(&method, &fixture.name);
+                                suite
+                                    .fixtures
+                                    .insert(format!("{prefix}{}", fixture.name), fixture);
+                            } else if config.is_test_function(method.name.as_str()) {
+                                let testname = format!("{prefix}{}", method.name);
+                                let mut details = TestDetails::from(method);
+                                suite.own_skip_marks(&mut details);
+                                marks.apply(&mut details);
+                                // `self` is not a fixture
+                                if !details.fixtures.is_empty() {
+                                    details.fixtures.remove(0);
+                                }
+                                for decorators in
+                                    [&details.ast.decorator_list, &class.decorator_list]
+                                {
+                                    let parametrize =
+                                        parametrizations(&suite.src, &testname, decorators)?;
+                                    details.parametrize.extend(parametrize);
+                                }
+                                suite.collect(testname, details);
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        suite.expand_parametrized()?;
+        suite.warnings.sort_by_key(CollectionWarning::line);
+        Ok(suite)
+    }
+}
+
+/// Collection, with checks for tests which will not run as they appear to.
+impl TestSuite {
+    fn line(&self, offset: TextSize) -> usize {
+        self.src.as_str().line_no(&Location::Offset(offset.into()))
+    }
+
+    /// Add a test, warning if it replaces an earlier test of the same name.
+    fn collect(&mut self, testname: String, details: TestDetails) {
+        let line = self.line(details.ast.range.start());
+        if let Some(earlier) = self.tests.insert(testname.clone(), details) {
+            let first_line = self.line(earlier.ast.range.start());
+            self.warnings.push(CollectionWarning::Redefined {
+                name: testname,
+                first_line,
+                line,
+            });
+        }
+    }
+
+    /// Record the `skip`, `skipif` and `xfail` marks decorating a test, before those it inherits.
+    fn own_skip_marks(&self, details: &mut TestDetails) {
+        let decorators = details.ast.decorator_list.iter().rev();
+        details.skip_marks =
+            skip_marks(&self.src, decorators.map(|decorator| &decorator.expression));
+    }
+
+    /// Drop, with a warning, an already collected test which `stmt` replaces, as pytest never
+    /// sees it. `prefix` is `Class::` within a class.
+    fn check_shadowing(&mut self, stmt: &Stmt, prefix: &str) {
+        for name in bound_names(stmt) {
+            let testname = format!("{prefix}{name}");
+            if self.tests.shift_remove(&testname).is_some() {
+                let line = self.line(stmt.start());
+                self.warnings.push(CollectionWarning::Shadowed {
+                    name: testname,
+                    line,
+                });
+            }
+        }
+    }
+
+    /// Replace each parametrized test, or test using a fixture with `params`, with a test per case,
+    /// named `testname[id]`. A test with no cases is not run, as pytest. A test whose cases pt
+    /// cannot find is kept, unexpanded, and fails when it runs.
+    fn expand_parametrized(&mut self) -> Result<(), Error> {
+        let mut expanded = IndexMap::new();
+        for (testname, details) in std::mem::take(&mut self.tests) {
+            let invalid = |msg: String| Error::InvalidParametrize(format!("In {testname}: {msg}"));
+            for argname in details.direct_argnames() {
+                if !details.fixtures.contains(argname) {
+                    return Err(invalid(format!("function uses no argument '{argname}'")));
+                }
+            }
+            let parametrized: Vec<&String> = details
+                .parametrize
+                .iter()
+                .flat_map(|parametrization| &parametrization.argnames)
+                .collect();
+            let class = testname.split_once("::").map(|(class, _)| class);
+            for parametrization in &details.parametrize {
+                for argname in &parametrization.indirect {
+                    if self.fixture(class, argname).is_none() {
+                        return Err(invalid(format!("function uses no fixture '{argname}'")));
+                    }
+                }
+            }
+            // a fixture which is parametrized directly by the test has no params of its own; an
+            // unknown fixture fails the test when it runs
+            let fixture_params: Vec<_> = self
+                .resolve_fixtures(&testname, &details)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|fixture| match fixture {
+                    Fixture::User(fixture) if !parametrized.contains(&&fixture.name) => {
+                        fixture.params.as_ref()
+                    }
+                    _ => None,
+                })
+                .collect();
+            if details.parametrize.is_empty() && fixture_params.is_empty() {
+                expanded.insert(testname, details);
+                continue;
+            }
+            let parametrizations: Vec<_> = fixture_params
+                .into_iter()
+                .chain(&details.parametrize)
+                .collect();
+            // the test is kept, to fail when it runs, rather than the whole module
+            if let Some(reason) = parametrizations
+                .iter()
+                .find_map(|parametrization| parametrization.unexpanded.as_ref())
+            {
+                let mut details = details.case(Vec::new());
+                details.unexpanded = Some(format!("In {testname}: {reason}"));
+                expanded.insert(testname, details);
+                continue;
+            }
+            for combination in combinations(&parametrizations) {
+                let mut case = details.case(combination.values);
+                case.markers.extend(combination.markers);
+                case.skip_marks.extend(combination.skip_marks);
+                expanded.insert(format!("{testname}[{}]", combination.id), case);
+            }
+        }
+        self.tests = expanded;
+        Ok(())
+    }
+
+    /// Warn about each use of a mark which is not registered, as pytest.
+    fn check_marks(&mut self, module: &[Stmt], config: &Config) {
+        let mut unknown = UnknownMarks {
+            is_registered: |name: &str| config.is_registered_marker(name),
+            bare_mark: module
+                .iter()
+                .any(|stmt| bound_names(stmt).contains(&"mark")),
+            found: Vec::new(),
+        };
+        unknown.visit_body(module);
+        for (name, expr) in unknown.found {
+            let line = self.line(expr.start());
+            self.warnings.push(CollectionWarning::UnknownMark {
+                name: name.to_string(),
+                line,
+            });
+        }
+    }
+
+    /// Warn about a fixture defined by `function` with a scope pt cannot keep its value for.
+    fn check_scope(&mut self, function: &StmtFunctionDef, name: &str) {
+        if let Some((scope, offset)) = ignored_scope(function) {
+            let line = self.line(offset);
+            self.warnings.push(CollectionWarning::IgnoredScope {
+                name: name.to_string(),
+                scope,
+                line,
+            });
+        }
+    }
+
+    /// Warn about tests defined inside `function`, which pytest never collects.
+    fn check_nested(&mut self, function: &StmtFunctionDef, config: &Config) {
+        let mut nested = NestedTests {
+            is_test: |name: &str| config.is_test_function(name),
+            found: Vec::new(),
+        };
+        nested.visit_body(&function.body);
+        for test in nested.found {
+            let line = self.line(test.range.start());
+            self.warnings.push(CollectionWarning::Nested {
+                name: test.name.to_string(),
+                line,
+            });
+        }
     }
 }
 
@@ -71,26 +451,582 @@ impl TestSuite {
     /// Generate a test runner which can be run with python and will execute all the tests.
     /// `id` is a unique ID which will be used in the output generated by python when executing the runner.
     pub fn runner<ID: AsRef<str>>(&self, id: ID) -> String {
-        let mut test_runner = self.src.clone();
-        test_runner.push_newline();
-        test_runner.push_newline();
+        self.runner_with(id, &RunnerOptions::default())
+    }
+
+    /// Generate a test runner, as `runner()`, customised by `options`.
+    pub fn runner_with<ID: AsRef<str>>(&self, id: ID, options: &RunnerOptions) -> String {
+        let maxfail = options.maxfail.map(|maxfail| maxfail.to_string());
+        let legacy_python = options
+            .python
+            .is_some_and(|version| !version.has_sys_exception());
+        let mut test_runner = String::new();
+        if options.module.is_none() {
+            test_runner.push_str(&self.src);
+            test_runner.push_newline();
+            test_runner.push_newline();
+        }
         test_runner.push_python_line(0, ["if __name__ == \"__main__\":"]);
         test_runner.push_python_line(1, ["from traceback import TracebackException"]);
         test_runner.push_python_line(1, ["import sys"]);
-        self.tests.keys().for_each(|testname| {
-            test_runner.push_newline();
-            test_runner.push_python_line(1, ["print(\"", id.as_ref(), " ", testname, " RUNNING\")"]);
-            test_runner.push_python_line( 1, ["try:"]);
-            test_runner.push_python_line( 2, [testname, "()"]);
-            test_runner.push_python_line( 1, ["except Exception:"]);
-            test_runner.push_python_line(2,["TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)"]);
-            test_runner.push_python_line(2,["print(\"", id.as_ref(), " ", testname, " FAIL\")"]);
-            test_runner.push_python_line(1, ["else:"]);
-            test_runner.push_python_line(2,["print(\"", id.as_ref(), " ", testname, " PASS\")"]);
-        });
+        push_outcome_definition(&mut test_runner, 1);
+        push_sources_definition(&mut test_runner, 1);
+        // as pytest, conftests are imported before the test module when it is imported
+        for (index, conftest) in self.conftests.iter().enumerate() {
+            let variable = format!("_pt_conftest_{index}");
+            conftest.module.push_import(&mut test_runner, 1, &variable);
+        }
+        // tests are called on the imported module, the harness's own frame is removed from
+        // tracebacks so they start in the test file
+        let (prefix, drop_frame) = match &options.module {
+            Some(module) => {
+                module.push_import(&mut test_runner, 1, "_pt_module");
+                ("_pt_module.", true)
+            }
+            None => ("", false),
+        };
+        let exception = match (drop_frame, legacy_python) {
+            (true, _) => "_pt_exc.with_traceback(_pt_exc.__traceback__.tb_next)",
+            (false, true) => "sys.exc_info()[1]",
+            (false, false) => "sys.exception()",
+        };
+        // once its traceback is printed, without the harness's frame
+        let printed_exception = match drop_frame {
+            true => "_pt_exc",
+            false => exception,
+        };
+        let fixtures: Vec<_> = self
+            .tests
+            .iter()
+            .map(|(testname, details)| match &details.unexpanded {
+                Some(reason) => Err(Error::InvalidParametrize(reason.clone())),
+                None => self.resolve_fixtures(testname, details),
+            })
+            .collect();
+        // as pytest, `capsys` & `capfd` work with `--capture=no`
+        let capture = options.capture.method();
+        if capture.is_some()
+            || fixtures.iter().flatten().flatten().any(
+                |fixture| matches!(fixture, Fixture::Builtin(builtin) if builtin.captures_output()),
+            )
+        {
+            push_capture_definitions(&mut test_runner, 1);
+        }
+        if fixtures.iter().flatten().flatten().any(Fixture::needs_node) {
+            push_request_definitions(&mut test_runner, 1, &options.config_options);
+        }
+        if let Some(method) = capture {
+            test_runner.push_python_line(1, ["_pt_capture = _PtCapture(\"", method, "\")"]);
+        }
+        push_warnings_definitions(
+            &mut test_runner,
+            1,
+            &options.filterwarnings,
+            &options.pythonwarnings,
+        );
+        // the class of each test which is a method of a unittest `TestCase`
+        let test_cases: Vec<Option<&str>> = self
+            .tests
+            .iter()
+            .map(|(testname, details)| match details.unittest {
+                true => testname.split_once("::").map(|(class, _)| class),
+                false => None,
+            })
+            .collect();
+        if test_cases.iter().any(Option::is_some) {
+            push_unittest_definitions(&mut test_runner, 1);
+        }
+        if self
+            .tests
+            .values()
+            .any(|details| !details.skip_marks.is_empty())
+        {
+            push_skipping_definitions(&mut test_runner, 1);
+        }
+        let mut session: Vec<Builtin> = Vec::new();
+        for fixture in fixtures.iter().flatten().flatten() {
+            if let Fixture::Builtin(fixture) = fixture
+                && !session.contains(fixture)
+            {
+                fixture.push_session_setup(&mut test_runner, 1, options.basetemp.as_deref());
+                session.push(*fixture);
+            }
+        }
+        if maxfail.is_some() {
+            test_runner.push_python_line(1, ["_pt_failures = 0"]);
+        }
+        // a value kept beyond its test is torn down after the last test using it
+        let mut last_use = HashMap::new();
+        for (index, ((testname, details), fixtures)) in self.tests.iter().zip(&fixtures).enumerate()
+        {
+            for fixture in fixtures.iter().flatten() {
+                if let Some(key) =
+                    fixture.cache_key(testname, details.fixture_param(fixture.name()))
+                {
+                    last_use.insert(key, index);
+                }
+            }
+        }
+        self.tests.iter().zip(fixtures).enumerate().for_each(
+            |(index, ((testname, details), fixtures))| {
+                // parametrized test names may include quotes
+                let output =
+                    |status: &str| python_str(&format!("{} {testname}{status}", id.as_ref()));
+                test_runner.push_newline();
+                // `setUpClass` runs before the first of a unittest class's tests, `tearDownClass`
+                // after the last
+                let test_case = test_cases[index];
+                if let Some(class) = test_case
+                    && (index == 0 || test_cases[index - 1] != test_case)
+                {
+                    test_runner.push_python_line(
+                        1,
+                        ["_pt_class_error = _pt_set_up_class(", prefix, class, ")"],
+                    );
+                }
+                let tear_down_class =
+                    test_case.is_some() && test_cases.get(index + 1) != Some(&test_case);
+                test_runner.push_python_line(1, ["print(", &output(" RUNNING"), ")"]);
+                if capture.is_some() {
+                    test_runner.push_python_line(1, ["_pt_capture.start()"]);
+                }
+                let filters: Vec<String> = details
+                    .filterwarnings
+                    .iter()
+                    .map(|filter| python_str(filter))
+                    .collect();
+                test_runner.push_python_line(1, ["try:"]);
+                let body = match tear_down_class {
+                    true => {
+                        test_runner.push_python_line(2, ["try:"]);
+                        3
+                    }
+                    false => 2,
+                };
+                // an invalid `filterwarnings` mark fails the test
+                test_runner
+                    .push_python_line(body, ["_pt_warnings.start([", &filters.join(", "), "])"]);
+                // parameter values and marks are python source, evaluated in the test module
+                let value = |value: &str| match &options.module {
+                    Some(_) => format!("eval({}, _pt_module.__dict__)", python_str(value)),
+                    None => value.to_string(),
+                };
+                let xfail = details.skip_marks.iter().any(|mark| mark.xfail);
+                if !details.skip_marks.is_empty() {
+                    let marks: Vec<String> = details
+                        .skip_marks
+                        .iter()
+                        .map(|mark| value(&mark.source))
+                        .collect();
+                    let namespace = match &options.module {
+                        Some(_) => "_pt_module.__dict__",
+                        None => "globals()",
+                    };
+                    let strict = match options.xfail_strict {
+                        true => "True",
+                        false => "False",
+                    };
+                    let assign = if xfail { "_pt_xfail = " } else { "" };
+                    test_runner.push_python_line(
+                        body,
+                        [
+                            assign,
+                            "_pt_skipping([",
+                            &marks.join(", "),
+                            "], ",
+                            namespace,
+                            ", ",
+                            strict,
+                            ")",
+                        ],
+                    );
+                }
+                // an `xfail` mark turns the test's failure, in setup or teardown too, into the
+                // expected failure, and its success into an unexpected pass
+                let body = match xfail {
+                    true => {
+                        test_runner.push_python_line(body, ["try:"]);
+                        body + 1
+                    }
+                    false => body,
+                };
+                match fixtures {
+                    // as pytest, the test is not run
+                    Err(err) => {
+                        let exception = match err {
+                            Error::InvalidParametrize(_) => "NotImplementedError(",
+                            _ => "LookupError(",
+                        };
+                        let message = python_str(&err.to_string());
+                        test_runner.push_python_line(body, ["raise ", exception, &message, ")"]);
+                    }
+                    Ok(fixtures) => {
+                        let method = details.ast.name.as_str();
+                        let (function, class) = match testname.split_once("::") {
+                            Some((class, _)) => (
+                                format!("{prefix}{class}.{method}"),
+                                format!("{prefix}{class}"),
+                            ),
+                            None => (format!("{prefix}{method}"), "None".to_string()),
+                        };
+                        if fixtures.iter().any(Fixture::needs_node) {
+                            let nodeid = match &options.location {
+                                Some(location) => format!("{location}::{testname}"),
+                                None => testname.to_string(),
+                            };
+                            let markers: Vec<String> = details
+                                .markers
+                                .iter()
+                                .map(|name| python_str(name))
+                                .collect();
+                            test_runner.push_python_line(
+                                body,
+                                [
+                                    "_pt_node = _PtNode(",
+                                    &python_str(testname.rsplit("::").next().unwrap_or(testname)),
+                                    ", ",
+                                    &python_str(&nodeid),
+                                    ", ",
+                                    &function,
+                                    ", ",
+                                    &class,
+                                    ", [",
+                                    &markers.join(", "),
+                                    "])",
+                                ],
+                            );
+                        }
+                        // fixtures defined in the class are called on the test's own instance, a
+                        // unittest test case is created for its test method
+                        if let Some((class, _)) = testname.split_once("::") {
+                            let method = match details.unittest {
+                                true => python_str(method),
+                                false => String::new(),
+                            };
+                            test_runner.push_python_line(
+                                body,
+                                ["_pt_self = ", prefix, class, "(", &method, ")"],
+                            );
+                        }
+                        // any fixture may be requested by a name only known as the test runs
+                        if details.runtime_fixtures
+                            || fixtures.iter().any(|fixture| {
+                                matches!(
+                                    fixture,
+                                    Fixture::User(fixture) if fixture.runtime_fixtures
+                                )
+                            })
+                        {
+                            let class = testname.split_once("::").map(|(class, _)| class);
+                            let available: Vec<String> = self
+                                .available_fixtures(class)
+                                .into_iter()
+                                .map(|(name, fixture)| {
+                                    format!(
+                                        "{}: {}",
+                                        python_str(name),
+                                        fixture.python_function(prefix)
+                                    )
+                                })
+                                .collect();
+                            test_runner.push_python_line(
+                                body,
+                                ["_pt_node.fixtures = {", &available.join(", "), "}"],
+                            );
+                        }
+                        for (argname, param) in &details.params {
+                            test_runner
+                                .push_python_line(body, ["_pt_", argname, " = ", &value(param)]);
+                        }
+                        // each fixture which is set up is torn down, even if the test fails
+                        let mut indent = body;
+                        let mut teardown = Vec::new();
+                        for (position, fixture) in fixtures.iter().enumerate() {
+                            let param = details.fixture_param(fixture.name());
+                            let cache_key = fixture.cache_key(testname, param);
+                            fixture.push_setup(
+                                &mut test_runner,
+                                indent,
+                                testname,
+                                &fixtures[..position],
+                                param.map(value).as_deref(),
+                            );
+                            let has_teardown = match &cache_key {
+                                Some(key) => last_use.get(key) == Some(&index),
+                                None => fixture.has_teardown(),
+                            };
+                            if has_teardown {
+                                test_runner.push_python_line(indent, ["try:"]);
+                                teardown.push((indent, fixture, cache_key.clone()));
+                                indent += 1;
+                            }
+                            fixture.push_execute(
+                                &mut test_runner,
+                                indent,
+                                prefix,
+                                cache_key.as_deref(),
+                            );
+                        }
+                        let arguments: Vec<String> = details
+                            .fixtures
+                            .iter()
+                            .map(|name| format!("{name}=_pt_{name}"))
+                            .collect();
+                        let arguments = arguments.join(", ");
+                        let instance = match testname.contains("::") {
+                            true => "_pt_self.",
+                            false => prefix,
+                        };
+                        if details.unittest {
+                            test_runner.push_python_line(
+                                indent,
+                                [
+                                    "_pt_unittest_outcome = ",
+                                    "_pt_run_unittest(_pt_self, _pt_class_error)",
+                                ],
+                            );
+                            test_runner
+                                .push_python_line(indent, ["if _pt_unittest_outcome is not None:"]);
+                            test_runner
+                                .push_python_line(indent + 1, ["raise _pt_unittest_outcome"]);
+                        } else {
+                            test_runner
+                                .push_python_line(indent, [instance, method, "(", &arguments, ")"]);
+                        }
+                        for (indent, fixture, cache_key) in teardown.into_iter().rev() {
+                            test_runner.push_python_line(indent, ["finally:"]);
+                            fixture.push_teardown(
+                                &mut test_runner,
+                                indent + 1,
+                                cache_key.as_deref(),
+                            );
+                        }
+                    }
+                }
+                if xfail {
+                    test_runner.push_python_line(
+                        body - 1,
+                        ["except (Exception, *_pt_outcome(\"fail\")) as _pt_exc:"],
+                    );
+                    test_runner.push_python_line(body, ["_pt_xfailed(_pt_xfail, _pt_exc)"]);
+                    test_runner.push_python_line(body, ["raise"]);
+                    test_runner.push_python_line(body - 1, ["_pt_xpassed(_pt_xfail)"]);
+                }
+                if let Some(class) = test_case
+                    && tear_down_class
+                {
+                    test_runner.push_python_line(2, ["finally:"]);
+                    test_runner.push_python_line(
+                        3,
+                        ["_pt_tear_down_class(", prefix, class, ", _pt_class_error)"],
+                    );
+                }
+                // the reason for a skip, xfail or unexpected pass is output before the status
+                let mut outcomes = vec![
+                    ("_pt_outcome(\"skip\")", " SKIP"),
+                    ("_pt_outcome(\"xfail\")", " XFAIL"),
+                ];
+                if xfail {
+                    outcomes.push(("_PtXPassed", " XPASS"));
+                }
+                for (exceptions, status) in outcomes {
+                    test_runner.push_python_line(1, ["except ", exceptions, " as _pt_exc:"]);
+                    if capture.is_some() {
+                        test_runner.push_python_line(2, ["_pt_capture.stop()"]);
+                    }
+                    test_runner.push_python_line(2, ["print(", &output(" REPORT"), ")"]);
+                    test_runner.push_python_line(2, ["print(_pt_exc)"]);
+                    test_runner.push_python_line(2, ["_pt_warnings.stop(", &output(""), ")"]);
+                    test_runner.push_python_line(2, ["print(", &output(status), ")"]);
+                }
+                match drop_frame {
+                    true => test_runner.push_python_line(
+                        1,
+                        ["except (Exception, *_pt_outcome(\"fail\")) as _pt_exc:"],
+                    ),
+                    false => test_runner
+                        .push_python_line(1, ["except (Exception, *_pt_outcome(\"fail\")):"]),
+                }
+                // output is reported after the traceback, so capturing stops first
+                if capture.is_some() {
+                    test_runner.push_python_line(2, ["_pt_captured = _pt_capture.stop()"]);
+                }
+                test_runner.push_python_line(2, ["print(", &output(" REPORT"), ")"]);
+                if legacy_python {
+                    test_runner.push_python_line(
+                        2,
+                        [
+                            "print(\"\".join(TracebackException.from_exception(",
+                            exception,
+                            ", capture_locals=True).format()), end=\"\")",
+                        ],
+                    );
+                } else {
+                    test_runner.push_python_line(
+                        2,
+                        [
+                            "TracebackException.from_exception(",
+                            exception,
+                            ", capture_locals=True).print(file=sys.stdout)",
+                        ],
+                    );
+                }
+                test_runner.push_python_line(
+                    2,
+                    [
+                        "_pt_print_sources(",
+                        &output(""),
+                        ", ",
+                        printed_exception,
+                        ", ",
+                        prefix,
+                        "__file__)",
+                    ],
+                );
+                if capture.is_some() {
+                    test_runner.push_python_line(
+                        2,
+                        ["_pt_print_captured(", &output(""), ", _pt_captured)"],
+                    );
+                }
+                test_runner.push_python_line(2, ["_pt_warnings.stop(", &output(""), ")"]);
+                test_runner.push_python_line(2, ["print(", &output(" FAIL"), ")"]);
+                if let Some(maxfail) = &maxfail {
+                    test_runner.push_python_line(2, ["_pt_failures += 1"]);
+                    test_runner.push_python_line(2, ["if _pt_failures >= ", maxfail, ":"]);
+                    test_runner.push_python_line(3, ["sys.exit()"]);
+                }
+                // as pytest, Ctrl+C stops the run, reporting where the test was interrupted
+                test_runner.push_python_line(1, ["except KeyboardInterrupt as _pt_exc:"]);
+                if capture.is_some() {
+                    test_runner.push_python_line(2, ["_pt_capture.stop()"]);
+                }
+                test_runner.push_python_line(2, ["print(", &output(" REPORT"), ")"]);
+                test_runner.push_python_line(
+                    2,
+                    ["_pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]"],
+                );
+                test_runner
+                    .push_python_line(2, ["print(f\"{_pt_frame.filename}:{_pt_frame.lineno}\")"]);
+                test_runner.push_python_line(2, ["print(", &output(" INTERRUPTED"), ")"]);
+                test_runner.push_python_line(2, ["sys.exit()"]);
+                test_runner.push_python_line(1, ["else:"]);
+                if capture.is_some() {
+                    test_runner.push_python_line(2, ["_pt_capture.stop()"]);
+                }
+                test_runner.push_python_line(2, ["_pt_warnings.stop(", &output(""), ")"]);
+                test_runner.push_python_line(2, ["print(", &output(" PASS"), ")"]);
+            },
+        );
         test_runner
     }
 
+    /// Python which runs only the module's top-level imports, each on its original line, to find
+    /// import errors before any tests run. Run it as an `Inline` runner.
+    pub fn import_check(&self) -> String {
+        let mut check = String::new();
+        let Ok(module) = parse_module(&self.src) else {
+            return check;
+        };
+        let mut lines = 1;
+        for stmt in module.suite() {
+            if !matches!(stmt, Stmt::Import(_) | Stmt::ImportFrom(_)) {
+                continue;
+            }
+            let line = self.line(stmt.start());
+            check.extend(std::iter::repeat_n('\n', line.saturating_sub(lines)));
+            let import = &self.src[stmt.range()];
+            check.push_line(0, [import]);
+            lines = line + import.lines().count();
+        }
+        check
+    }
+
+    /// Whether any test uses `tmp_path` or another fixture needing `RunnerOptions::basetemp`.
+    pub fn requires_basetemp(&self) -> bool {
+        self.tests.iter().any(|(testname, details)| {
+            self.resolve_fixtures(testname, details)
+                .is_ok_and(|fixtures| fixtures.contains(&Fixture::Builtin(Builtin::TmpPathFactory)))
+        })
+    }
+
+    /// The fixtures to set up for a test, in order, ending with its own `request` if it has one.
+    fn resolve_fixtures<'a>(
+        &'a self,
+        testname: &str,
+        details: &'a TestDetails,
+    ) -> Result<Vec<Fixture<'a>>, Error> {
+        let class = testname.split_once("::").map(|(class, _)| class);
+        let requested = details.requested(self.autouse(class));
+        let mut fixtures = fixtures::resolve(requested, &|name| self.fixture(class, name))?;
+        if details.fixtures.iter().any(|name| name == "request") {
+            fixtures.push(Fixture::Request);
+        }
+        Ok(fixtures)
+    }
+
+    /// The fixture `name` available to tests in `class`, if one is defined: in the class, the
+    /// module or the closest conftest.
+    fn fixture(&self, class: Option<&str>, name: &str) -> Option<&UserFixture> {
+        class
+            .and_then(|class| self.fixtures.get(&format!("{class}::{name}")))
+            .or_else(|| self.fixtures.get(name))
+            .or_else(|| {
+                self.conftests
+                    .iter()
+                    .rev()
+                    .find_map(|conftest| conftest.fixtures.get(name))
+            })
+    }
+
+    /// The fixtures available to tests in `class`, by name, as `fixture()` finds them.
+    fn available_fixtures(&self, class: Option<&str>) -> IndexMap<&str, &UserFixture> {
+        self.conftests
+            .iter()
+            .flat_map(|conftest| conftest.fixtures.values())
+            .chain(self.fixtures.values())
+            .filter_map(|fixture| {
+                let name = fixture.name.as_str();
+                self.fixture(class, name).map(|fixture| (name, fixture))
+            })
+            .collect()
+    }
+
+    /// Names of the `autouse` fixtures available to tests in `class`, set up before any others:
+    /// those in conftests, then the module, then the class.
+    fn autouse(&self, class: Option<&str>) -> Vec<&str> {
+        let conftests = self
+            .conftests
+            .iter()
+            .flat_map(|conftest| conftest.fixtures.values());
+        let module = self
+            .fixtures
+            .values()
+            .filter(|fixture| fixture.defined_in == DefinedIn::Module);
+        let class = self.fixtures.values().filter(|fixture| {
+            matches!(&fixture.defined_in, DefinedIn::Class(defined_in) if Some(defined_in.as_str()) == class)
+        });
+        conftests
+            .chain(module)
+            .chain(class)
+            .filter(|fixture| fixture.autouse)
+            .map(|fixture| fixture.name.as_str())
+            .collect()
+    }
+
+    /// Likely mistakes found while collecting the tests, e.g. a test defined twice.
+    pub fn warnings(&self) -> &[CollectionWarning] {
+        &self.warnings
+    }
+
+    /// Number of failed tests.
+    pub fn failures(&self) -> usize {
+        self.tests
+            .values()
+            .filter(|test| matches!(test.status, TestStatus::Fail(_, _)))
+            .count()
+    }
+
     /// Returns a PythonTest with non-mutable references to both the full python source text and the
     /// test details.
     pub fn test<'suite, 'name>(
@@ -103,6 +1039,10 @@ impl TestSuite {
             full_src: &self.src,
             test_ast: &testdetails.ast,
             status: &testdetails.status,
+            markers: &testdetails.markers,
+            captured: &testdetails.captured,
+            warnings: &testdetails.warnings,
+            sources: &testdetails.sources,
         })
     }
 
@@ -113,30 +1053,131 @@ impl TestSuite {
             full_src: &self.src,
             test_ast: &testdetails.ast,
             status: &testdetails.status,
+            markers: &testdetails.markers,
+            captured: &testdetails.captured,
+            warnings: &testdetails.warnings,
+            sources: &testdetails.sources,
         })
     }
 
+    /// Remove tests which do not match a `-k` expression. Identifiers in the expression match
+    /// case-insensitive substrings of the test name (including class name) or a marker name.
+    /// Returns the number of deselected tests.
+    pub fn deselect_by_keyword(&mut self, keyword: &str) -> Result<usize, Error> {
+        let expression = Expression::try_from(keyword)?;
+        Ok(self.deselect(|testname, details| {
+            expression.evaluate(&|ident| {
+                let ident = ident.to_lowercase();
+                testname.to_lowercase().contains(&ident)
+                    || details
+                        .markers
+                        .iter()
+                        .any(|marker| marker.to_lowercase() == ident)
+            })
+        }))
+    }
+
+    /// Remove tests which do not match a `-m` expression. Identifiers in the expression match
+    /// marker names. Returns the number of deselected tests.
+    pub fn deselect_by_marker(&mut self, markexpr: &str) -> Result<usize, Error> {
+        let expression = Expression::try_from(markexpr)?;
+        Ok(self.deselect(|_, details| {
+            expression.evaluate(&|ident| details.markers.iter().any(|marker| marker == ident))
+        }))
+    }
+
+    /// Remove tests for which `keep(testname)` is false. Returns the number of deselected tests.
+    pub fn deselect_by_name(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        self.deselect(|testname, _| keep(testname))
+    }
+
+    /// Reorder tests so that those for which `first(testname)` is true run first, otherwise
+    /// retaining the original order.
+    pub fn prioritise(&mut self, first: impl Fn(&str) -> bool) {
+        self.tests
+            .sort_by(|name1, _, name2, _| first(name2).cmp(&first(name1)));
+    }
+
+    /// Retain only the tests for which `keep` is true, returns the number of deselected tests.
+    fn deselect(&mut self, keep: impl Fn(&str, &TestDetails) -> bool) -> usize {
+        let before = self.tests.len();
+        self.tests
+            .retain(|testname, details| keep(testname, details));
+        before - self.tests.len()
+    }
+
+    /// Mark every test skipped, as when the module skips itself while it is imported.
+    pub fn skip(&mut self, reason: &str) {
+        for details in self.tests.values_mut() {
+            details.status = TestStatus::Skip(reason.to_string());
+        }
+    }
+
     /// Parse the output generated by executing the code from `runner()` and update status of all tests.
     /// This is potentially fallible (if the inputs are invalid). In case of error this will leave the
     /// TestSuite in an **undefined state**.
+    ///
+    /// The traceback of a failed test, or the reason for a skip, follows a
+    /// `<id> <testname> REPORT` line, anything before it is output which was not captured.
+    /// Output captured from a failed test follows its traceback, after a
+    /// `<id> <testname> CAPTURED <stdout|stderr>` line for each stream, then each warning the test
+    /// emitted, after a `<id> <testname> WARNING` line.
     pub fn update_status(&mut self, id: &str, stdout: &str) -> Result<(), Error> {
         let mut tb_buf = String::new();
+        // `(section, output)`, a stream or `WARNING`
+        let mut captured: Vec<(String, String)> = Vec::new();
         for line in stdout.lines() {
-            let mut words = line.split_whitespace();
-            match words.next() {
-                Some(id_) if id_ == id => {
-                    let testname = words.next().ok_or(Error::InvalidOutput(line.to_string()))?;
+            // parametrized test names may include spaces, the status is always the last word
+            match line
+                .strip_prefix(id)
+                .and_then(|line| line.strip_prefix(' '))
+            {
+                Some(output) => {
+                    let (testname, status) = output
+                        .rsplit_once(' ')
+                        .ok_or(Error::InvalidOutput(line.to_string()))?;
+                    if let Some((testname, "CAPTURED")) = testname.rsplit_once(' ')
+                        && self.tests.contains_key(testname)
+                    {
+                        captured.push((status.to_string(), String::new()));
+                        continue;
+                    }
+                    // output the test wrote without capturing precedes its report
+                    if status == "REPORT" && self.tests.contains_key(testname) {
+                        tb_buf.clear();
+                        continue;
+                    }
+                    if matches!(status, "WARNING" | "SOURCE") && self.tests.contains_key(testname) {
+                        captured.push((status.to_string(), String::new()));
+                        continue;
+                    }
                     let test = self
                         .tests
                         .get_mut(testname)
                         .ok_or(Error::InvalidOutput(line.to_string()))?;
-                    let status = words.next().ok_or(Error::InvalidOutput(line.to_string()))?;
                     test.status = (status, tb_buf.as_str()).try_into()?;
+                    let (warnings, captured): (Vec<_>, Vec<_>) = std::mem::take(&mut captured)
+                        .into_iter()
+                        .partition(|(section, _)| section == "WARNING");
+                    let (sources, captured) = captured
+                        .into_iter()
+                        .partition(|(section, _)| section == "SOURCE");
+                    test.captured = captured;
+                    test.warnings = warnings.into_iter().map(|(_, warning)| warning).collect();
+                    // the file name, then its source
+                    test.sources = sources
+                        .into_iter()
+                        .filter_map(|(_, source)| {
+                            let (file, src) = source.split_once('\n')?;
+                            Some((file.to_string(), src.to_string()))
+                        })
+                        .collect();
                     tb_buf.clear();
                 }
-                _ => {
-                    tb_buf.push_line(0, [line]);
-                }
+                _ => match captured.last_mut() {
+                    Some((_, output)) => output.push_line(0, [line]),
+                    None => tb_buf.push_line(0, [line]),
+                },
             }
         }
         Ok(())
@@ -144,11 +1185,16 @@ impl TestSuite {
 
     /// List all tests with status, then provide failure reports for all failed tests.
     pub fn summary_report(&self) -> String {
+        self.summary_report_with(&ReportOptions::default())
+    }
+
+    /// Summary report, as `summary_report()`, with failure reports in the given style.
+    pub fn summary_report_with(&self, options: &ReportOptions) -> String {
         let mut summary = String::new();
         let mut details = String::new();
         for test in self.tests() {
             summary.push_line(0, [test.testname, " ", test.status.as_str()]);
-            if let Some(report) = test.report() {
+            if let Some(report) = test.report_with(options) {
                 details.push_newline();
                 details.push_str(&report);
             }
@@ -156,6 +1202,48 @@ impl TestSuite {
         summary.push_str(&details);
         summary
     }
+
+    /// pytest's "short test summary info" line for each failed test:
+    /// `FAILED <location>::<testname> - <exception>`.
+    pub fn short_summary(&self, location: &str) -> String {
+        let mut summary = String::new();
+        for test in self.tests() {
+            if let TestStatus::Fail(exception, _) = test.status {
+                let nodeid = format!("{location}::{}", test.testname);
+                summary.push_line(0, ["FAILED ", &nodeid, " - ", &exception.summary()]);
+            }
+        }
+        summary
+    }
+}
+
+/// Options affecting the code generated by `TestSuite::runner_with()`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunnerOptions {
+    /// Stop executing after this many failures, remaining tests will stay `TestStatus::NoRun`.
+    pub maxfail: Option<usize>,
+    /// Generate code for this python version, defaults to the newest supported version.
+    pub python: Option<PythonVersion>,
+    /// Import the tests from this module's file rather than including the source in the runner.
+    pub module: Option<TestModule>,
+    /// Directory for `tmp_path` fixtures, see `BaseTemp`. A new temporary directory is used if
+    /// this is not set.
+    pub basetemp: Option<PathBuf>,
+    /// How output written by each test is captured, to be reported if the test fails.
+    pub capture: Capture,
+    /// Path of the module relative to `rootdir`, for the node ids `request.node.nodeid` returns.
+    pub location: Option<String>,
+    /// Values `request.config.getoption()` returns, by option name, as python expressions. See
+    /// `Args::options()`.
+    pub config_options: Vec<(String, String)>,
+    /// Warning filters from the `filterwarnings` ini option, as regular expressions.
+    pub filterwarnings: Vec<String>,
+    /// Warning filters given with `-W`, matched literally. These take precedence over
+    /// `filterwarnings`, and `filterwarnings` marks over both.
+    pub pythonwarnings: Vec<String>,
+    /// Whether an `xfail` marked test which passes fails, unless the mark gives `strict`; the
+    /// `xfail_strict` ini option.
+    pub xfail_strict: bool,
 }
 
 #[derive(Debug)]
@@ -164,6 +1252,106 @@ pub enum Error {
     InvalidStatus(String),
     InvalidOutput(String),
     InvalidPython(ParseError),
+    InvalidConfig(String),
+    InvalidArgument(String),
+    /// A `@pytest.mark.parametrize` or fixture `params` which pt cannot expand
+    InvalidParametrize(String),
+    /// A test requests a fixture, by this name, which is neither pt's nor defined by the tests
+    FixtureNotFound(String),
+    /// A fixture, by this name, requires itself, directly or through the fixtures it requests
+    RecursiveFixture(String),
+    /// A fixture requests one whose value is not kept as long as its own, this message says which
+    ScopeMismatch(String),
+    /// No python interpreter could be run
+    PythonNotFound(String),
+    /// The python interpreter's version is not supported
+    UnsupportedPython(String),
+    /// Any of the above, which occurred in the file at `path`; see `Error::in_file()`
+    InFile {
+        path: PathBuf,
+        line: Option<usize>,
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Add the file, and line if known, in which this error occurred.
+    pub fn in_file(self, path: impl Into<PathBuf>, line: Option<usize>) -> Self {
+        Self::InFile {
+            path: path.into(),
+            line,
+            error: Box::new(self),
+        }
+    }
+
+    /// Render this error with the offending span of `src`, the contents of the file it occurred
+    /// in, underlined:
+    /// ```text
+    /// tests/test_it.py:4:14: Expected ')', found ':'
+    /// 4: def test_two(:
+    ///                 ^
+    /// ```
+    pub fn diagnostic(&self, src: &str) -> String {
+        let (path, error) = match self {
+            Self::InFile { path, error, .. } => (Some(path), error.as_ref()),
+            error => (None, error),
+        };
+        let span = self.span(src);
+        let mut location: Vec<String> = path
+            .map(|path| path.display().to_string())
+            .into_iter()
+            .collect();
+        let message = match error {
+            Self::InvalidPython(err) => err.error.to_string(),
+            error => error.to_string(),
+        };
+        if let Some((line_number, columns)) = &span {
+            location.push(line_number.to_string());
+            if matches!(error, Self::InvalidPython(_)) {
+                location.push((columns.start + 1).to_string());
+            }
+        }
+        let mut diagnostic = String::new();
+        match location.is_empty() {
+            true => diagnostic.push_line(0, [message.as_str()]),
+            false => diagnostic.push_line(0, [&location.join(":"), ": ", &message]),
+        }
+        if let Some((line_number, columns)) = span
+            && let Some(source) = src.lines_from(&Location::Line(line_number)).next()
+        {
+            let line_number = line_number.to_string();
+            let carets = "^".repeat(columns.len());
+            diagnostic.push_line(0, [&line_number, ": ", source]);
+            diagnostic.push_line(line_number.len() + 2 + columns.start, [carets.as_str()]);
+        }
+        diagnostic
+    }
+
+    /// Line number and character columns of the offending source, if known.
+    fn span(&self, src: &str) -> Option<(usize, Range<usize>)> {
+        match self {
+            Self::InvalidPython(err) => {
+                let start = usize::from(err.location.start()).min(src.len());
+                let line_number = src.line_no(&Location::Offset(start));
+                let line_start = src[..start].rfind('\n').map_or(0, |newline| newline + 1);
+                let line_end = src[start..]
+                    .find('\n')
+                    .map_or(src.len(), |newline| start + newline);
+                let end = usize::from(err.location.end()).clamp(start, line_end);
+                let first = src[line_start..start].chars().count();
+                let last = src[line_start..end].chars().count().max(first + 1);
+                Some((line_number, first..last))
+            }
+            Self::InFile { line, error, .. } => error.span(src).or_else(|| {
+                // the whole line, without indentation
+                let line_number = (*line)?;
+                let source = src.lines_from(&Location::Line(line_number)).next()?;
+                let indent = source.chars().take_while(|c| c.is_whitespace()).count();
+                Some((line_number, indent..source.trim_end().chars().count()))
+            }),
+            _ => None,
+        }
+    }
 }
 
 impl From<ParseError> for Error {
@@ -179,6 +1367,35 @@ impl Display for Error {
             Error::InvalidStatus(status) => write!(f, "Invalid Status: {status}"),
             Error::InvalidOutput(line) => write!(f, "Invalid Output: {line}"),
             Error::InvalidPython(err) => write!(f, "{err}"),
+            Error::InvalidConfig(msg) => write!(f, "Invalid Config: {msg}"),
+            Error::InvalidArgument(msg)
+            | Error::InvalidParametrize(msg)
+            | Error::ScopeMismatch(msg) => write!(f, "{msg}"),
+            Error::FixtureNotFound(name) => write!(f, "fixture '{name}' not found"),
+            Error::RecursiveFixture(name) => {
+                write!(
+                    f,
+                    "recursive dependency involving fixture '{name}' detected"
+                )
+            }
+            Error::PythonNotFound(msg) | Error::UnsupportedPython(msg) => write!(f, "{msg}"),
+            Error::InFile {
+                path,
+                line: Some(line),
+                error,
+            } => write!(f, "{}:{line}: {error}", path.display()),
+            Error::InFile { path, error, .. } => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // both display the inner error, so its message is not repeated
+            Error::InvalidPython(err) => err.source(),
+            Error::InFile { error, .. } => error.source(),
+            _ => None,
         }
     }
 }
@@ -214,4 +1431,994 @@ def test_passes():
         assert!(pytests.tests.contains_key("test_fails"));
         assert!(pytests.tests.contains_key("test_passes"));
     }
+
+    #[test]
+    fn test_class() {
+        let src = r"class TestThings:
+    def helper(self):
+        return 1
+
+    def test_method(self):
+        assert self.helper() == 1
+
+
+class TestNotCollected:
+    def __init__(self):
+        pass
+
+    def test_ignored(self):
+        pass
+";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(1, pytests.tests.len());
+        assert!(pytests.tests.contains_key("TestThings::test_method"));
+        let runner = pytests.runner("ID");
+        // the test is called on its own instance, on which class fixtures are called too
+        assert!(
+            runner.contains("        _pt_self = TestThings()\n        _pt_self.test_method()\n"),
+            "{runner}"
+        );
+    }
+
+    #[test]
+    fn python_functions_config() {
+        let src = r"def check_one():
+    assert True
+
+
+def test_two():
+    assert True
+";
+        let config = Config {
+            python_functions: vec!["check_*".to_string()],
+            ..Default::default()
+        };
+        let pytests = TestSuite::try_from((src.to_string(), &config)).unwrap();
+        assert_eq!(1, pytests.tests.len());
+        assert!(pytests.tests.contains_key("check_one"));
+    }
+
+    #[test]
+    fn select_by_keyword_and_marker() {
+        let src = r"import pytest
+
+
+@pytest.mark.slow
+def test_slow_sum():
+    assert 1 + 1 == 2
+
+
+def test_fast_sum():
+    assert 1 + 1 == 2
+
+
+def test_fast_product():
+    assert 1 * 1 == 1
+";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(
+            1,
+            pytests.deselect_by_marker("not slow and not fast").unwrap()
+        );
+        assert_eq!(
+            vec!["test_fast_sum", "test_fast_product"],
+            pytests.tests.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(1, pytests.deselect_by_keyword("SUM").unwrap());
+        assert_eq!(
+            vec!["test_fast_sum"],
+            pytests.tests.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn runner_maxfail() {
+        let src = r"def test_fails():
+    assert False
+
+
+def test_passes():
+    assert True
+";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert!(!pytests.runner("ID").contains("_pt_failures"));
+        let options = RunnerOptions {
+            maxfail: Some(2),
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        let expected = r#"        print("ID test_fails FAIL")
+        _pt_failures += 1
+        if _pt_failures >= 2:
+            sys.exit()
+    except KeyboardInterrupt as _pt_exc:
+"#;
+        assert!(runner.contains(expected), "{runner}");
+    }
+
+    #[test]
+    fn runner_legacy_python() {
+        let src = "def test_fails():\n    assert False\n";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        let options = RunnerOptions {
+            python: Some(PythonVersion::new(3, 10)),
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        assert!(!runner.contains("sys.exception()"), "{runner}");
+        assert!(runner.contains("sys.exc_info()[1]"), "{runner}");
+        let options = RunnerOptions {
+            python: Some(PythonVersion::new(3, 11)),
+            ..Default::default()
+        };
+        assert_eq!(pytests.runner("ID"), pytests.runner_with("ID", &options));
+    }
+
+    #[test]
+    fn runner_import_module() {
+        let src = "def test_fails():\n    assert False\n";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        let options = RunnerOptions {
+            module: Some(TestModule {
+                path: "/tests/mypkg/test_it.py".into(),
+                basedir: "/tests".into(),
+                name: "mypkg.test_it".to_string(),
+            }),
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        assert!(!runner.contains(src), "{runner}");
+        assert!(
+            runner.contains(
+                "spec_from_file_location(\"mypkg.test_it\", \"/tests/mypkg/test_it.py\")"
+            ),
+            "{runner}"
+        );
+        assert!(
+            runner.contains("        _pt_module.test_fails()\n"),
+            "{runner}"
+        );
+        assert!(runner.contains("__traceback__.tb_next"), "{runner}");
+    }
+
+    #[test]
+    fn error_diagnostic() {
+        let src = "def test_one():\n    assert True\n\n\ndef test_two(:\n    pass\n";
+        let err = TestSuite::try_from(src.to_string()).unwrap_err();
+        // the parse error is displayed, not repeated as the source
+        assert!(std::error::Error::source(&err).is_none());
+        let diagnostic = err.in_file("test_it.py", None).diagnostic(src);
+        assert!(diagnostic.starts_with("test_it.py:5:"), "{diagnostic}");
+        assert!(diagnostic.contains("\n5: def test_two(:\n"), "{diagnostic}");
+
+        let err = Error::InvalidOutput("???".to_string()).in_file("test_it.py", Some(2));
+        assert_eq!("test_it.py:2: Invalid Output: ???", err.to_string());
+        assert!(std::error::Error::source(&err).is_none());
+        let expected =
+            "test_it.py:2: Invalid Output: ???\n2:     assert True\n       ^^^^^^^^^^^\n";
+        assert_eq!(expected, err.diagnostic(src));
+        assert_eq!(
+            "Invalid Output: ???\n",
+            Error::InvalidOutput("???".to_string()).diagnostic(src)
+        );
+    }
+
+    #[test]
+    fn collection_warnings() {
+        let src = r"import os
+
+
+def test_one():
+    assert True
+
+
+def test_one():
+    def test_nested():
+        pass
+
+
+class TestClass:
+    def test_method(self):
+        pass
+
+    test_method = None
+
+
+test_two = os.getcwd
+from os import path as test_one
+";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(
+            vec![
+                CollectionWarning::Redefined {
+                    name: "test_one".to_string(),
+                    first_line: 4,
+                    line: 8,
+                },
+                CollectionWarning::Nested {
+                    name: "test_nested".to_string(),
+                    line: 9,
+                },
+                CollectionWarning::Shadowed {
+                    name: "TestClass::test_method".to_string(),
+                    line: 17,
+                },
+                CollectionWarning::Shadowed {
+                    name: "test_one".to_string(),
+                    line: 21,
+                },
+            ],
+            pytests.warnings()
+        );
+        let tests: Vec<_> = pytests.tests().map(|test| test.testname).collect();
+        assert!(tests.is_empty(), "{tests:?}");
+    }
+
+    #[test]
+    fn import_check() {
+        let src = r#""""docstring"""
+import os
+
+from pathlib import (
+    Path,
+)
+
+X = os.sep
+if X:
+    import json
+
+
+def test_it():
+    import sys
+"#;
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(
+            "\nimport os\n\nfrom pathlib import (\n    Path,\n)\n",
+            pytests.import_check()
+        );
+    }
+
+    #[test]
+    fn unknown_marks() {
+        let src = r"import pytest
+
+pytestmark = pytest.mark.network
+
+
+@pytest.mark.slow
+@pytest.mark.slwo(reason='typo')
+@pytest.mark.skip
+def test_one():
+    pass
+";
+        let config = Config {
+            markers: vec!["slow: takes a while".to_string()],
+            ..Default::default()
+        };
+        let pytests = TestSuite::try_from((src.to_string(), &config)).unwrap();
+        assert_eq!(
+            vec![
+                CollectionWarning::UnknownMark {
+                    name: "network".to_string(),
+                    line: 3,
+                },
+                CollectionWarning::UnknownMark {
+                    name: "slwo".to_string(),
+                    line: 7,
+                },
+            ],
+            pytests.warnings()
+        );
+        // a local `mark` is not pytest's
+        let src = r"def test_markers(request):
+    for mark in request.node.iter_markers():
+        assert mark.name
+";
+        let pytests = TestSuite::try_from((src.to_string(), &config)).unwrap();
+        assert!(pytests.warnings().is_empty());
+        let src = format!("from pytest import mark\n\n\n{src}");
+        let pytests = TestSuite::try_from((src, &config)).unwrap();
+        assert_eq!(
+            vec![CollectionWarning::UnknownMark {
+                name: "name".to_string(),
+                line: 6,
+            }],
+            pytests.warnings()
+        );
+    }
+
+    #[test]
+    fn ignored_scopes() {
+        let src = r#"import pytest
+
+
+@pytest.fixture(scope="function")
+def each():
+    pass
+
+
+@pytest.fixture(scope="session")
+def database():
+    pass
+
+
+class TestThings:
+    @pytest.fixture(autouse=True, scope="class")
+    def setup(self):
+        pass
+
+    @pytest.fixture(scope=lambda fixture_name, config: "module")
+    def chosen(self):
+        pass
+
+    def test_one(self, each, database, chosen):
+        pass
+"#;
+        let pytests = TestSuite::try_from(src.to_string()).unwrap();
+        assert_eq!(
+            vec![
+                CollectionWarning::IgnoredScope {
+                    name: "database".to_string(),
+                    scope: "session".to_string(),
+                    line: 9,
+                },
+                CollectionWarning::IgnoredScope {
+                    name: "chosen".to_string(),
+                    scope: "dynamic".to_string(),
+                    line: 19,
+                },
+            ],
+            pytests.warnings()
+        );
+    }
+
+    #[test]
+    fn runner_fixtures() {
+        let src = r"def test_tmp(tmpdir):
+    assert tmpdir
+
+
+def test_unknown(no_such_fixture):
+    assert True
+";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert!(pytests.requires_basetemp());
+        let options = RunnerOptions {
+            basetemp: Some(PathBuf::from("/tmp/pt-0")),
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        let expected = r#"        _pt_tmp_path = _pt_tmp_path_factory.mktemp("test_tmp", numbered=True)
+        _pt_tmpdir = _pt_local(_pt_tmp_path)
+        test_tmp(tmpdir=_pt_tmpdir)
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        assert!(
+            runner.contains("    _pt_tmp_path_factory = _PtTempPathFactory(\"/tmp/pt-0\")\n"),
+            "{runner}"
+        );
+        assert!(
+            runner.contains("        raise LookupError(\"fixture 'no_such_fixture' not found\")\n"),
+            "{runner}"
+        );
+    }
+
+    #[test]
+    fn runner_fixture_teardown() {
+        let src = "def test_env(monkeypatch):\n    monkeypatch.setenv(\"A\", \"1\")\n";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert!(!pytests.requires_basetemp());
+        let runner = pytests.runner("ID");
+        let expected = r#"    try:
+        _pt_warnings.start([])
+        _pt_monkeypatch = _PtMonkeyPatch()
+        try:
+            test_env(monkeypatch=_pt_monkeypatch)
+        finally:
+            _pt_monkeypatch.undo()
+    except _pt_outcome("skip") as _pt_exc:
+"#;
+        assert!(runner.contains(expected), "{runner}");
+    }
+
+    #[test]
+    fn runner_user_fixtures() {
+        let src = r#"import pytest
+
+
+@pytest.fixture(name="db")
+def database(request, tmp_path):
+    yield request.getfixturevalue("other")
+
+
+@pytest.fixture
+def other():
+    return 1
+
+
+def test_db(db, request):
+    assert db == 1
+"#;
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(1, pytests.tests.len());
+        let options = RunnerOptions {
+            capture: Capture::No,
+            location: Some("tests/test_db.py".to_string()),
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        let expected = r#"        _pt_node = _PtNode("test_db", "tests/test_db.py::test_db", test_db, None, [])
+        _pt_tmp_path = _pt_tmp_path_factory.mktemp("test_db", numbered=True)
+        _ptrequest_other = _PtFixtureRequest(_pt_node, "other", ["tmp_path_factory", "tmp_path"])
+        try:
+            _pt_other = _ptrequest_other._execute(other)
+            _ptrequest_db = _PtFixtureRequest(_pt_node, "db", ["tmp_path_factory", "tmp_path", "other"])
+            try:
+                _pt_db = _ptrequest_db._execute(database, request=_ptrequest_db, tmp_path=_pt_tmp_path)
+                _pt_request = _PtFixtureRequest(_pt_node, None, ["tmp_path_factory", "tmp_path", "other", "db"])
+                try:
+                    test_db(db=_pt_db, request=_pt_request)
+                finally:
+                    _pt_request._finish()
+            finally:
+                _ptrequest_db._finish()
+        finally:
+            _ptrequest_other._finish()
+    except _pt_outcome("skip") as _pt_exc:
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        assert!(
+            runner.contains("    _pt_config = _PtConfig({})\n"),
+            "{runner}"
+        );
+
+        let src = "import pytest\n\n\n@pytest.fixture\ndef a(a):\n    pass\n\n\ndef test_a(a):\n    pass\n";
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        let runner = pytests.runner("ID");
+        assert!(
+            runner.contains(
+                "raise LookupError(\"recursive dependency involving fixture 'a' detected\")"
+            ),
+            "{runner}"
+        );
+    }
+
+    #[test]
+    fn autouse_and_usefixtures() {
+        let src = r#"import pytest
+
+pytestmark = [pytest.mark.usefixtures("from_module"), pytest.mark.slow]
+
+
+@pytest.fixture
+def from_module():
+    pass
+
+
+@pytest.fixture(autouse=True)
+def always():
+    pass
+
+
+@pytest.fixture
+def requested():
+    pass
+
+
+@pytest.mark.usefixtures("from_class")
+class TestClass:
+    @pytest.fixture(autouse=True)
+    def in_class(self, requested):
+        pass
+
+    @pytest.fixture
+    def from_class(self):
+        pass
+
+    def test_method(self, requested):
+        pass
+
+
+def test_function():
+    pass
+"#;
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        assert_eq!(
+            vec!["usefixtures".to_string(), "slow".to_string()],
+            pytests.tests["test_function"].markers
+        );
+        let fixtures = |testname: &str| -> Vec<String> {
+            pytests
+                .resolve_fixtures(testname, &pytests.tests[testname])
+                .unwrap()
+                .iter()
+                .map(|fixture| fixture.name().to_string())
+                .collect()
+        };
+        assert_eq!(vec!["always", "from_module"], fixtures("test_function"));
+        assert_eq!(
+            vec![
+                "always",
+                "requested",
+                "in_class",
+                "from_class",
+                "from_module"
+            ],
+            fixtures("TestClass::test_method")
+        );
+        let runner = pytests.runner("ID");
+        assert!(
+            runner.contains("        _pt_self = TestClass()\n"),
+            "{runner}"
+        );
+        assert!(
+            runner.contains(
+                "_pt_in_class = _ptrequest_in_class._execute(_pt_self.in_class, requested=_pt_requested)\n"
+            ),
+            "{runner}"
+        );
+    }
+
+    #[test]
+    fn parametrized() {
+        let src = r#"import pytest
+
+
+@pytest.fixture(params=[1, 2], ids=["one", "two"])
+def number(request):
+    return request.param
+
+
+@pytest.mark.parametrize("text, n", [("a b", 1), pytest.param("'", 2, id="quote")])
+def test_text(text, n, number):
+    pass
+
+
+@pytest.mark.parametrize("number", [3], indirect=True)
+class TestIndirect:
+    def test_number(self, number):
+        pass
+"#;
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let testnames: Vec<&String> = pytests.tests.keys().collect();
+        assert_eq!(
+            vec![
+                "test_text[one-a b-1]",
+                "test_text[one-quote]",
+                "test_text[two-a b-1]",
+                "test_text[two-quote]",
+                "TestIndirect::test_number[3]",
+            ],
+            testnames
+        );
+        let runner = pytests.runner_with(
+            "ID",
+            &RunnerOptions {
+                capture: Capture::No,
+                ..Default::default()
+            },
+        );
+        let expected = r#"    print("ID test_text[two-quote] RUNNING")
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_text[two-quote]", "test_text[two-quote]", test_text, None, ["parametrize"])
+        _pt_text = "'"
+        _pt_n = 2
+        _ptrequest_number = _PtFixtureRequest(_pt_node, "number", [], param=2)
+        try:
+            _pt_number = _ptrequest_number._execute(number, request=_ptrequest_number)
+            test_text(text=_pt_text, n=_pt_n, number=_pt_number)
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        assert!(
+            runner.contains("_PtFixtureRequest(_pt_node, \"number\", [], param=3)\n"),
+            "{runner}"
+        );
+        assert!(
+            runner.contains("            _pt_self.test_number(number=_pt_number)\n"),
+            "{runner}"
+        );
+
+        pytests
+            .update_status(
+                "ID",
+                "ID test_text[one-a b-1] RUNNING\nID test_text[one-a b-1] PASS\n",
+            )
+            .unwrap();
+        assert_eq!(
+            TestStatus::Pass,
+            pytests.tests["test_text[one-a b-1]"].status
+        );
+
+        let src =
+            "import pytest\n\n\n@pytest.mark.parametrize('x', [1])\ndef test_x():\n    pass\n";
+        let error = TestSuite::try_from(src.to_string()).unwrap_err();
+        assert_eq!(
+            "In test_x: function uses no argument 'x'",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn param_marks_and_unexpanded() {
+        let src = r#"import pytest
+
+
+@pytest.mark.parametrize("n", range(3))
+def test_range(n):
+    pass
+
+
+@pytest.mark.parametrize("n", [1, pytest.param(2, marks=[pytest.mark.slow])])
+def test_marked(n):
+    pass
+"#;
+        let pytests: TestSuite = src.to_string().try_into().unwrap();
+        let testnames: Vec<&String> = pytests.tests.keys().collect();
+        assert_eq!(
+            vec!["test_range", "test_marked[1]", "test_marked[2]"],
+            testnames
+        );
+        assert_eq!(
+            vec!["parametrize", "slow"],
+            pytests.tests["test_marked[2]"].markers
+        );
+        let runner = pytests.runner("ID");
+        assert!(
+            runner.contains(
+                "raise NotImplementedError(\"In test_range: pt cannot expand the values range(3), \
+                 as it requires a list or tuple\")\n"
+            ),
+            "{runner}"
+        );
+    }
+
+    #[test]
+    fn captured_output() {
+        let src = "def test_fails(capsys):\n    print(1)\n    assert False\n";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let runner = pytests.runner("ID");
+        assert!(
+            runner.contains("    _pt_capture = _PtCapture(\"fd\")\n"),
+            "{runner}"
+        );
+        let expected = r#"        _pt_capsys = _PtCapture("sys")
+        _pt_capsys.start()
+        try:
+            test_fails(capsys=_pt_capsys)
+        finally:
+            _pt_capsys.close()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        let expected = r#"    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        let options = RunnerOptions {
+            capture: Capture::No,
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        assert!(!runner.contains("_pt_capture."), "{runner}");
+        assert!(runner.contains("class _PtCapture:"), "{runner}");
+
+        let stdout = r#"ID test_fails RUNNING
+Traceback (most recent call last):
+  File "test_it.py", line 3, in test_fails
+    assert False
+AssertionError
+ID test_fails CAPTURED stdout
+1
+ID test_fails FAIL
+"#;
+        pytests.update_status("ID", stdout).unwrap();
+        let report = pytests.summary_report();
+        assert!(
+            report.ends_with("AssertionError\n---- Captured stdout call ----\n1\n"),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn skipped_and_xfailed() {
+        let src = "import pytest\n\n\ndef test_skips():\n    pytest.skip(\"not here\")\n\n\ndef test_xfails():\n    pytest.xfail()\n";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let runner = pytests.runner("ID");
+        let expected = r#"    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("ID test_xfails REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("ID test_xfails")
+        print("ID test_xfails XFAIL")
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        // output which was not captured precedes the report
+        let stdout = "ID test_skips RUNNING\nprinted\nID test_skips REPORT\nnot here\nID test_skips SKIP\nID test_xfails RUNNING\nID test_xfails REPORT\n\nID test_xfails XFAIL\n";
+        pytests.update_status("ID", stdout).unwrap();
+        assert_eq!(
+            &TestStatus::Skip("not here".to_string()),
+            pytests.test("test_skips").unwrap().status
+        );
+        assert_eq!(
+            &TestStatus::XFail(String::new()),
+            pytests.test("test_xfails").unwrap().status
+        );
+        assert_eq!(0, pytests.failures());
+        assert_eq!(
+            "test_skips SKIP\ntest_xfails XFAIL\n",
+            pytests.summary_report()
+        );
+    }
+
+    #[test]
+    fn skip_marks() {
+        let src = r#"import pytest
+
+pytestmark = pytest.mark.skipif("sys.platform == 'win32'")
+
+
+@pytest.mark.xfail(reason="bug")
+def test_marked():
+    pass
+"#;
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let options = RunnerOptions {
+            xfail_strict: true,
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        let expected = r#"        _pt_xfail = _pt_skipping([pytest.mark.xfail(reason="bug"), pytest.mark.skipif("sys.platform == 'win32'")], globals(), True)
+        try:
+            test_marked()
+        except (Exception, *_pt_outcome("fail")) as _pt_exc:
+            _pt_xfailed(_pt_xfail, _pt_exc)
+            raise
+        _pt_xpassed(_pt_xfail)
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        assert!(
+            runner.contains("    except _PtXPassed as _pt_exc:\n"),
+            "{runner}"
+        );
+        let stdout = "ID test_marked RUNNING\nID test_marked REPORT\nbug\nID test_marked XPASS\n";
+        pytests.update_status("ID", stdout).unwrap();
+        assert_eq!(
+            &TestStatus::XPass("bug".to_string()),
+            pytests.test("test_marked").unwrap().status
+        );
+        assert_eq!(0, pytests.failures());
+    }
+
+    #[test]
+    fn frame_sources() {
+        let src = "from helper import check\n\n\ndef test_it():\n    check(6)\n";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let runner = pytests.runner("ID");
+        assert!(
+            runner
+                .contains("        _pt_print_sources(\"ID test_it\", sys.exception(), __file__)\n"),
+            "{runner}"
+        );
+        // `gone.py` was deleted after it was imported, so has no source
+        let stdout = r#"ID test_it RUNNING
+ID test_it REPORT
+Traceback (most recent call last):
+  File "/tests/test_it.py", line 5, in test_it
+    check(6)
+  File "/tests/helper.py", line 3, in check
+    inner(value)
+  File "/tests/gone.py", line 3, in inner
+    assert value == 7
+AssertionError
+ID test_it SOURCE
+/tests/helper.py
+def check(value):
+    # from gone
+    inner(value)
+ID test_it SOURCE
+/tests/gone.py
+ID test_it FAIL
+"#;
+        pytests.update_status("ID", stdout).unwrap();
+        let test = pytests.test("test_it").unwrap();
+        assert_eq!(
+            vec![
+                (
+                    "/tests/helper.py".to_string(),
+                    "def check(value):\n    # from gone\n    inner(value)\n".to_string()
+                ),
+                ("/tests/gone.py".to_string(), String::new())
+            ],
+            test.sources
+        );
+        let report = test.report_with(&ReportOptions {
+            rootdir: Some(PathBuf::from("/tests")),
+            ..Default::default()
+        });
+        let expected = r#"==== test_it ====
+   def test_it():
+5:     check(6)
+---- check (helper.py:3) ----
+   def check(value):
+       # from gone
+3:     inner(value)
+---- inner (gone.py:3) ----
+3:     assert value == 7
+AssertionError
+"#;
+        assert_eq!(Some(expected.to_string()), report);
+    }
+
+    #[test]
+    fn interrupted() {
+        let src = "def test_sleeps():\n    sleep(60)\n\n\ndef test_after():\n    pass\n";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let runner = pytests.runner("ID");
+        let expected = r#"    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("ID test_sleeps REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("ID test_sleeps INTERRUPTED")
+        sys.exit()
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        let stdout = "ID test_sleeps RUNNING\nID test_sleeps REPORT\ntest_it.py:2\nID test_sleeps INTERRUPTED\n";
+        pytests.update_status("ID", stdout).unwrap();
+        assert_eq!(
+            &TestStatus::Interrupted("test_it.py:2".to_string()),
+            pytests.test("test_sleeps").unwrap().status
+        );
+        assert_eq!(
+            "test_sleeps INTERRUPTED\ntest_after NO RUN\n",
+            pytests.summary_report()
+        );
+        assert_eq!(0, pytests.failures());
+    }
+
+    #[test]
+    fn unittest_test_case() {
+        let src = r#"import unittest
+
+
+class Base(unittest.TestCase):
+    def helper(self):
+        pass
+
+    def test_a(self):
+        pass
+
+    def test_b(self):
+        pass
+
+
+class Checks(Base):
+    def test_c(self):
+        pass
+"#;
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let testnames: Vec<&String> = pytests.tests.keys().collect();
+        assert_eq!(
+            vec![
+                "Base::test_a",
+                "Base::test_b",
+                "Checks::test_a",
+                "Checks::test_b",
+                "Checks::test_c"
+            ],
+            testnames
+        );
+        let runner = pytests.runner_with(
+            "ID",
+            &RunnerOptions {
+                capture: Capture::No,
+                ..Default::default()
+            },
+        );
+        let expected = r#"    _pt_class_error = _pt_set_up_class(Base)
+    print("ID Base::test_a RUNNING")
+    try:
+        _pt_warnings.start([])
+        _pt_self = Base("test_a")
+        _pt_unittest_outcome = _pt_run_unittest(_pt_self, _pt_class_error)
+        if _pt_unittest_outcome is not None:
+            raise _pt_unittest_outcome
+    except _pt_outcome("skip") as _pt_exc:
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        let expected = r#"    try:
+        try:
+            _pt_warnings.start([])
+            _pt_self = Base("test_b")
+            _pt_unittest_outcome = _pt_run_unittest(_pt_self, _pt_class_error)
+            if _pt_unittest_outcome is not None:
+                raise _pt_unittest_outcome
+        finally:
+            _pt_tear_down_class(Base, _pt_class_error)
+"#;
+        assert!(runner.contains(expected), "{runner}");
+        assert_eq!(2, runner.matches("= _pt_set_up_class(").count(), "{runner}");
+        // inherited methods run on the subclass
+        assert!(
+            runner.contains("        _pt_self = Checks(\"test_a\")\n"),
+            "{runner}"
+        );
+
+        let stdout = "ID Base::test_a RUNNING\nskipped\nID Base::test_a SKIP\n";
+        pytests.update_status("ID", stdout).unwrap();
+        assert_eq!(
+            &TestStatus::Skip("skipped".to_string()),
+            pytests.test("Base::test_a").unwrap().status
+        );
+    }
+
+    #[test]
+    fn recorded_warnings() {
+        let src = r#"import pytest
+
+pytestmark = pytest.mark.filterwarnings("ignore:module")
+
+
+@pytest.mark.filterwarnings("error::UserWarning")
+class TestWarns:
+    @pytest.mark.filterwarnings("ignore:outer")
+    @pytest.mark.filterwarnings("ignore:inner")
+    def test_warns(self):
+        pass
+"#;
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        let options = RunnerOptions {
+            filterwarnings: vec!["ignore:.*ini".to_string()],
+            pythonwarnings: vec!["error".to_string()],
+            ..Default::default()
+        };
+        let runner = pytests.runner_with("ID", &options);
+        assert!(
+            runner.contains(
+                r#"    _pt_warnings = _PtWarnings([("ignore:.*ini", False), ("error", True)])"#
+            ),
+            "{runner}"
+        );
+        assert!(
+            runner.contains(r#"    _pt_warnings.start(["ignore:inner", "ignore:outer", "error::UserWarning", "ignore:module"])"#),
+            "{runner}"
+        );
+        let stdout = r#"ID TestWarns::test_warns RUNNING
+ID TestWarns::test_warns WARNING
+test_it.py:3: UserWarning: careful
+  warnings.warn("careful")
+ID TestWarns::test_warns PASS
+"#;
+        pytests.update_status("ID", stdout).unwrap();
+        let test = pytests.test("TestWarns::test_warns").unwrap();
+        assert_eq!(&TestStatus::Pass, test.status);
+        assert!(test.captured.is_empty());
+        assert_eq!(
+            ["test_it.py:3: UserWarning: careful\n  warnings.warn(\"careful\")\n"],
+            test.warnings
+        );
+    }
+
+    #[test]
+    fn prioritise() {
+        let src = r"def test_one():
+    assert True
+
+
+def test_two():
+    assert True
+
+
+def test_three():
+    assert True
+";
+        let mut pytests: TestSuite = src.to_string().try_into().unwrap();
+        pytests.prioritise(|testname| testname != "test_one");
+        assert_eq!(
+            vec!["test_two", "test_three", "test_one"],
+            pytests.tests.keys().collect::<Vec<_>>()
+        );
+    }
 }
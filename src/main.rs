@@ -1,34 +1,474 @@
 #![feature(never_type)]
 #![feature(try_trait_v2)]
-use std::{env, fs, io, path::PathBuf, process::Command, string::FromUtf8Error};
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Write},
+    process::{Output, Stdio},
+    string::FromUtf8Error,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
 
 use std::process::Termination as _T;
 
 use exit_safely::Termination;
 use try_v2::{Try, Try_ConvertResult};
 
-use pt::{TestStatus, TestSuite};
+use pt::{
+    Args, BaseTemp, Cache, CollectionError, CollectionWarning, Config, Conftest, Interpreter,
+    MODULE_SKIPPED, ReportOptions, RunnerMode, RunnerOptions, TestModule, TestStatus, TestSuite,
+    Traceback, check_filter, warnings_summary,
+};
+
+/// Set by the Ctrl+C handler
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// pid of the running python process, 0 if none is running
+static PYTHON_PID: AtomicU32 = AtomicU32::new(0);
 
 fn main() -> Exit<()> {
     let id = "PT_CLI";
 
-    let src_path: PathBuf = env::args()
-        .nth(1)
-        .ok_or_else(|| Exit::InvalidInvocation("Please provide a file to test".to_string()))?
-        .into();
-    let src = fs::read_to_string(&src_path)
-        .map_err(|err| Exit::InternalError(format!("Error opening {src_path:?}: {err}")))?;
-    let mut suite = TestSuite::try_from(src)
-        .map_err(|err| Exit::InternalError(format!("Error parsing {src_path:?}: {err}")))?;
+    ctrlc::set_handler(|| {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        #[cfg(unix)]
+        if let pid @ 1.. = PYTHON_PID.load(Ordering::SeqCst) {
+            // SAFETY: `kill` does not access any memory owned by this process
+            unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) };
+        }
+    })
+    .map_err(|err| Exit::InternalError(err.to_string()))?;
+
+    let cli_args: Vec<String> = env::args().skip(1).collect();
+    let config = Config::discover(&Args::parse(&cli_args)?.paths, &env::current_dir()?)?;
+    let env_addopts = env::var("PYTEST_ADDOPTS").unwrap_or_default();
+    let args = Args::parse_with_addopts(&config.addopts, &env_addopts, &cli_args)?;
+    if args.help {
+        print!("{}", Args::USAGE);
+        return Exit::Ok(());
+    }
+    for plugin in &args.plugins {
+        eprintln!("pt: warning: plugins are not loaded, ignoring -p {plugin}");
+    }
+    for filter in &args.pythonwarnings {
+        check_filter("-W", filter)?;
+    }
+    for filter in &config.filterwarnings {
+        check_filter("filterwarnings", filter)?;
+    }
+
+    if let Some(glob) = &args.cache_show {
+        print!("{}", Cache::load(&config.rootdir).show(glob));
+        return Exit::Ok(());
+    }
+    let mut cache = (!args
+        .disabled_plugins
+        .iter()
+        .any(|plugin| plugin == "cacheprovider"))
+    .then(|| Cache::load(&config.rootdir));
+    if let Some(cache) = &mut cache {
+        if args.cache_clear {
+            cache.clear()?;
+        }
+        if args.last_failed {
+            match cache.lastfailed.len() {
+                0 => {
+                    println!("run-last-failure: no previously failed tests, not deselecting items.")
+                }
+                failures => println!("run-last-failure: rerun previous {failures} failures"),
+            }
+        }
+    }
+
+    let src_paths = config.collect_files(&args.paths)?;
+    if src_paths.is_empty() {
+        println!("no tests ran");
+        return Exit::NoTestsCollected;
+    }
+
+    let python = match args.collect_only {
+        true => None,
+        false => Some(Interpreter::find(
+            args.python.as_deref(),
+            env::var_os("VIRTUAL_ENV"),
+            &config.rootdir,
+        )?),
+    };
+    // as pytest, the header is hidden by `-q`
+    if args.verbosity >= 0 {
+        println!(
+            "============================= test session starts =============================="
+        );
+        if let Some(python) = &python {
+            println!(
+                "python: {} (Python {})",
+                python.path.display(),
+                python.version
+            );
+        }
+        println!("rootdir: {}", config.rootdir.display());
+        if let Some(configfile) = &config.configfile {
+            println!("configfile: {}", configfile.display());
+        }
+        println!();
+    }
+    let pythonpath: Vec<_> = config
+        .pythonpath
+        .iter()
+        .map(|path| config.rootdir.join(path))
+        .collect();
+    let report_options = ReportOptions {
+        tb: args.tb,
+        showlocals: args.showlocals,
+        python: python.as_ref().map(|python| python.version),
+        // as python names the files in tracebacks
+        rootdir: Some(
+            config
+                .rootdir
+                .canonicalize()
+                .unwrap_or(config.rootdir.clone()),
+        ),
+    };
+    // Every module is collected before any tests run
+    let mut modules = Vec::with_capacity(src_paths.len());
+    let mut collection_errors = Vec::new();
+    let mut collection_warnings = Vec::new();
+    // each conftest is loaded once, `None` if it could not be
+    let mut conftests: HashMap<_, Option<Conftest>> = HashMap::new();
+    for src_path in src_paths {
+        let src = fs::read_to_string(&src_path)
+            .map_err(|err| Exit::InternalError(format!("Error opening {src_path:?}: {err}")))?;
+        let conftest_paths = config.conftests(&src_path);
+        for path in &conftest_paths {
+            if conftests.contains_key(path) {
+                continue;
+            }
+            let conftest = match Conftest::try_from(path.as_path()) {
+                // `--strict` makes every collection warning an error
+                Ok(conftest) if args.strict && !conftest.warnings().is_empty() => {
+                    let errors = CollectionError::Warnings(conftest.warnings().to_vec());
+                    collection_errors.push((path.clone(), errors));
+                    None
+                }
+                Ok(conftest) => {
+                    if !conftest.warnings().is_empty() {
+                        collection_warnings.push((path.clone(), conftest.warnings().to_vec()));
+                    }
+                    Some(conftest)
+                }
+                Err(pt::Error::InvalidPython(err)) => {
+                    let src = fs::read_to_string(path).unwrap_or_default();
+                    collection_errors.push((path.clone(), CollectionError::syntax(&src, err)));
+                    None
+                }
+                Err(pt::Error::InvalidParametrize(msg)) => {
+                    collection_errors.push((path.clone(), CollectionError::Parametrize(msg)));
+                    None
+                }
+                Err(err) => return Exit::InternalError(err.in_file(path, None).to_string()),
+            };
+            conftests.insert(path.clone(), conftest);
+        }
+        // as pytest, a module is not collected if one of its conftests fails to load
+        let Some(module_conftests) = conftest_paths
+            .iter()
+            .map(|path| conftests[path].clone())
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let mut suite =
+            match TestSuite::try_from((src.clone(), &config, module_conftests.as_slice())) {
+                Ok(suite) => suite,
+                Err(pt::Error::InvalidPython(err)) => {
+                    collection_errors.push((src_path, CollectionError::syntax(&src, err)));
+                    continue;
+                }
+                Err(pt::Error::InvalidParametrize(msg)) => {
+                    collection_errors.push((src_path, CollectionError::Parametrize(msg)));
+                    continue;
+                }
+                Err(err) => return Exit::InternalError(err.in_file(src_path, None).to_string()),
+            };
+        // `--strict` makes every collection warning an error, `--strict-markers` unknown marks
+        let (errors, warnings): (Vec<_>, Vec<_>) =
+            suite.warnings().iter().cloned().partition(|warning| {
+                args.strict
+                    || (args.strict_markers
+                        && matches!(warning, CollectionWarning::UnknownMark { .. }))
+            });
+        if !errors.is_empty() {
+            collection_errors.push((src_path, CollectionError::Warnings(errors)));
+            continue;
+        }
+        if !warnings.is_empty() {
+            collection_warnings.push((src_path.clone(), warnings));
+        }
+        if let Some(keyword) = &args.keyword {
+            suite.deselect_by_keyword(keyword)?;
+        }
+        if let Some(markexpr) = &args.markexpr {
+            suite.deselect_by_marker(markexpr)?;
+        }
+        let nodeid = |testname: &str| Cache::nodeid(&config.rootdir, &src_path, testname);
+        if let Some(cache) = &cache {
+            if args.last_failed && !cache.lastfailed.is_empty() {
+                suite.deselect_by_name(|testname| cache.failed(&nodeid(testname)));
+            }
+            if args.failed_first {
+                suite.prioritise(|testname| cache.failed(&nodeid(testname)));
+            }
+            if args.new_first {
+                suite.prioritise(|testname| cache.is_new(&nodeid(testname)));
+            }
+        }
+        modules.push((src_path, suite));
+    }
+
+    // As pytest, which imports every module before running any test, import errors are found
+    // first. Other errors running a module are found when its tests run.
+    if let Some(python) = &python {
+        let mut imported = Vec::with_capacity(modules.len());
+        for (src_path, mut suite) in modules {
+            let check = suite.import_check();
+            if !check.is_empty() && suite.tests().next().is_some() {
+                let module = TestModule::try_from(src_path.as_path())?;
+                let launcher = module.launcher(
+                    args.import_mode,
+                    RunnerMode::Inline,
+                    &pythonpath,
+                    args.pytest_shim,
+                );
+                let output = run_python(python, &launcher, &check)?;
+                if let Some(reason) = module_skipped(&output) {
+                    suite.skip(&reason);
+                } else if !output.status.success() && !INTERRUPTED.load(Ordering::SeqCst) {
+                    let stderr = String::from_utf8(output.stderr)?;
+                    let error = CollectionError::Import(Traceback::from(stderr));
+                    collection_errors.push((src_path, error));
+                    continue;
+                }
+            }
+            imported.push((src_path, suite));
+        }
+        modules = imported;
+    }
+
+    let mut basetemp: Option<BaseTemp> = None;
+    let mut suites = Vec::with_capacity(modules.len());
+    let mut failed_summary = String::new();
+    // `(nodeid, warning)` for each warning emitted by a test
+    let mut recorded: Vec<(String, String)> = Vec::new();
+    // as pytest, errors collecting a module count towards `--maxfail`
+    let mut failures = collection_errors.len();
+    for (src_path, mut suite) in modules {
+        if args.collect_only {
+            for test in suite.tests() {
+                println!("{}::{}", src_path.display(), test.testname);
+            }
+            suites.push(suite);
+            continue;
+        }
+        // As `-x`, but for collection errors: no further modules are run
+        if !collection_errors.is_empty() && !args.continue_on_collection_errors {
+            break;
+        }
 
-    let mut runner = Command::new("python");
-    runner.args(["-c", &suite.runner(id)]);
+        let stopped = args.maxfail.is_some_and(|maxfail| failures >= maxfail);
+        if basetemp.is_none() && suite.requires_basetemp() {
+            basetemp = Some(BaseTemp::create(args.basetemp.as_deref())?);
+        }
+        let module = TestModule::try_from(src_path.as_path())?;
+        let options = RunnerOptions {
+            maxfail: args.maxfail.map(|maxfail| maxfail.saturating_sub(failures)),
+            python: python.as_ref().map(|python| python.version),
+            module: (args.runner == RunnerMode::Import).then(|| module.clone()),
+            basetemp: basetemp.as_ref().map(|basetemp| basetemp.path.clone()),
+            capture: args.capture,
+            location: Cache::nodeid(&config.rootdir, &src_path, "")
+                .strip_suffix("::")
+                .map(str::to_string),
+            config_options: args.options(),
+            filterwarnings: config.filterwarnings.clone(),
+            pythonwarnings: args.pythonwarnings.clone(),
+            xfail_strict: config.xfail_strict,
+        };
+        // Tests in remaining files stay NoRun once maxfail is reached or after an interrupt, and
+        // a module skipped by its imports is not run again
+        if !stopped
+            && !INTERRUPTED.load(Ordering::SeqCst)
+            && suite
+                .tests()
+                .any(|test| matches!(test.status, TestStatus::NoRun))
+        {
+            let python = python
+                .as_ref()
+                .expect("interpreter is found unless collecting only");
+            let launcher =
+                module.launcher(args.import_mode, args.runner, &pythonpath, args.pytest_shim);
+            let runner = suite.runner_with(id, &options);
+            let output = run_python(python, &launcher, &runner)?;
+            let skipped = module_skipped(&output);
+            let python_output = String::from_utf8(output.stdout)?;
+
+            // python exits before running any test if importing the module fails or skips it
+            if let Some(reason) = skipped {
+                suite.skip(&reason);
+            } else if !output.status.success()
+                && !INTERRUPTED.load(Ordering::SeqCst)
+                && !python_output.lines().any(|line| line.starts_with(id))
+            {
+                let stderr = String::from_utf8(output.stderr)?;
+                let error = CollectionError::Import(Traceback::from(stderr));
+                collection_errors.push((src_path, error));
+                failures += 1;
+                continue;
+            } else {
+                suite
+                    .update_status(id, &python_output)
+                    .map_err(|err| err.in_file(&src_path, None))?;
+            }
+        }
+        if let Some(cache) = &mut cache {
+            for test in suite.tests() {
+                cache.update(
+                    Cache::nodeid(&config.rootdir, &src_path, test.testname),
+                    test.status,
+                );
+            }
+        }
+        print!("{}", suite.summary_report_with(&report_options));
+        failed_summary.push_str(&suite.short_summary(&src_path.display().to_string()));
+        for test in suite.tests() {
+            let nodeid = Cache::nodeid(&config.rootdir, &src_path, test.testname);
+            recorded.extend(
+                test.warnings
+                    .iter()
+                    .map(|warning| (nodeid.clone(), warning.clone())),
+            );
+        }
+        failures += suite.failures();
+        suites.push(suite);
+    }
+    if !args.collect_only
+        && let Some(cache) = &cache
+    {
+        cache.save()?;
+    }
+    let mut error_summary = String::new();
+    for (src_path, error) in &collection_errors {
+        let location = src_path.display().to_string();
+        print!("{}", error.report(&location));
+        error_summary.push_str(&error.short_summary(&location));
+    }
+    if !collection_warnings.is_empty() || !recorded.is_empty() {
+        println!(
+            "\n=============================== warnings summary ==============================="
+        );
+        for (src_path, warnings) in &collection_warnings {
+            let location = src_path.display().to_string();
+            warnings
+                .iter()
+                .for_each(|warning| print!("{}", warning.report(&location)));
+        }
+        let recorded = recorded
+            .iter()
+            .map(|(nodeid, warning)| (nodeid.clone(), warning.as_str()));
+        print!("{}", warnings_summary(recorded));
+        println!("-- Docs: https://docs.pytest.org/en/stable/how-to/capture-warnings.html");
+    }
+    let mut short_summary = String::new();
+    // as pytest, `N` resets the chars before it, including the default `fE`
+    let report_chars = args.report_chars.rsplit('N').next().unwrap_or_default();
+    if report_chars.contains(['f', 'a', 'A']) {
+        short_summary.push_str(&failed_summary);
+    }
+    if report_chars.contains(['E', 'a', 'A']) {
+        short_summary.push_str(&error_summary);
+    }
+    if !short_summary.is_empty() {
+        println!(
+            "\n=========================== short test summary info ============================"
+        );
+        print!("{short_summary}");
+    }
+    // as pytest, the reason the run stopped early precedes the summary line
+    if let Some(maxfail) = args.maxfail
+        && failures >= maxfail
+    {
+        println!("\n!!!!!!!!!!!!!!!!!!!! stopping after {maxfail} failures !!!!!!!!!!!!!!!!!!!!");
+    }
+    let exit = if !collection_errors.is_empty() && !args.continue_on_collection_errors {
+        let errors = collection_errors.len();
+        let plural = if errors == 1 { "" } else { "s" };
+        println!(
+            "\n!!!!!!!!!!!!!!!!!!!! Interrupted: {errors} error{plural} during collection !!!!!!!!!!!!!!!!!!!!"
+        );
+        Exit::Interrupted
+    } else if INTERRUPTED.load(Ordering::SeqCst) {
+        println!(
+            "\n!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!! KeyboardInterrupt !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!"
+        );
+        // as pytest, followed by where the interrupted test was
+        if let Some(location) =
+            suites
+                .iter()
+                .flat_map(TestSuite::tests)
+                .find_map(|test| match &test.status {
+                    TestStatus::Interrupted(location) => Some(location),
+                    _ => None,
+                })
+        {
+            println!("{location}: KeyboardInterrupt");
+        }
+        Exit::Interrupted
+    } else if !collection_errors.is_empty() {
+        // With `--continue-on-collection-errors` the run still fails
+        Exit::TestsFailed
+    } else {
+        Exit::from(suites.as_slice())
+    };
+    if suites.iter().all(|suite| suite.tests().next().is_none()) {
+        println!("no tests ran");
+    }
+    exit
+}
 
-    let python_output = String::from_utf8(runner.output()?.stdout)?;
+/// Run `runner` with python, via `launcher` which sets up the module context. The runner is
+/// written to python's stdin, as a single argument is limited in size. Python runs in its own
+/// process group so that Ctrl+C is only delivered once, by the handler in `main()`, allowing
+/// python to report partial results.
+fn run_python(interpreter: &Interpreter, launcher: &str, runner: &str) -> io::Result<Output> {
+    let mut python = interpreter.command();
+    python
+        .args(["-c", launcher])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut python, 0);
+    let mut child = python.spawn()?;
+    PYTHON_PID.store(child.id(), Ordering::SeqCst);
+    // python reads the whole runner before it writes any output; if it exits first, e.g. because
+    // the launcher fails, its stderr says why
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(err) = stdin.write_all(runner.as_bytes())
+        && err.kind() != io::ErrorKind::BrokenPipe
+    {
+        PYTHON_PID.store(0, Ordering::SeqCst);
+        return Err(err);
+    }
+    let output = child.wait_with_output();
+    PYTHON_PID.store(0, Ordering::SeqCst);
+    output
+}
 
-    suite.update_status(id, &python_output)?;
-    print!("{}", suite.summary_report());
-    Exit::from(suite)
+/// The reason the module was skipped, if python exited with `MODULE_SKIPPED`.
+fn module_skipped(output: &Output) -> Option<String> {
+    if output.status.code() != Some(MODULE_SKIPPED) {
+        return None;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Some(stderr.lines().last().unwrap_or_default().to_string())
 }
 
 /// Custom ExitCode handler. Using this rather than just calling `exit()` to allow for proper
@@ -51,14 +491,20 @@ fn main() -> Exit<()> {
 enum Exit<T: _T> {
     Ok(T) = 0,
     TestsFailed = 1,
+    Interrupted = 2,
     InternalError(String) = 3,
     InvalidInvocation(String) = 4,
+    NoTestsCollected = 5,
 }
 
-impl From<TestSuite> for Exit<()> {
-    fn from(suite: TestSuite) -> Self {
-        if suite
-            .tests()
+impl From<&[TestSuite]> for Exit<()> {
+    fn from(suites: &[TestSuite]) -> Self {
+        if suites.iter().all(|suite| suite.tests().next().is_none()) {
+            return Exit::NoTestsCollected;
+        }
+        if suites
+            .iter()
+            .flat_map(TestSuite::tests)
             .any(|test| matches!(test.status, TestStatus::Fail(_, _)))
         {
             return Exit::TestsFailed;
@@ -81,8 +527,15 @@ impl<T: _T> From<io::Error> for Exit<T> {
     }
 }
 
+/// Invalid command line arguments or a missing / unsupported python interpreter return
+/// InvalidInvocation, all other errors InternalError
 impl<T: _T> From<pt::Error> for Exit<T> {
     fn from(err: pt::Error) -> Self {
-        Exit::InternalError(err.to_string())
+        match err {
+            pt::Error::InvalidArgument(msg)
+            | pt::Error::PythonNotFound(msg)
+            | pt::Error::UnsupportedPython(msg) => Exit::InvalidInvocation(msg),
+            err => Exit::InternalError(err.to_string()),
+        }
     }
 }
@@ -0,0 +1,238 @@
+//! Locating test modules within python packages
+
+use std::path::{Path, PathBuf};
+
+use crate::{Error, ImportMode, RunnerMode, multiline::MultilineMut, shim};
+
+/// Exit code of the launcher when the module is skipped while it is imported, by
+/// `pytest.skip(allow_module_level=True)`, `pytest.importorskip()` or `unittest.SkipTest`. The
+/// reason is the last line written to stderr.
+pub const MODULE_SKIPPED: i32 = 77;
+
+/// A test file, its dotted module name and the `basedir` from which that name can be imported.
+///
+/// As pytest, the package containing a test file is found by walking up the directories which
+/// contain an `__init__.py`; `basedir` is the first directory without one.
+///
+/// Usage:
+/// ```rust,no_run
+/// use std::path::Path;
+/// use pt::TestModule;
+/// let module = TestModule::try_from(Path::new("tests/mypkg/test_it.py")).unwrap();
+/// assert_eq!("mypkg.test_it", module.name);
+/// assert_eq!(Some("mypkg"), module.package());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestModule {
+    /// absolute path to the test file
+    pub path: PathBuf,
+    pub basedir: PathBuf,
+    pub name: String,
+}
+
+impl TryFrom<&Path> for TestModule {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let path = path
+            .canonicalize()
+            .map_err(|err| Error::InvalidArgument(format!("{}: {err}", path.display())))?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| Error::InvalidArgument(format!("{} is not a module", path.display())))?;
+        // a package's `__init__.py` is imported as the package itself
+        let mut parts: Vec<String> = (stem != "__init__")
+            .then(|| stem.to_string())
+            .into_iter()
+            .collect();
+        let mut basedir = path.parent().unwrap_or(Path::new("/"));
+        while basedir.join("__init__.py").is_file()
+            && let (Some(package), Some(parent)) = (basedir.file_name(), basedir.parent())
+        {
+            parts.push(package.to_string_lossy().into_owned());
+            basedir = parent;
+        }
+        parts.reverse();
+        Ok(Self {
+            basedir: basedir.to_path_buf(),
+            name: parts.join("."),
+            path,
+        })
+    }
+}
+
+impl TestModule {
+    /// Push python code which imports this module from its file as `variable`, registered in
+    /// `sys.modules` under its name. A module already imported from the same file, e.g. a conftest
+    /// which the test module imports, is used as is.
+    pub(crate) fn push_import(&self, runner: &mut String, indent: usize, variable: &str) {
+        let name = python_str(&self.name);
+        let path = python_str(&self.path.to_string_lossy());
+        runner.push_python_line(indent, [variable, " = sys.modules.get(", &name, ")"]);
+        runner.push_python_line(
+            indent,
+            [
+                "if getattr(",
+                variable,
+                ", \"__file__\", None) != ",
+                &path,
+                ":",
+            ],
+        );
+        runner.push_python_line(indent + 1, ["import importlib.util as _pt_importlib_util"]);
+        runner.push_python_line(
+            indent + 1,
+            [
+                "_pt_spec = _pt_importlib_util.spec_from_file_location(",
+                &name,
+                ", ",
+                &path,
+                ")",
+            ],
+        );
+        runner.push_python_line(
+            indent + 1,
+            [variable, " = _pt_importlib_util.module_from_spec(_pt_spec)"],
+        );
+        runner.push_python_line(indent + 1, ["sys.modules[", &name, "] = ", variable]);
+        runner.push_python_line(indent + 1, ["_pt_spec.loader.exec_module(", variable, ")"]);
+    }
+
+    /// The package containing this module, `None` for a top-level module.
+    pub fn package(&self) -> Option<&str> {
+        self.name.rsplit_once('.').map(|(package, _)| package)
+    }
+
+    /// Python code, to run with `python -c`, which sets up `sys.path` and executes the runner
+    /// read from stdin. Entries in `pythonpath` are added to the start of `sys.path`,
+    /// before `basedir` is inserted according to `import_mode`. pt's own `pytest` module is
+    /// installed if pytest is not, or always with `force_shim`.
+    ///
+    /// An `Inline` runner is executed in a module registered as `__main__`, with this module's
+    /// file and package, and compiled with the test file's path, so tracebacks refer to the real
+    /// file. An `Import` runner imports the module itself and is executed as is. Either exits with
+    /// `MODULE_SKIPPED` if the module is skipped.
+    pub fn launcher(
+        &self,
+        import_mode: ImportMode,
+        runner_mode: RunnerMode,
+        pythonpath: &[PathBuf],
+        force_shim: bool,
+    ) -> String {
+        let literal = |path: &Path| python_str(&path.to_string_lossy());
+        let mut launcher = String::from("import sys\n");
+        if !pythonpath.is_empty() {
+            let entries: Vec<_> = pythonpath.iter().map(|path| literal(path)).collect();
+            launcher.push_str(&format!("sys.path[0:0] = [{}]\n", entries.join(", ")));
+        }
+        match import_mode {
+            ImportMode::Prepend => {
+                launcher.push_str(&format!("sys.path.insert(0, {})\n", literal(&self.basedir)))
+            }
+            ImportMode::Append => {
+                launcher.push_str(&format!("sys.path.append({})\n", literal(&self.basedir)))
+            }
+            ImportMode::Importlib => (),
+        }
+        shim::push_install(&mut launcher, force_shim);
+        launcher.push_str("try:\n");
+        match runner_mode {
+            RunnerMode::Inline => {
+                let package = self.package().map(python_str);
+                launcher.push_str(&format!(
+                    "    import types\n    \
+                     _pt_module = types.ModuleType(\"__main__\")\n    \
+                     _pt_module.__file__ = {file}\n    \
+                     _pt_module.__package__ = {package}\n    \
+                     _pt_module.__builtins__ = __builtins__\n    \
+                     sys.modules[\"__main__\"] = _pt_module\n    \
+                     exec(compile(sys.stdin.read(), {file}, \"exec\"), _pt_module.__dict__)\n",
+                    file = literal(&self.path),
+                    package = package.as_deref().unwrap_or("None"),
+                ));
+            }
+            RunnerMode::Import => launcher.push_str("    exec(sys.stdin.read())\n"),
+        }
+        // tests' own skips are caught by the runner, only skipping the whole module reaches here
+        launcher.push_str(&format!(
+            "except BaseException as _pt_exc:\n    \
+             _pt_skiptest = getattr(sys.modules.get(\"unittest\"), \"SkipTest\", ())\n    \
+             if not (getattr(_pt_exc, \"allow_module_level\", False) or isinstance(_pt_exc, _pt_skiptest)):\n        \
+             raise\n    \
+             print(_pt_exc, file=sys.stderr)\n    \
+             sys.exit({MODULE_SKIPPED})\n"
+        ));
+        launcher
+    }
+}
+
+/// A python string literal. JSON string escapes are all valid in python.
+pub(crate) fn python_str(text: &str) -> String {
+    serde_json::to_string(text).expect("strings are always valid json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_module() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/package");
+        let module = TestModule::try_from(root.join("mypkg/test_imports.py").as_path()).unwrap();
+        assert_eq!("mypkg.test_imports", module.name);
+        assert_eq!(Some("mypkg"), module.package());
+        assert_eq!(root.canonicalize().unwrap(), module.basedir);
+
+        let module = TestModule::try_from(root.join("mypkg/__init__.py").as_path()).unwrap();
+        assert_eq!("mypkg", module.name);
+
+        let basic = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/basic/src.py");
+        let module = TestModule::try_from(basic.as_path()).unwrap();
+        assert_eq!("src", module.name);
+        assert_eq!(None, module.package());
+    }
+
+    #[test]
+    fn launcher() {
+        let module = TestModule {
+            path: PathBuf::from("/tests/mypkg/test_it.py"),
+            basedir: PathBuf::from("/tests"),
+            name: "mypkg.test_it".to_string(),
+        };
+        let launcher = module.launcher(
+            ImportMode::Append,
+            RunnerMode::Inline,
+            &[PathBuf::from("/src")],
+            false,
+        );
+        assert!(
+            launcher.contains("sys.path[0:0] = [\"/src\"]\n"),
+            "{launcher}"
+        );
+        assert!(
+            launcher.contains("sys.path.append(\"/tests\")\n"),
+            "{launcher}"
+        );
+        assert!(
+            launcher.contains("_pt_module.__package__ = \"mypkg\"\n"),
+            "{launcher}"
+        );
+        assert!(
+            launcher.contains("if importlib.util.find_spec(\"pytest\") is None:\n"),
+            "{launcher}"
+        );
+        assert!(
+            launcher.contains("exec(compile(sys.stdin.read(), \"/tests/mypkg/test_it.py\", \"exec\"), _pt_module.__dict__)\n"),
+            "{launcher}"
+        );
+        assert!(
+            launcher.contains(&format!("sys.exit({MODULE_SKIPPED})\n")),
+            "{launcher}"
+        );
+        let launcher = module.launcher(ImportMode::Importlib, RunnerMode::Import, &[], true);
+        assert!(!launcher.contains("sys.path"), "{launcher}");
+        assert!(launcher.contains("if True:\n"), "{launcher}");
+        assert!(!launcher.contains("_pt_module"), "{launcher}");
+    }
+}
@@ -69,7 +69,7 @@ impl Multiline for &str {
             }),
             Location::Offset(pos) => {
                 // Not safe to index to (*pos + 1) as this may not be a valid UTF-8 char boundary
-                self[..*pos].lines().count() + 1
+                self[..*pos].matches('\n').count() + 1
             }
             Location::Line(line) => *line,
         }
@@ -170,5 +170,9 @@ mod tests {
         assert_eq!(1, text.as_str().line_no(&end_of_line_1));
         let start_of_line_2 = Location::Char(8);
         assert_eq!(2, text.as_str().line_no(&start_of_line_2));
+        let middle_of_line_2 = Location::Offset(10);
+        assert_eq!(2, text.as_str().line_no(&middle_of_line_2));
+        let start_of_line_3 = Location::Offset(14);
+        assert_eq!(3, text.as_str().line_no(&start_of_line_3));
     }
 }
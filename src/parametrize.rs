@@ -0,0 +1,410 @@
+//! Parametrized tests: `@pytest.mark.parametrize` and fixtures with `params`, each case of which
+//! is run as a separate test, `testname[id]`
+
+use ruff_python_ast::{Arguments, Decorator, Expr, UnaryOp};
+use ruff_text_size::Ranged;
+
+use crate::{
+    Error,
+    pytests::mark,
+    skipping::{SkipMark, skip_marks},
+};
+
+/// One set of values for the argnames of a `Parametrization`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Case {
+    pub(crate) id: String,
+    /// python source of each value
+    pub(crate) values: Vec<String>,
+    /// names of the marks given with `pytest.param(..., marks=)`
+    pub(crate) markers: Vec<String>,
+    /// those marks which are `skip`, `skipif` or `xfail`
+    pub(crate) skip_marks: Vec<SkipMark>,
+}
+
+/// The cases of one `@pytest.mark.parametrize`, or of a fixture's `params`.
+///
+/// Cases are only known if the values are given as a list or tuple in the source, as pt expands
+/// tests without running python. Otherwise `unexpanded` is the reason, and tests using it fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Parametrization {
+    pub(crate) argnames: Vec<String>,
+    /// argnames whose values are passed to the fixture of the same name, as `request.param`
+    pub(crate) indirect: Vec<String>,
+    pub(crate) cases: Vec<Case>,
+    pub(crate) unexpanded: Option<String>,
+}
+
+impl Parametrization {
+    /// From the arguments of `@pytest.mark.parametrize(argnames, argvalues, indirect=, ids=)`.
+    pub(crate) fn from_mark(src: &str, arguments: &Arguments) -> Result<Self, String> {
+        let argnames = match arguments.find_argument_value("argnames", 0) {
+            Some(Expr::StringLiteral(names)) => names
+                .value
+                .to_str()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Some(Expr::List(list)) => string_literals(&list.elts)?,
+            Some(Expr::Tuple(tuple)) => string_literals(&tuple.elts)?,
+            _ => return Err("argnames must be a string, list or tuple of names".to_string()),
+        };
+        let indirect = match arguments
+            .find_keyword("indirect")
+            .map(|keyword| &keyword.value)
+        {
+            Some(Expr::BooleanLiteral(indirect)) if indirect.value => argnames.clone(),
+            Some(Expr::List(list)) => string_literals(&list.elts)?,
+            Some(Expr::Tuple(tuple)) => string_literals(&tuple.elts)?,
+            _ => Vec::new(),
+        };
+        let argvalues = arguments.find_argument_value("argvalues", 1);
+        let (cases, unexpanded) = match unexpanded(src, argvalues) {
+            Some(reason) => (Vec::new(), Some(reason)),
+            None => (cases(src, &argnames, argvalues, ids(arguments))?, None),
+        };
+        Ok(Self {
+            argnames,
+            indirect,
+            cases,
+            unexpanded,
+        })
+    }
+
+    /// From the arguments of `@pytest.fixture(params=, ids=)` for fixture `name`, if it has
+    /// `params`.
+    pub(crate) fn from_fixture(
+        src: &str,
+        name: &str,
+        arguments: &Arguments,
+    ) -> Result<Option<Self>, String> {
+        let Some(params) = arguments.find_keyword("params") else {
+            return Ok(None);
+        };
+        let argnames = vec![name.to_string()];
+        let (cases, unexpanded) = match unexpanded(src, Some(&params.value)) {
+            Some(reason) => (Vec::new(), Some(reason)),
+            None => (
+                cases(src, &argnames, Some(&params.value), ids(arguments))?,
+                None,
+            ),
+        };
+        Ok(Some(Self {
+            indirect: argnames.clone(),
+            argnames,
+            cases,
+            unexpanded,
+        }))
+    }
+}
+
+/// Each `@pytest.mark.parametrize` of test `testname` in `decorators`, from the python `src`.
+pub(crate) fn parametrizations(
+    src: &str,
+    testname: &str,
+    decorators: &[Decorator],
+) -> Result<Vec<Parametrization>, Error> {
+    parametrize_marks(decorators)
+        .map(|arguments| {
+            Parametrization::from_mark(src, arguments)
+                .map_err(|msg| Error::InvalidParametrize(format!("In {testname}: {msg}")))
+        })
+        .collect()
+}
+
+/// The arguments of each `@pytest.mark.parametrize` or `@mark.parametrize` in `decorators`,
+/// innermost first, which is the order pytest applies them.
+fn parametrize_marks(decorators: &[Decorator]) -> impl Iterator<Item = &Arguments> {
+    decorators.iter().rev().filter_map(|decorator| {
+        let Expr::Call(call) = &decorator.expression else {
+            return None;
+        };
+        let Expr::Attribute(parametrize) = &*call.func else {
+            return None;
+        };
+        let is_mark = match &*parametrize.value {
+            Expr::Attribute(mark) => {
+                mark.attr.as_str() == "mark"
+                    && matches!(&*mark.value, Expr::Name(pytest) if pytest.id.as_str() == "pytest")
+            }
+            Expr::Name(mark) => mark.id.as_str() == "mark",
+            _ => false,
+        };
+        (is_mark && parametrize.attr.as_str() == "parametrize").then_some(&call.arguments)
+    })
+}
+
+fn string_literals(exprs: &[Expr]) -> Result<Vec<String>, String> {
+    exprs
+        .iter()
+        .map(|expr| match expr {
+            Expr::StringLiteral(name) => Ok(name.value.to_str().to_string()),
+            _ => Err("argnames must be a string, list or tuple of names".to_string()),
+        })
+        .collect()
+}
+
+/// The `ids=` given as a list or tuple, `None` entries use the default id.
+fn ids(arguments: &Arguments) -> Vec<Option<String>> {
+    let ids = match arguments.find_keyword("ids").map(|keyword| &keyword.value) {
+        Some(Expr::List(list)) => &list.elts[..],
+        Some(Expr::Tuple(tuple)) => &tuple.elts[..],
+        _ => &[],
+    };
+    ids.iter()
+        .map(|id| match id {
+            Expr::StringLiteral(id) => Some(id.value.to_str().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Why pt cannot expand `argvalues` which are not a list or tuple, e.g. a name or a
+/// comprehension, whose values are only known when python runs.
+fn unexpanded(src: &str, argvalues: Option<&Expr>) -> Option<String> {
+    match argvalues? {
+        Expr::List(_) | Expr::Tuple(_) => None,
+        argvalues => Some(format!(
+            "pt cannot expand the values {}, as it requires a list or tuple",
+            &src[argvalues.range()]
+        )),
+    }
+}
+
+/// A case for each of `argvalues`, ids are made unique as pytest does, by adding a number.
+fn cases(
+    src: &str,
+    argnames: &[String],
+    argvalues: Option<&Expr>,
+    ids: Vec<Option<String>>,
+) -> Result<Vec<Case>, String> {
+    let argvalues = match argvalues {
+        Some(Expr::List(list)) => &list.elts[..],
+        Some(Expr::Tuple(tuple)) => &tuple.elts[..],
+        _ => return Err("argvalues must be given".to_string()),
+    };
+    let mut cases = argvalues
+        .iter()
+        .zip(ids.into_iter().chain(std::iter::repeat(None)))
+        .enumerate()
+        .map(|(index, (argvalue, id))| {
+            // `pytest.param(*values, id=, marks=)`
+            let (values, param_id, marks): (Vec<&Expr>, _, _) = match argvalue {
+                Expr::Call(call) if is_param(&call.func) => {
+                    let id = match call.arguments.find_keyword("id").map(|id| &id.value) {
+                        Some(Expr::StringLiteral(id)) => Some(id.value.to_str().to_string()),
+                        _ => None,
+                    };
+                    let marks = match call
+                        .arguments
+                        .find_keyword("marks")
+                        .map(|marks| &marks.value)
+                    {
+                        Some(Expr::List(list)) => list.elts.iter().collect(),
+                        Some(Expr::Tuple(tuple)) => tuple.elts.iter().collect(),
+                        Some(mark) => vec![mark],
+                        None => Vec::new(),
+                    };
+                    (call.arguments.args.iter().collect(), id, marks)
+                }
+                _ if argnames.len() == 1 => (vec![argvalue], None, Vec::new()),
+                Expr::Tuple(tuple) => (tuple.elts.iter().collect(), None, Vec::new()),
+                Expr::List(list) => (list.elts.iter().collect(), None, Vec::new()),
+                _ => (vec![argvalue], None, Vec::new()),
+            };
+            if values.len() != argnames.len() {
+                return Err(format!(
+                    "wrong number of values in parametrize case {index}, expected {} for {}",
+                    argnames.len(),
+                    argnames.join(", ")
+                ));
+            }
+            let id = param_id.or(id).unwrap_or_else(|| {
+                let ids: Vec<String> = values
+                    .iter()
+                    .zip(argnames)
+                    .map(|(value, argname)| {
+                        literal_id(src, value).unwrap_or_else(|| format!("{argname}{index}"))
+                    })
+                    .collect();
+                ids.join("-")
+            });
+            Ok(Case {
+                id: ascii_escaped(&id),
+                values: values
+                    .into_iter()
+                    .map(|value| src[value.range()].to_string())
+                    .collect(),
+                markers: marks
+                    .iter()
+                    .filter_map(|expr| mark(expr))
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+                skip_marks: skip_marks(src, marks),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let duplicated: Vec<String> = cases
+        .iter()
+        .filter(|case| cases.iter().filter(|other| other.id == case.id).count() > 1)
+        .map(|case| case.id.clone())
+        .collect();
+    let mut counts = std::collections::HashMap::new();
+    for case in cases
+        .iter_mut()
+        .filter(|case| duplicated.contains(&case.id))
+    {
+        let count = counts.entry(case.id.clone()).or_insert(0);
+        case.id = format!("{}{count}", case.id);
+        *count += 1;
+    }
+    Ok(cases)
+}
+
+/// `pytest.param` or `param`
+fn is_param(func: &Expr) -> bool {
+    match func {
+        Expr::Attribute(param) => {
+            param.attr.as_str() == "param"
+                && matches!(&*param.value, Expr::Name(pytest) if pytest.id.as_str() == "pytest")
+        }
+        Expr::Name(param) => param.id.as_str() == "param",
+        _ => false,
+    }
+}
+
+/// pytest's id for a literal value, as `str(value)`.
+fn literal_id(src: &str, value: &Expr) -> Option<String> {
+    match value {
+        Expr::StringLiteral(string) => Some(string.value.to_str().to_string()),
+        Expr::NumberLiteral(_) => Some(src[value.range()].to_string()),
+        Expr::UnaryOp(unary)
+            if matches!(unary.op, UnaryOp::USub | UnaryOp::UAdd)
+                && matches!(&*unary.operand, Expr::NumberLiteral(_)) =>
+        {
+            Some(src[value.range()].to_string())
+        }
+        Expr::BooleanLiteral(boolean) => Some(if boolean.value { "True" } else { "False" }.into()),
+        Expr::NoneLiteral(_) => Some("None".to_string()),
+        _ => None,
+    }
+}
+
+/// `id` as pytest shows it, with python's `unicode_escape` escapes for anything other than
+/// printable ascii.
+fn ascii_escaped(id: &str) -> String {
+    id.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '\t' => "\\t".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            ' '..='~' => c.to_string(),
+            '\0'..='\u{ff}' => format!("\\x{:02x}", c as u32),
+            '\u{100}'..='\u{ffff}' => format!("\\u{:04x}", c as u32),
+            _ => format!("\\U{:08x}", c as u32),
+        })
+        .collect()
+}
+
+/// A combination of one case from each of a test's parametrizations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Combination {
+    /// the ids of the cases, `-` separated
+    pub(crate) id: String,
+    /// `(argname, value)` for each argname
+    pub(crate) values: Vec<(String, String)>,
+    /// names of the cases' marks
+    pub(crate) markers: Vec<String>,
+    /// the cases' `skip`, `skipif` and `xfail` marks
+    pub(crate) skip_marks: Vec<SkipMark>,
+}
+
+/// Every combination of one case from each of `parametrizations`, the first varying slowest.
+pub(crate) fn combinations(parametrizations: &[&Parametrization]) -> Vec<Combination> {
+    let mut combinations = vec![(Vec::new(), Vec::new(), Vec::new(), Vec::new())];
+    for parametrization in parametrizations {
+        combinations = combinations
+            .into_iter()
+            .flat_map(
+                |(ids, values, markers, marks): (Vec<&str>, Vec<_>, Vec<_>, Vec<_>)| {
+                    parametrization.cases.iter().map(move |case| {
+                        let mut ids = ids.clone();
+                        ids.push(&case.id);
+                        let mut values = values.clone();
+                        values.extend(
+                            parametrization
+                                .argnames
+                                .iter()
+                                .cloned()
+                                .zip(case.values.iter().cloned()),
+                        );
+                        let mut markers = markers.clone();
+                        markers.extend(case.markers.iter().cloned());
+                        let mut marks = marks.clone();
+                        marks.extend(case.skip_marks.iter().cloned());
+                        (ids, values, markers, marks)
+                    })
+                },
+            )
+            .collect();
+    }
+    combinations
+        .into_iter()
+        .map(|(ids, values, markers, skip_marks)| Combination {
+            id: ids.join("-"),
+            values,
+            markers,
+            skip_marks,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(id: &str, values: &[&str]) -> Case {
+        Case {
+            id: id.to_string(),
+            values: values.iter().map(|value| value.to_string()).collect(),
+            markers: Vec::new(),
+            skip_marks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn combined_ids() {
+        let first = Parametrization {
+            argnames: vec!["y".to_string()],
+            indirect: Vec::new(),
+            cases: vec![case("2", &["2"]), case("3", &["3"])],
+            unexpanded: None,
+        };
+        let second = Parametrization {
+            argnames: vec!["x".to_string()],
+            indirect: Vec::new(),
+            cases: vec![case("a", &["'a'"]), case("x1", &["[1]"])],
+            unexpanded: None,
+        };
+        let ids: Vec<String> = combinations(&[&first, &second])
+            .into_iter()
+            .map(|combination| combination.id)
+            .collect();
+        assert_eq!(vec!["2-a", "2-x1", "3-a", "3-x1"], ids);
+        assert_eq!(
+            vec![
+                ("y".to_string(), "3".to_string()),
+                ("x".to_string(), "[1]".to_string())
+            ],
+            combinations(&[&first, &second])[3].values
+        );
+    }
+
+    #[test]
+    fn escaped_ids() {
+        assert_eq!("a b\\n\\\\\\xe9\\u2603", ascii_escaped("a b\n\\é☃"));
+    }
+}
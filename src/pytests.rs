@@ -1,30 +1,318 @@
 //!Handling individual tests & their status
 
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
 use base_traits::AsStr;
-use ruff_python_ast::StmtFunctionDef;
+use ruff_python_ast::{
+    Arguments, Decorator, Expr, ExprCall, Stmt, StmtClassDef, StmtFunctionDef,
+    visitor::{Visitor, walk_expr, walk_stmt},
+};
+use ruff_python_parser::parse_module;
+use ruff_text_size::{Ranged, TextRange};
 
 use crate::{
-    Error, Exception, Traceback,
-    failures::TracebackLine,
+    Error, Exception, Traceback, TracebackStyle,
+    failures::{Frame, TracebackException},
+    fixtures::{dynamic_fixtures, parameter_names, runtime_fixtures},
     multiline::{Location, Multiline, MultilineMut},
+    parametrize::Parametrization,
+    python::PythonVersion,
+    skipping::{SkipMark, skip_marks},
 };
 
+/// Options for `PythonTest::report_with()`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub tb: TracebackStyle,
+    /// Show local variables for each frame
+    pub showlocals: bool,
+    /// Version of python which ran the tests. Before 3.11 python prints no carets, so they are
+    /// recreated.
+    pub python: Option<PythonVersion>,
+    /// Locations of frames outside the test module are shown relative to `rootdir`
+    pub rootdir: Option<PathBuf>,
+}
+
 /// Owned details of single test. Does not store the original source text, only the AST.
 /// Construct with `Pytest::from(ruff_python_ast::StmtFunctionDef)`
 #[derive(Debug, PartialEq)]
 pub(crate) struct TestDetails {
     pub ast: StmtFunctionDef,
     pub status: TestStatus,
+    /// names of `@pytest.mark.<name>` decorators
+    pub markers: Vec<String>,
+    /// names of the fixtures the test requests: its parameters without defaults
+    pub fixtures: Vec<String>,
+    /// names of fixtures the test requests with `request.getfixturevalue("name")`
+    pub dynamic_fixtures: Vec<String>,
+    /// whether the test calls `request.getfixturevalue(name)` where `name` is not a literal
+    pub runtime_fixtures: bool,
+    /// names of fixtures used with `@pytest.mark.usefixtures`, on the test, its class or module
+    pub usefixtures: Vec<String>,
+    /// `@pytest.mark.filterwarnings` filters, on the test, its class or module, in the order
+    /// pytest applies them
+    pub filterwarnings: Vec<String>,
+    /// `skip`, `skipif` and `xfail` marks, on the test, its class or module, in the order pytest
+    /// evaluates them
+    pub skip_marks: Vec<SkipMark>,
+    /// `(stream, output)` captured while the test ran, if it failed
+    pub captured: Vec<(String, String)>,
+    /// warnings emitted while the test ran, each as python formats it
+    pub warnings: Vec<String>,
+    /// `(file, source)` of the files in a failed test's traceback other than the test module, as
+    /// the runner read them
+    pub sources: Vec<(String, String)>,
+    /// its `@pytest.mark.parametrize` marks, before the test is expanded into a test per case
+    pub parametrize: Vec<Parametrization>,
+    /// `(argname, value)` passed directly to the test, for one case of a parametrized test
+    pub params: Vec<(String, String)>,
+    /// `(fixture, value)` for `request.param`, for one case of a parametrized test
+    pub fixture_params: Vec<(String, String)>,
+    /// a test method of a `unittest.TestCase`, run by unittest
+    pub unittest: bool,
+    /// why a parametrized test could not be expanded into a test per case, it fails when run
+    pub unexpanded: Option<String>,
 }
 
 impl From<StmtFunctionDef> for TestDetails {
     //TODO: convert to TryFrom and include logic to validate whether this is a test function.
     fn from(fndef: StmtFunctionDef) -> Self {
         Self {
+            markers: marker_names(&fndef.decorator_list).collect(),
+            fixtures: parameter_names(&fndef),
+            dynamic_fixtures: dynamic_fixtures(&fndef),
+            runtime_fixtures: runtime_fixtures(&fndef),
+            usefixtures: usefixtures(fndef.decorator_list.iter().map(|mark| &mark.expression)),
+            filterwarnings: filterwarnings(
+                fndef
+                    .decorator_list
+                    .iter()
+                    .rev()
+                    .map(|mark| &mark.expression),
+            ),
             ast: fndef,
             status: Default::default(),
+            skip_marks: Vec::new(),
+            captured: Vec::new(),
+            warnings: Vec::new(),
+            sources: Vec::new(),
+            parametrize: Vec::new(),
+            params: Vec::new(),
+            fixture_params: Vec::new(),
+            unittest: false,
+            unexpanded: None,
+        }
+    }
+}
+
+impl TestDetails {
+    /// One case of a parametrized test, with the `(argname, value)` for each of its parameters.
+    pub(crate) fn case(&self, values: Vec<(String, String)>) -> Self {
+        let (params, fixture_params) = values
+            .into_iter()
+            .partition(|(argname, _)| self.direct_argnames().any(|direct| direct == argname));
+        Self {
+            ast: self.ast.clone(),
+            status: Default::default(),
+            markers: self.markers.clone(),
+            fixtures: self.fixtures.clone(),
+            dynamic_fixtures: self.dynamic_fixtures.clone(),
+            runtime_fixtures: self.runtime_fixtures,
+            usefixtures: self.usefixtures.clone(),
+            filterwarnings: self.filterwarnings.clone(),
+            skip_marks: self.skip_marks.clone(),
+            captured: Vec::new(),
+            warnings: Vec::new(),
+            sources: Vec::new(),
+            parametrize: Vec::new(),
+            params,
+            fixture_params,
+            unittest: self.unittest,
+            unexpanded: None,
+        }
+    }
+
+    /// Names of the fixtures the test uses, in pytest's order, after the `autouse` fixtures
+    /// available to it. Arguments parametrized directly are not fixtures.
+    pub(crate) fn requested<'a>(&'a self, autouse: Vec<&'a str>) -> impl Iterator<Item = &'a str> {
+        let requested = self
+            .usefixtures
+            .iter()
+            .chain(&self.fixtures)
+            .chain(&self.dynamic_fixtures)
+            .map(String::as_str);
+        autouse.into_iter().chain(requested).filter(|name| {
+            !self.direct_argnames().any(|argname| argname == name)
+                && !self.params.iter().any(|(argname, _)| argname == name)
+        })
+    }
+
+    /// The source of `request.param` for fixture `name`, for one case of a parametrized test.
+    pub(crate) fn fixture_param(&self, name: &str) -> Option<&str> {
+        self.fixture_params
+            .iter()
+            .find(|(fixture, _)| fixture == name)
+            .map(|(_, param)| param.as_str())
+    }
+
+    /// The argnames passed directly to the test by its `@pytest.mark.parametrize` marks.
+    pub(crate) fn direct_argnames(&self) -> impl Iterator<Item = &String> {
+        self.parametrize.iter().flat_map(|parametrization| {
+            parametrization
+                .argnames
+                .iter()
+                .filter(|argname| !parametrization.indirect.contains(argname))
+        })
+    }
+}
+
+/// Names of all `@pytest.mark.<name>` or `@mark.<name>` decorators, with or without arguments.
+pub(crate) fn marker_names(decorators: &[Decorator]) -> impl Iterator<Item = String> {
+    decorators
+        .iter()
+        .filter_map(|decorator| mark(&decorator.expression))
+        .map(|(name, _)| name.to_string())
+}
+
+/// The name and any arguments of `pytest.mark.<name>` or `mark.<name>`.
+pub(crate) fn mark(expr: &Expr) -> Option<(&str, Option<&Arguments>)> {
+    let (expr, arguments) = match expr {
+        Expr::Call(call) => (&*call.func, Some(&call.arguments)),
+        expr => (expr, None),
+    };
+    let Expr::Attribute(marker) = expr else {
+        return None;
+    };
+    let is_mark = match &*marker.value {
+        Expr::Attribute(mark) => {
+            mark.attr.as_str() == "mark"
+                && matches!(&*mark.value, Expr::Name(pytest) if pytest.id.as_str() == "pytest")
+        }
+        Expr::Name(mark) => mark.id.as_str() == "mark",
+        _ => false,
+    };
+    is_mark.then_some((marker.attr.as_str(), arguments))
+}
+
+/// The marks assigned to `pytestmark` in a module or class `body`: a single mark, or a list or
+/// tuple of them.
+pub(crate) fn pytestmark(body: &[Stmt]) -> Vec<&Expr> {
+    body.iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Assign(assign)
+                if matches!(&assign.targets[..], [Expr::Name(target)] if target.id.as_str() == "pytestmark") =>
+            {
+                Some(&*assign.value)
+            }
+            _ => None,
+        })
+        .next_back()
+        .map(|value| match value {
+            Expr::List(list) => list.elts.iter().collect(),
+            Expr::Tuple(tuple) => tuple.elts.iter().collect(),
+            mark => vec![mark],
+        })
+        .unwrap_or_default()
+}
+
+/// Names of the fixtures each `pytest.mark.usefixtures("name", ...)` in `marks` uses.
+pub(crate) fn usefixtures<'a>(marks: impl IntoIterator<Item = &'a Expr>) -> Vec<String> {
+    string_arguments(marks, "usefixtures")
+}
+
+/// The filters of each `pytest.mark.filterwarnings("filter", ...)` in `marks`.
+pub(crate) fn filterwarnings<'a>(marks: impl IntoIterator<Item = &'a Expr>) -> Vec<String> {
+    string_arguments(marks, "filterwarnings")
+}
+
+/// The string literal arguments of each `pytest.mark.<name>(...)` in `marks`.
+fn string_arguments<'a>(marks: impl IntoIterator<Item = &'a Expr>, name: &str) -> Vec<String> {
+    marks
+        .into_iter()
+        .filter_map(mark)
+        .filter(|(mark, _)| *mark == name)
+        .filter_map(|(_, arguments)| arguments)
+        .flat_map(|arguments| &arguments.args)
+        .filter_map(|fixture| match fixture {
+            Expr::StringLiteral(fixture) => Some(fixture.value.to_str().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The marks a module or class applies to each of its tests, after the test's own.
+#[derive(Debug, Default)]
+pub(crate) struct InheritedMarks {
+    pub markers: Vec<String>,
+    pub usefixtures: Vec<String>,
+    pub filterwarnings: Vec<String>,
+    pub skip_marks: Vec<SkipMark>,
+}
+
+impl InheritedMarks {
+    /// The marks a module's `pytestmark` applies.
+    pub(crate) fn module(src: &str, body: &[Stmt]) -> Self {
+        let marks = pytestmark(body);
+        Self {
+            skip_marks: skip_marks(src, marks.iter().copied()),
+            markers: marks
+                .iter()
+                .filter_map(|expr| mark(expr))
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            filterwarnings: filterwarnings(marks.iter().copied()),
+            usefixtures: usefixtures(marks),
+        }
+    }
+
+    /// The marks a class's decorators and `pytestmark` apply, followed by `outer`: those of its
+    /// module, or of the base class it inherits its tests from.
+    pub(crate) fn class(src: &str, class: &StmtClassDef, outer: &Self) -> Self {
+        let decorators = class
+            .decorator_list
+            .iter()
+            .map(|decorator| &decorator.expression);
+        let class_marks: Vec<&Expr> = decorators.clone().chain(pytestmark(&class.body)).collect();
+        let markers = class_marks
+            .iter()
+            .filter_map(|expr| mark(expr))
+            .map(|(name, _)| name.to_string())
+            .chain(outer.markers.iter().cloned())
+            .collect();
+        let mut class_usefixtures = usefixtures(class_marks);
+        class_usefixtures.extend(outer.usefixtures.iter().cloned());
+        // closest first: `pytestmark`, then the decorators from the innermost
+        let mut class_filterwarnings = filterwarnings(
+            pytestmark(&class.body)
+                .into_iter()
+                .chain(decorators.clone().rev()),
+        );
+        class_filterwarnings.extend(outer.filterwarnings.iter().cloned());
+        let mut class_skip_marks = skip_marks(
+            src,
+            pytestmark(&class.body).into_iter().chain(decorators.rev()),
+        );
+        class_skip_marks.extend(outer.skip_marks.iter().cloned());
+        Self {
+            markers,
+            usefixtures: class_usefixtures,
+            filterwarnings: class_filterwarnings,
+            skip_marks: class_skip_marks,
         }
     }
+
+    /// Add these marks to those of a test, which apply first.
+    pub(crate) fn apply(&self, details: &mut TestDetails) {
+        details.markers.extend(self.markers.iter().cloned());
+        details.usefixtures.extend(self.usefixtures.iter().cloned());
+        details
+            .filterwarnings
+            .extend(self.filterwarnings.iter().cloned());
+        details.skip_marks.extend(self.skip_marks.iter().cloned());
+    }
 }
 
 /// A single test, with references to the full module source and the test details.
@@ -34,65 +322,321 @@ pub struct PythonTest<'name, 'suite, 'details> {
     pub full_src: &'suite str,
     pub test_ast: &'details StmtFunctionDef,
     pub status: &'details TestStatus,
+    pub markers: &'details [String],
+    /// `(stream, output)` captured while the test ran, see `TestSuite::update_status()`
+    pub captured: &'details [(String, String)],
+    /// warnings emitted while the test ran, see `TestSuite::update_status()`
+    pub warnings: &'details [String],
+    /// `(file, source)` of other files in the traceback, see `TestSuite::update_status()`
+    pub sources: &'details [(String, String)],
 }
 
 impl PythonTest<'_, '_, '_> {
     /// Produce a test execution report.
     pub fn report(&self) -> Option<String> {
-        enum Prefix {
-            Text(String),
-            Indent(usize),
-        }
+        self.report_with(&ReportOptions::default())
+    }
 
-        let TestStatus::Fail(err, tb) = &self.status else {
+    /// Produce a test execution report in the given traceback style.
+    pub fn report_with(&self, options: &ReportOptions) -> Option<String> {
+        let TestStatus::Fail(_, tb) = &self.status else {
             return None;
         };
+        if options.tb == TracebackStyle::No {
+            return None;
+        }
+        let chain = match tb.chain() {
+            Ok(chain) => chain,
+            Err(err) => return Some(err.to_string()),
+        };
+
+        let mut report = String::new();
+        match options.tb {
+            TracebackStyle::Line => {
+                let last = chain.last()?;
+                let location = self
+                    .test_frames(&last.frames)
+                    .last()
+                    .map(|(frame, _)| {
+                        format!("{}:{}: ", frame.header.file_name, frame.header.line_number)
+                    })
+                    .unwrap_or_default();
+                report.push_line(0, [location.as_str(), &last.exception.join(" ")]);
+                return Some(report);
+            }
+            TracebackStyle::Auto => report.push_line(0, ["==== ", self.testname, " ===="]),
+            _ => (),
+        }
+        self.push_chain(&mut report, &chain, options, true);
+        for (stream, output) in self.captured {
+            report.push_line(0, ["---- Captured ", stream.as_str(), " call ----"]);
+            report.push_str(output);
+        }
+        Some(report)
+    }
+
+    /// Each exception in a chain, separated by python's chain messages. The sub-exceptions of an
+    /// `ExceptionGroup` are drawn inside `| ` borders, as python does.
+    fn push_chain(
+        &self,
+        report: &mut String,
+        chain: &[TracebackException],
+        options: &ReportOptions,
+        outermost: bool,
+    ) {
+        for (idx, exception) in chain.iter().enumerate() {
+            if let Some(cause) = exception.cause {
+                report.push_newline();
+                report.push_line(0, [cause.as_str()]);
+                report.push_newline();
+            }
+            self.push_exception(report, exception, options, outermost && idx == 0);
+            for (number, member) in exception.exceptions.iter().enumerate() {
+                let mut member_report = String::new();
+                self.push_chain(&mut member_report, member, options, false);
+                let number = (number + 1).to_string();
+                report.push_line(0, ["+---------------- ", &number, " ----------------"]);
+                member_report
+                    .lines()
+                    .for_each(|line| report.push_line(0, ["| ", line]));
+            }
+            if !exception.exceptions.is_empty() {
+                report.push_line(0, ["+------------------------------------"]);
+            }
+        }
+    }
+
+    /// The frames of a single exception followed by the exception itself. In `auto` style the
+    /// first frame of the `outermost` exception is titled by the report header.
+    fn push_exception(
+        &self,
+        report: &mut String,
+        exception: &TracebackException,
+        options: &ReportOptions,
+        outermost: bool,
+    ) {
+        match options.tb {
+            TracebackStyle::Native => {
+                if !exception.frames.is_empty() {
+                    report.push_line(0, ["Traceback (most recent call last):"]);
+                }
+                for frame in &exception.frames {
+                    push_native_frame(report, frame, options.showlocals);
+                }
+            }
+            TracebackStyle::Long => {
+                for (frame, source) in &self.test_frames(&exception.frames) {
+                    source.push_title(report, "====", frame, options.rootdir.as_deref());
+                    source.push_frame(report, frame, options);
+                }
+            }
+            TracebackStyle::Short => {
+                for (frame, _) in &self.test_frames(&exception.frames) {
+                    push_short_frame(report, frame, options.showlocals);
+                }
+            }
+            _ => {
+                // Auto: each frame in full
+                let frames = self.test_frames(&exception.frames);
+                for (idx, (frame, source)) in frames.iter().enumerate() {
+                    if idx > 0 || !outermost {
+                        source.push_title(report, "----", frame, options.rootdir.as_deref());
+                    }
+                    source.push_frame(report, frame, options);
+                }
+            }
+        }
+        exception
+            .exception
+            .iter()
+            .for_each(|line| report.push_line(0, [*line]));
+    }
+
+    /// Frames to report, each with the source of the file it refers to. pt's runner code, which is
+    /// appended to the test module, is hidden, as is pt's `pytest` module, as pytest hides its own.
+    fn test_frames<'frames, 'tb>(
+        &self,
+        frames: &'frames [Frame<'tb>],
+    ) -> Vec<(&'frames Frame<'tb>, FrameSource<'_>)> {
+        let src_lines = self.full_src.lines().count();
+        frames
+            .iter()
+            .filter(|frame| frame.header.file_name != "<pt pytest>")
+            .map(|frame| (frame, self.frame_source(frame)))
+            .filter(|(frame, source)| !(source.test_module && frame.header.line_number > src_lines))
+            .collect()
+    }
 
-        let mut frame_buf = String::new();
-        match err {
-            Exception::AssertionError => {
-                let mut prefix = Prefix::Indent(0);
-                for line in tb.lines() {
-                    match line {
-                        Ok(TracebackLine::TracebackHeader) => (),
-                        Ok(TracebackLine::FrameHeader(frameheader)) => {
-                            frame_buf.clear(); // We only want details from the last frame
-
-                            let failure = Location::Line(frameheader.line_number);
-                            let testfn_def = Location::Offset(self.test_ast.range.start().into());
-                            let line_no = frameheader.line_number.to_string();
-                            let indent = line_no.len() + 2;
-
-                            frame_buf.push_line(0, ["==== ", frameheader.function_name, " ===="]);
-                            self.full_src
-                                .lines_from(&testfn_def)
-                                .lines_to(&failure)
-                                .for_each(|line| frame_buf.push_line(indent, [line]));
-
-                            prefix = Prefix::Text(line_no);
-                        }
-                        Ok(TracebackLine::FrameContents { text }) => match prefix {
-                            // TODO: compatibility python <3.13 ... need to manually recreate the
-                            //       nice details that are in later version Tracebacks
-                            Prefix::Text(lineno) => {
-                                frame_buf.push_line(0, [&lineno, ": ", text]);
-                                prefix = Prefix::Indent(lineno.len() + 2);
-                            }
-                            Prefix::Indent(indent) => {
-                                frame_buf.push_line(indent, [text]);
-                            }
-                        },
-                        Ok(TracebackLine::Exception(err)) => {
-                            frame_buf.push_line(0, [err.as_str()]);
-                        }
-                        Err(err) => frame_buf.push_line(0, [err.to_string().as_str()]),
+    /// Source text for the file named in a traceback frame. The runner reports the source of
+    /// each file in the traceback other than the test module, so frames without one are in the
+    /// test module, or pt's runner appended to it.
+    fn frame_source(&self, frame: &Frame) -> FrameSource<'_> {
+        match self
+            .sources
+            .iter()
+            .find(|(file, _)| file == frame.header.file_name)
+        {
+            Some((_, src)) => FrameSource {
+                src: (!src.is_empty()).then_some(src.as_str()),
+                test_module: false,
+            },
+            None => FrameSource {
+                src: Some(self.full_src),
+                test_module: true,
+            },
+        }
+    }
+}
+
+/// The source of the file a traceback frame refers to, if available.
+#[derive(Debug, Default)]
+struct FrameSource<'src> {
+    src: Option<&'src str>,
+    /// Frame is in the test module (or pt's runner), rather than a library or helper module
+    test_module: bool,
+}
+
+impl FrameSource<'_> {
+    /// `<marker> function <marker>`, including the location for frames outside the test module.
+    fn push_title(&self, report: &mut String, marker: &str, frame: &Frame, rootdir: Option<&Path>) {
+        let header = &frame.header;
+        if self.test_module {
+            report.push_line(0, [marker, " ", header.function_name, " ", marker]);
+        } else {
+            let file_name = Path::new(header.file_name);
+            let file_name = rootdir
+                .and_then(|rootdir| file_name.strip_prefix(rootdir).ok())
+                .unwrap_or(file_name);
+            let location = format!("{}:{}", file_name.display(), header.line_number);
+            report.push_line(
+                0,
+                [
+                    marker,
+                    " ",
+                    header.function_name,
+                    " (",
+                    &location,
+                    ") ",
+                    marker,
+                ],
+            );
+        }
+    }
+
+    /// Source from start of the enclosing function up to the failing line, carets and optionally
+    /// locals. Carets are realigned to the original indentation of the failing line.
+    fn push_frame(&self, report: &mut String, frame: &Frame, options: &ReportOptions) {
+        let header = &frame.header;
+        let line_no = header.line_number.to_string();
+        let indent = line_no.len() + 2;
+
+        let src = self.src;
+        let failing_line = src.and_then(|src| src.lines().nth(header.line_number.checked_sub(1)?));
+        // Before 3.13 python has no source for `<string>` frames, so the first local looks like
+        // the source line
+        let mut misparsed_local = None;
+        match (src, failing_line) {
+            (Some(src), Some(failing_line)) => {
+                if let Some((fn_start, _)) = enclosing_function(src, header.line_number) {
+                    src.lines_from(&Location::Line(fn_start))
+                        .lines_to(&Location::Line(header.line_number))
+                        .for_each(|line| report.push_line(indent, [line]));
+                }
+                report.push_line(0, [&line_no, ": ", failing_line]);
+                misparsed_local = frame
+                    .source
+                    .filter(|source| source.trim() != failing_line.trim());
+                // python prints the failing line dedented, then indented by 4
+                let original_indent = failing_line.len() - failing_line.trim_start().len();
+                for carets in &frame.carets {
+                    match original_indent.checked_sub(4) {
+                        Some(shift) => report.push_line(indent + shift, [*carets]),
+                        None => report.push_line(indent, [&carets[4 - original_indent..]]),
                     }
                 }
+                // from 3.11 python prints carets itself, where they are useful
+                if options
+                    .python
+                    .is_some_and(|python| python < PythonVersion::new(3, 11))
+                    && let Some(carets) = recreate_carets(src, header.line_number)
+                {
+                    report.push_line(indent, [carets.as_str()]);
+                }
             }
-            _ => todo!(),
-        };
-        Some(frame_buf)
+            _ => {
+                if let Some(source) = frame.source {
+                    report.push_line(0, [&line_no, ": ", source]);
+                }
+                frame
+                    .carets
+                    .iter()
+                    .for_each(|carets| report.push_line(indent, [*carets]));
+            }
+        }
+        if options.showlocals {
+            misparsed_local
+                .iter()
+                .chain(&frame.locals)
+                .for_each(|local| report.push_line(indent, [*local]));
+        }
+    }
+}
+
+/// `file:line: in function`, followed by the failing line, as printed by python.
+fn push_short_frame(report: &mut String, frame: &Frame, showlocals: bool) {
+    let header = &frame.header;
+    let location = format!("{}:{}: in ", header.file_name, header.line_number);
+    report.push_line(0, [location.as_str(), header.function_name]);
+    frame
+        .source
+        .iter()
+        .chain(&frame.carets)
+        .for_each(|line| report.push_line(0, [*line]));
+    if showlocals {
+        frame
+            .locals
+            .iter()
+            .for_each(|local| report.push_line(0, [*local]));
+    }
+}
+
+/// A frame as printed by python, without locals unless requested.
+fn push_native_frame(report: &mut String, frame: &Frame, showlocals: bool) {
+    report.push_line(0, [frame.header.to_line().as_str()]);
+    frame
+        .source
+        .iter()
+        .chain(&frame.carets)
+        .for_each(|line| report.push_line(0, [*line]));
+    if showlocals {
+        frame
+            .locals
+            .iter()
+            .for_each(|local| report.push_line(0, [*local]));
+    }
+}
+
+/// Line number (of the `def` or first decorator) & name of the innermost function containing
+/// `line_number`.
+pub(crate) fn enclosing_function(src: &str, line_number: usize) -> Option<(usize, String)> {
+    fn search(src: &str, stmts: &[Stmt], line_number: usize) -> Option<(usize, String)> {
+        stmts.iter().find_map(|stmt| {
+            let (range, body, name) = match stmt {
+                Stmt::FunctionDef(function) => {
+                    (function.range, &function.body, Some(&function.name))
+                }
+                Stmt::ClassDef(class) => (class.range, &class.body, None),
+                _ => return None,
+            };
+            let start = src.line_no(&Location::Offset(range.start().into()));
+            let end = src.line_no(&Location::Offset(range.end().into()));
+            if !(start..=end).contains(&line_number) {
+                return None;
+            }
+            search(src, body, line_number).or_else(|| Some((start, name?.to_string())))
+        })
     }
+    search(src, parse_module(src).ok()?.suite(), line_number)
 }
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -102,6 +646,14 @@ pub enum TestStatus {
     Running,
     Pass,
     Fail(Exception, Traceback),
+    /// `pytest.skip()` was called, with the reason
+    Skip(String),
+    /// `pytest.xfail()` was called, or the test failed as an `xfail` mark expects, with the reason
+    XFail(String),
+    /// the test passed despite an `xfail` mark, with the mark's reason
+    XPass(String),
+    /// Ctrl+C interrupted the test, at `file:line`
+    Interrupted(String),
 }
 
 impl AsStr for TestStatus {
@@ -111,6 +663,10 @@ impl AsStr for TestStatus {
             TestStatus::Running => "RUNNING",
             TestStatus::Pass => "PASS",
             TestStatus::Fail(_, _) => "FAIL",
+            TestStatus::Skip(_) => "SKIP",
+            TestStatus::XFail(_) => "XFAIL",
+            TestStatus::XPass(_) => "XPASS",
+            TestStatus::Interrupted(_) => "INTERRUPTED",
         }
     }
 }
@@ -123,7 +679,133 @@ impl TryFrom<(&str, &str)> for TestStatus {
             "RUNNING" => Ok(Self::Running),
             "PASS" => Ok(Self::Pass),
             "FAIL" => Ok(Self::Fail(traceback.try_into()?, traceback.into())),
+            "SKIP" => Ok(Self::Skip(traceback.trim_end().to_string())),
+            "XFAIL" => Ok(Self::XFail(traceback.trim_end().to_string())),
+            "XPASS" => Ok(Self::XPass(traceback.trim_end().to_string())),
+            "INTERRUPTED" => Ok(Self::Interrupted(traceback.trim_end().to_string())),
             _ => Err(Error::InvalidStatus(status.to_string())),
         }
     }
 }
+
+/// Recreate the carets python >= 3.11 prints under the failing part of a line, for tracebacks
+/// which do not include them: `^` under the test of an `assert`, or for the outermost call in
+/// a simple statement `~` under the function and `^` under the arguments.
+///
+/// Columns are characters from the start of the line in `src`.
+pub(crate) fn recreate_carets(src: &str, line_number: usize) -> Option<String> {
+    let line_start: usize = src
+        .split_inclusive('\n')
+        .take(line_number.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    let line_end = line_start + src.lines().nth(line_number - 1)?.len();
+    let parsed = parse_module(src).ok()?;
+
+    let mut statement = StatementOnLine {
+        line: line_start..line_end,
+        found: None,
+    };
+    statement.visit_body(parsed.suite());
+    let marks = match statement.found? {
+        Stmt::Assert(assert) => vec![(assert.test.range(), '^')],
+        stmt @ (Stmt::Expr(_) | Stmt::Assign(_) | Stmt::AnnAssign(_) | Stmt::Return(_)) => {
+            let mut call = OutermostCall { found: None };
+            walk_stmt(&mut call, stmt);
+            let call = call.found?;
+            let arguments = TextRange::new(call.func.end(), call.range.end());
+            vec![(call.func.range(), '~'), (arguments, '^')]
+        }
+        _ => return None,
+    };
+
+    // ranges are byte offsets, carets are one per character
+    let column = |offset: usize| src.get(line_start..offset).map(|text| text.chars().count());
+    let mut carets = String::new();
+    for (range, mark) in marks {
+        let (start, end): (usize, usize) = (range.start().into(), range.end().into());
+        if end > line_end {
+            return None;
+        }
+        let (start, end) = (column(start)?, column(end)?);
+        if start < carets.len() {
+            return None;
+        }
+        carets.extend(std::iter::repeat_n(' ', start - carets.len()));
+        carets.extend(std::iter::repeat_n(mark, end - start));
+    }
+    Some(carets)
+}
+
+/// Finds the first statement starting within `line`
+struct StatementOnLine<'a> {
+    line: Range<usize>,
+    found: Option<&'a Stmt>,
+}
+
+impl<'a> Visitor<'a> for StatementOnLine<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Stmt) {
+        if self.found.is_some() {
+            return;
+        }
+        if self.line.contains(&stmt.start().into()) {
+            self.found = Some(stmt);
+        } else {
+            walk_stmt(self, stmt);
+        }
+    }
+}
+
+/// Finds the first, outermost, call expression
+struct OutermostCall<'a> {
+    found: Option<&'a ExprCall>,
+}
+
+impl<'a> Visitor<'a> for OutermostCall<'a> {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match expr {
+            _ if self.found.is_some() => (),
+            Expr::Call(call) => self.found = Some(call),
+            expr => walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recreated_carets() {
+        let src = r#"def test_it():
+    assert seven() == 6, "message"
+    check(seven(), 6)
+    if True:
+        pass
+"#;
+        assert_eq!(
+            Some("           ^^^^^^^^^^^^".to_string()),
+            recreate_carets(src, 2)
+        );
+        assert_eq!(
+            Some("    ~~~~~^^^^^^^^^^^^".to_string()),
+            recreate_carets(src, 3)
+        );
+        assert_eq!(None, recreate_carets(src, 4));
+        assert_eq!(None, recreate_carets(src, 10));
+    }
+
+    #[test]
+    fn recreated_carets_non_ascii() {
+        let src =
+            "def test_it():\n    résultat = check(\"crème\")\n    assert résultat == \"brûlée\"\n";
+        assert_eq!(
+            Some("               ~~~~~^^^^^^^^^".to_string()),
+            recreate_carets(src, 2)
+        );
+        assert_eq!(
+            Some("           ^^^^^^^^^^^^^^^^^^^^".to_string()),
+            recreate_carets(src, 3)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Python interpreter versions
+
+use std::{fmt, str::FromStr};
+
+use crate::Error;
+
+/// `major.minor` version of a python interpreter.
+///
+/// Usage:
+/// ```rust
+/// use pt::PythonVersion;
+/// let version: PythonVersion = "Python 3.11.7".parse().unwrap();
+/// assert_eq!(PythonVersion::new(3, 11), version);
+/// assert!(version.is_supported());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PythonVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl PythonVersion {
+    /// Oldest supported version
+    pub const MIN: Self = Self::new(3, 8);
+    /// Newest supported version
+    pub const MAX: Self = Self::new(3, 14);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        (Self::MIN..=Self::MAX).contains(self)
+    }
+
+    /// `sys.exception()` and `TracebackException.print()` are available from 3.11
+    pub(crate) fn has_sys_exception(&self) -> bool {
+        *self >= Self::new(3, 11)
+    }
+}
+
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Parses the output of `python --version`, e.g. `Python 3.11.7`, or a bare version `3.11`.
+impl FromStr for PythonVersion {
+    type Err = Error;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let parsed: Option<_> = try {
+            let number = version.trim();
+            let number = number.strip_prefix("Python ").unwrap_or(number);
+            let mut parts = number.split('.');
+            let major = parts.next()?.parse().ok()?;
+            // the patch version is ignored, it may be a pre-release: `3.14.0rc1`
+            let minor = parts.next()?.parse().ok()?;
+            Self { major, minor }
+        };
+        parsed.ok_or(Error::InvalidOutput(format!(
+            "Unable to determine python version from {version:?}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version() {
+        assert_eq!(
+            PythonVersion::new(3, 14),
+            "Python 3.14.0rc1\n".parse().unwrap()
+        );
+        assert_eq!(PythonVersion::new(3, 8), "3.8".parse().unwrap());
+        assert!(!PythonVersion::new(3, 7).is_supported());
+        assert!(!PythonVersion::new(2, 7).is_supported());
+        assert!("Python".parse::<PythonVersion>().is_err());
+    }
+}
@@ -0,0 +1,516 @@
+//! pt's own `pytest` module, for running tests in environments where pytest is not installed
+
+use crate::{fixtures::push_definition, module::python_str};
+
+/// The python source of the `pytest` module: the parts of pytest's API used by test code.
+const PYTEST: &str = r#""""pt's stand-in for pytest, used when pytest is not installed"""
+
+import decimal
+import importlib
+import math
+import re
+import traceback
+import warnings
+from collections import namedtuple
+from collections.abc import Collection, Mapping, Sized
+
+__all__ = [
+    "ExceptionInfo",
+    "Mark",
+    "MarkDecorator",
+    "approx",
+    "deprecated_call",
+    "fail",
+    "fixture",
+    "importorskip",
+    "mark",
+    "param",
+    "raises",
+    "skip",
+    "warns",
+    "xfail",
+]
+
+
+class OutcomeException(BaseException):
+    # as pytest, shown as `Failed: ...` rather than `pytest.Failed: ...`
+    __module__ = "builtins"
+
+    def __init__(self, msg=None, pytrace=True):
+        super().__init__(msg)
+        self.msg = msg
+        self.pytrace = pytrace
+
+    def __repr__(self):
+        if self.msg is not None:
+            return self.msg
+        return f"<{self.__class__.__name__} instance>"
+
+    __str__ = __repr__
+
+
+class Skipped(OutcomeException):
+    __module__ = "builtins"
+
+    def __init__(self, msg=None, pytrace=True, allow_module_level=False):
+        super().__init__(msg, pytrace)
+        self.allow_module_level = allow_module_level
+
+
+class Failed(OutcomeException):
+    __module__ = "builtins"
+
+
+class XFailed(Failed):
+    __module__ = "builtins"
+
+
+def _with_exception(exception):
+    def decorate(function):
+        function.Exception = exception
+        return function
+
+    return decorate
+
+
+@_with_exception(Skipped)
+def skip(reason="", *, allow_module_level=False):
+    raise Skipped(reason, allow_module_level=allow_module_level)
+
+
+@_with_exception(Failed)
+def fail(reason="", pytrace=True):
+    raise Failed(reason, pytrace)
+
+
+@_with_exception(XFailed)
+def xfail(reason=""):
+    raise XFailed(reason)
+
+
+def _version(version):
+    return tuple(int(part) for part in re.findall(r"\d+", str(version)))
+
+
+def importorskip(modname, minversion=None, reason=None):
+    try:
+        module = importlib.import_module(modname)
+    except ImportError as exc:
+        if reason is None:
+            reason = f"could not import {modname!r}: {exc}"
+        raise Skipped(reason, allow_module_level=True) from None
+    if minversion is not None:
+        version = getattr(module, "__version__", None)
+        if version is None or _version(version) < _version(minversion):
+            raise Skipped(
+                f"module {modname!r} has __version__ {version!r}, required is: {minversion!r}",
+                allow_module_level=True,
+            )
+    return module
+
+
+class ExceptionInfo:
+    def __init__(self, excinfo=None):
+        self._excinfo = excinfo
+
+    @classmethod
+    def from_exception(cls, exception):
+        return cls((type(exception), exception, exception.__traceback__))
+
+    @classmethod
+    def for_later(cls):
+        return cls()
+
+    def fill_unfilled(self, excinfo):
+        assert self._excinfo is None, "ExceptionInfo was already filled"
+        self._excinfo = excinfo
+
+    @property
+    def type(self):
+        assert self._excinfo is not None, ".type can only be used after the context manager exits"
+        return self._excinfo[0]
+
+    @property
+    def value(self):
+        assert self._excinfo is not None, ".value can only be used after the context manager exits"
+        return self._excinfo[1]
+
+    @property
+    def tb(self):
+        assert self._excinfo is not None, ".tb can only be used after the context manager exits"
+        return self._excinfo[2]
+
+    @property
+    def typename(self):
+        return self.type.__name__
+
+    def exconly(self, tryshort=False):
+        return "".join(traceback.format_exception_only(self.type, self.value)).rstrip()
+
+    def errisinstance(self, exc):
+        return isinstance(self.value, exc)
+
+    def match(self, regexp):
+        value = str(self.value)
+        assert re.search(regexp, value), (
+            f"Regex pattern did not match.\n Regex: {regexp!r}\n Input: {value!r}"
+        )
+        return True
+
+    def __repr__(self):
+        if self._excinfo is None:
+            return "<ExceptionInfo for raises contextmanager>"
+        return f"<ExceptionInfo {self.exconly()!r} tblen={len(traceback.extract_tb(self.tb))}>"
+
+
+class RaisesContext:
+    def __init__(self, expected_exception, message, match_expr):
+        self.expected_exception = expected_exception
+        self.message = message
+        self.match_expr = match_expr
+        self.excinfo = None
+
+    def __enter__(self):
+        self.excinfo = ExceptionInfo.for_later()
+        return self.excinfo
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        if exc_type is None:
+            fail(self.message)
+        if not issubclass(exc_type, self.expected_exception):
+            return False
+        self.excinfo.fill_unfilled((exc_type, exc_val, exc_tb))
+        if self.match_expr is not None:
+            self.excinfo.match(self.match_expr)
+        return True
+
+
+def raises(expected_exception, *args, match=None, **kwargs):
+    message = f"DID NOT RAISE {expected_exception}"
+    if not args:
+        if kwargs:
+            names = ", ".join(sorted(kwargs))
+            raise TypeError(f"Unexpected keyword arguments passed to pytest.raises: {names}")
+        return RaisesContext(expected_exception, message, match)
+    func, *args = args
+    if not callable(func):
+        raise TypeError(f"{func!r} object (type: {type(func)}) must be callable")
+    try:
+        func(*args, **kwargs)
+    except expected_exception as exc:
+        excinfo = ExceptionInfo.from_exception(exc)
+        if match is not None:
+            excinfo.match(match)
+        return excinfo
+    fail(message)
+
+
+class WarningsRecorder(warnings.catch_warnings):
+    def __init__(self):
+        super().__init__(record=True)
+        self._list = []
+
+    @property
+    def list(self):
+        return self._list
+
+    def __getitem__(self, index):
+        return self._list[index]
+
+    def __iter__(self):
+        return iter(self._list)
+
+    def __len__(self):
+        return len(self._list)
+
+    def pop(self, cls=Warning):
+        for index, warning in enumerate(self._list):
+            if issubclass(warning.category, cls):
+                return self._list.pop(index)
+        raise AssertionError(f"{cls!r} not found in warning list")
+
+    def clear(self):
+        self._list[:] = []
+
+    def __enter__(self):
+        self._list = super().__enter__()
+        warnings.simplefilter("always")
+        return self
+
+
+class WarningsChecker(WarningsRecorder):
+    def __init__(self, expected_warning=Warning, match_expr=None):
+        super().__init__()
+        self.expected_warning = expected_warning
+        self.match_expr = match_expr
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        super().__exit__(exc_type, exc_val, exc_tb)
+        # an exception raised inside the block is reported instead
+        if exc_type is not None:
+            return
+        emitted = [warning.message for warning in self]
+        expected = [warning for warning in self if issubclass(warning.category, self.expected_warning)]
+        if not expected:
+            fail(
+                f"DID NOT WARN. No warnings of type {self.expected_warning} were emitted.\n"
+                f" Emitted warnings: {emitted}."
+            )
+        if self.match_expr is not None and not any(
+            re.search(self.match_expr, str(warning.message)) for warning in expected
+        ):
+            fail(
+                f"DID NOT WARN. No warnings of type {self.expected_warning} matching the regex"
+                f" were emitted.\n Regex: {self.match_expr}\n Emitted warnings: {emitted}."
+            )
+
+
+def warns(expected_warning=Warning, *args, match=None, **kwargs):
+    if not args:
+        if kwargs:
+            names = ", ".join(sorted(kwargs))
+            raise TypeError(f"Unexpected keyword arguments passed to pytest.warns: {names}")
+        return WarningsChecker(expected_warning, match)
+    func, *args = args
+    if not callable(func):
+        raise TypeError(f"{func!r} object (type: {type(func)}) must be callable")
+    with WarningsChecker(expected_warning, match):
+        return func(*args, **kwargs)
+
+
+def deprecated_call(func=None, *args, **kwargs):
+    if func is not None:
+        args = (func, *args)
+    return warns((DeprecationWarning, PendingDeprecationWarning, FutureWarning), *args, **kwargs)
+
+
+class ApproxBase:
+    __hash__ = None
+
+    def __init__(self, expected, rel=None, abs=None, nan_ok=False):
+        self.expected = expected
+        self.rel = rel
+        self.abs = abs
+        self.nan_ok = nan_ok
+
+    def __ne__(self, actual):
+        return not (actual == self)
+
+    def __bool__(self):
+        raise AssertionError(
+            "approx() is not supported in a boolean context.\n"
+            "Did you mean: `assert a == approx(b)`?"
+        )
+
+    def _approx_scalar(self, x):
+        cls = ApproxDecimal if isinstance(x, decimal.Decimal) else ApproxScalar
+        return cls(x, rel=self.rel, abs=self.abs, nan_ok=self.nan_ok)
+
+
+class ApproxScalar(ApproxBase):
+    DEFAULT_ABSOLUTE_TOLERANCE = 1e-12
+    DEFAULT_RELATIVE_TOLERANCE = 1e-6
+
+    def __repr__(self):
+        if isinstance(self.expected, bool) or not isinstance(
+            self.expected, (int, float, complex, decimal.Decimal)
+        ):
+            return str(self.expected)
+        try:
+            tolerance = f"{self.tolerance:.1e}"
+        except ValueError:
+            tolerance = "???"
+        return f"{self.expected} ± {tolerance}"
+
+    def __eq__(self, actual):
+        if actual == self.expected:
+            return True
+        numbers = (int, float, complex, decimal.Decimal)
+        if not (isinstance(self.expected, numbers) and isinstance(actual, numbers)):
+            return False
+        if isinstance(self.expected, bool) or isinstance(actual, bool):
+            return False
+        if math.isnan(abs(self.expected)):
+            return self.nan_ok and math.isnan(abs(actual))
+        if math.isinf(abs(self.expected)):
+            return False
+        return abs(self.expected - actual) <= self.tolerance
+
+    @property
+    def tolerance(self):
+        absolute = self.DEFAULT_ABSOLUTE_TOLERANCE if self.abs is None else self.abs
+        if absolute < 0:
+            raise ValueError(f"absolute tolerance can't be negative: {absolute}")
+        if math.isnan(absolute):
+            raise ValueError("absolute tolerance can't be NaN.")
+        if self.rel is None and self.abs is not None:
+            return absolute
+        rel = self.DEFAULT_RELATIVE_TOLERANCE if self.rel is None else self.rel
+        relative = rel * abs(self.expected)
+        if relative < 0:
+            raise ValueError(f"relative tolerance can't be negative: {relative}")
+        if math.isnan(relative):
+            raise ValueError("relative tolerance can't be NaN.")
+        return max(relative, absolute)
+
+
+class ApproxDecimal(ApproxScalar):
+    DEFAULT_ABSOLUTE_TOLERANCE = decimal.Decimal("1e-12")
+    DEFAULT_RELATIVE_TOLERANCE = decimal.Decimal("1e-6")
+
+
+class ApproxMapping(ApproxBase):
+    def __repr__(self):
+        return f"approx({ {key: self._approx_scalar(value) for key, value in self.expected.items()}!r})"
+
+    def __eq__(self, actual):
+        try:
+            if set(actual.keys()) != set(self.expected.keys()):
+                return False
+        except AttributeError:
+            return False
+        return all(actual[key] == self._approx_scalar(value) for key, value in self.expected.items())
+
+
+class ApproxSequenceLike(ApproxBase):
+    def __repr__(self):
+        seq_type = type(self.expected)
+        if seq_type not in (tuple, list):
+            seq_type = list
+        return f"approx({seq_type(self._approx_scalar(x) for x in self.expected)!r})"
+
+    def __eq__(self, actual):
+        try:
+            if len(actual) != len(self.expected):
+                return False
+        except TypeError:
+            return False
+        return all(a == self._approx_scalar(x) for a, x in zip(actual, self.expected))
+
+
+def approx(expected, rel=None, abs=None, nan_ok=False):
+    if isinstance(expected, decimal.Decimal):
+        cls = ApproxDecimal
+    elif isinstance(expected, Mapping):
+        cls = ApproxMapping
+    elif (
+        hasattr(expected, "__getitem__")
+        and isinstance(expected, Sized)
+        and not isinstance(expected, (str, bytes))
+    ):
+        cls = ApproxSequenceLike
+    elif isinstance(expected, Collection) and not isinstance(expected, (str, bytes)):
+        raise TypeError(f"pytest.approx() only supports ordered sequences, but got: {expected!r}")
+    else:
+        cls = ApproxScalar
+    return cls(expected, rel, abs, nan_ok)
+
+
+class Mark:
+    def __init__(self, name, args, kwargs):
+        self.name = name
+        self.args = args
+        self.kwargs = kwargs
+
+    def __repr__(self):
+        return f"Mark(name={self.name!r}, args={self.args!r}, kwargs={self.kwargs!r})"
+
+
+class MarkDecorator:
+    def __init__(self, mark):
+        self.mark = mark
+
+    @property
+    def name(self):
+        return self.mark.name
+
+    @property
+    def args(self):
+        return self.mark.args
+
+    @property
+    def kwargs(self):
+        return self.mark.kwargs
+
+    def with_args(self, *args, **kwargs):
+        return MarkDecorator(Mark(self.name, self.args + args, {**self.kwargs, **kwargs}))
+
+    def __call__(self, *args, **kwargs):
+        if len(args) == 1 and not kwargs and callable(args[0]):
+            func = args[0]
+            func.pytestmark = [*getattr(func, "pytestmark", []), self.mark]
+            return func
+        return self.with_args(*args, **kwargs)
+
+    def __repr__(self):
+        return f"<MarkDecorator {self.mark!r}>"
+
+
+class MarkGenerator:
+    def __getattr__(self, name):
+        if name.startswith("_"):
+            raise AttributeError("Marker name must NOT start with underscore")
+        return MarkDecorator(Mark(name, (), {}))
+
+
+mark = MarkGenerator()
+
+
+class ParameterSet(namedtuple("ParameterSet", ["values", "marks", "id"])):
+    pass
+
+
+def param(*values, marks=(), id=None):
+    marks = (marks,) if isinstance(marks, MarkDecorator) else tuple(marks)
+    if id is not None and not isinstance(id, str):
+        raise TypeError(f"Expected id to be a string, got {type(id)}: {id!r}")
+    return ParameterSet(values, marks, id)
+
+
+class FixtureFunctionMarker(namedtuple("FixtureFunctionMarker", ["scope", "params", "autouse", "ids", "name"])):
+    def __call__(self, function):
+        function._pytestfixturefunction = self
+        return function
+
+
+def fixture(fixture_function=None, *, scope="function", params=None, autouse=False, ids=None, name=None):
+    marker = FixtureFunctionMarker(scope, params, autouse, ids, name)
+    if fixture_function is not None:
+        return marker(fixture_function)
+    return marker"#;
+
+/// Push python to `launcher` which installs pt's `pytest` module into `sys.modules`, unless
+/// pytest is installed and `force` is false.
+pub(crate) fn push_install(launcher: &mut String, force: bool) {
+    let condition = match force {
+        true => "True",
+        false => "importlib.util.find_spec(\"pytest\") is None",
+    };
+    launcher.push_str(&format!(
+        "import importlib.util, types\n\
+         if {condition}:\n    \
+         _pt_pytest = types.ModuleType(\"pytest\")\n    \
+         exec(compile({}, \"<pt pytest>\", \"exec\"), _pt_pytest.__dict__)\n    \
+         sys.modules[\"pytest\"] = _pt_pytest\n",
+        python_str(PYTEST),
+    ));
+}
+
+/// `_pt_outcome(name)`, a tuple of the exceptions for a test's outcome: the one `pytest.<name>()`
+/// raises (`skip`, `xfail` or `fail`), if pytest is imported, and, as pytest, unittest's skips and
+/// expected failures.
+const OUTCOME: &str = r#"class _PtExpectedFailure(Exception):
+    pass
+
+
+def _pt_outcome(name):
+    # a test can only raise unittest's skip once unittest is imported
+    skip = getattr(sys.modules.get("unittest"), "SkipTest", None)
+    exceptions = {"skip": (skip,) if skip else (), "xfail": (_PtExpectedFailure,)}.get(name, ())
+    exception = getattr(getattr(sys.modules.get("pytest"), name, None), "Exception", None)
+    return exceptions if exception is None else (exception, *exceptions)"#;
+
+/// Push the definition of `_pt_outcome`, once before any tests.
+pub(crate) fn push_outcome_definition(runner: &mut String, indent: usize) {
+    push_definition(runner, indent, OUTCOME);
+}
@@ -0,0 +1,141 @@
+//! `skip`, `skipif` and `xfail` marks, evaluated as each test runs, as pytest's `skipping` plugin
+
+use ruff_python_ast::Expr;
+use ruff_text_size::Ranged;
+
+use crate::{fixtures::push_definition, pytests::mark};
+
+/// A `skip`, `skipif` or `xfail` mark on a test, its class or module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SkipMark {
+    pub(crate) xfail: bool,
+    /// python source of the mark, evaluated in the test module
+    pub(crate) source: String,
+}
+
+/// The `skip`, `skipif` and `xfail` marks in `marks`, in the order given.
+pub(crate) fn skip_marks<'a>(
+    src: &str,
+    marks: impl IntoIterator<Item = &'a Expr>,
+) -> Vec<SkipMark> {
+    marks
+        .into_iter()
+        .filter_map(|expr| match mark(expr)? {
+            ("skip" | "skipif", _) => Some((false, expr)),
+            ("xfail", _) => Some((true, expr)),
+            _ => None,
+        })
+        .map(|(xfail, expr)| SkipMark {
+            xfail,
+            source: src[expr.range()].to_string(),
+        })
+        .collect()
+}
+
+/// Evaluates a test's marks before its fixtures are set up, as pytest: raises the skip for the
+/// first `skipif` whose condition is true, or `skip`, and returns the `xfail` which applies as
+/// `(reason, strict, raises)`.
+const SKIPPING: &str = r#"class _PtXPassed(Exception):
+    pass
+
+
+def _pt_condition(mark, condition, namespace):
+    # a string is evaluated with the test module's globals, `os`, `sys` and `platform`
+    if isinstance(condition, str):
+        import os, platform
+
+        result = eval(condition, {"os": os, "sys": sys, "platform": platform, **namespace})
+    else:
+        result = bool(condition)
+    reason = mark.kwargs.get("reason")
+    if reason is None:
+        if not isinstance(condition, str):
+            raise ValueError(
+                f"Error evaluating {mark.name!r}: "
+                "you need to specify reason=STRING when using booleans as conditions."
+            )
+        reason = "condition: " + condition
+    return result, reason
+
+
+def _pt_applies(mark, namespace):
+    # the reason, if the mark has no conditions or one of them is true
+    conditions = (mark.kwargs["condition"],) if "condition" in mark.kwargs else mark.args
+    if not conditions:
+        return mark.kwargs.get("reason", "")
+    for condition in conditions:
+        result, reason = _pt_condition(mark, condition, namespace)
+        if result:
+            return reason
+    return None
+
+
+def _pt_skip_reason(reason="unconditional skip"):
+    return reason
+
+
+def _pt_skipping(marks, namespace, strict):
+    for mark in marks:
+        reason = _pt_applies(mark, namespace) if mark.name == "skipif" else None
+        if reason is not None:
+            raise _pt_outcome("skip")[0](reason)
+    for mark in marks:
+        if mark.name == "skip":
+            raise _pt_outcome("skip")[0](_pt_skip_reason(*mark.args, **mark.kwargs))
+    for mark in marks:
+        reason = _pt_applies(mark, namespace) if mark.name == "xfail" else None
+        if reason is not None:
+            if not mark.kwargs.get("run", True):
+                raise _pt_outcome("xfail")[0]("[NOTRUN] " + reason)
+            return reason, mark.kwargs.get("strict", strict), mark.kwargs.get("raises")
+    return None
+
+
+def _pt_xfailed(xfail, exc):
+    # an expected failure, unless the mark only expects other exceptions
+    if xfail is not None and not isinstance(exc, _pt_outcome("xfail")):
+        reason, _, raises = xfail
+        if raises is None or isinstance(exc, raises):
+            raise _pt_outcome("xfail")[0](reason) from exc
+
+
+def _pt_xpassed(xfail):
+    if xfail is not None:
+        reason, strict, _ = xfail
+        if strict:
+            raise (*_pt_outcome("fail"), AssertionError)[0]("[XPASS(strict)] " + reason)
+        raise _PtXPassed(reason)"#;
+
+/// Push the definitions needed to evaluate `skip`, `skipif` and `xfail` marks, once before any
+/// tests, after `push_outcome_definition()`.
+pub(crate) fn push_skipping_definitions(runner: &mut String, indent: usize) {
+    push_definition(runner, indent, SKIPPING);
+}
+
+#[cfg(test)]
+mod tests {
+    use ruff_python_parser::parse_module;
+
+    use super::*;
+    use crate::pytests::pytestmark;
+
+    #[test]
+    fn marks() {
+        let src =
+            "pytestmark = [pytest.mark.slow, pytest.mark.skipif(x, reason='no'), mark.xfail]\n";
+        let module = parse_module(src).unwrap().into_suite();
+        assert_eq!(
+            vec![
+                SkipMark {
+                    xfail: false,
+                    source: "pytest.mark.skipif(x, reason='no')".to_string(),
+                },
+                SkipMark {
+                    xfail: true,
+                    source: "mark.xfail".to_string(),
+                },
+            ],
+            skip_marks(src, pytestmark(&module))
+        );
+    }
+}
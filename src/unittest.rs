@@ -0,0 +1,108 @@
+//! `unittest.TestCase` classes, which pytest collects whatever their name and runs with unittest's
+//! own `setUp` / `tearDown`, `setUpClass` / `tearDownClass`, `subTest` and `skipTest`
+
+use ruff_python_ast::{Expr, StmtClassDef};
+
+use crate::{fixtures::push_definition, multiline::MultilineMut};
+
+/// Runs a test method as unittest would, the outcome is returned as an exception for the runner to
+/// raise: the first error or failure, `unittest.SkipTest` or `_PtExpectedFailure`.
+const UNITTEST: &str = r#"class _PtTestResult(_pt_unittest.TestResult):
+    outcome = None
+
+    def _set_outcome(self, exception):
+        if self.outcome is None:
+            self.outcome = exception
+
+    def _failed(self, err):
+        _, exception, tb = err
+        # as `unittest.TestResult`, unittest's own frames are removed, those calling the test and
+        # those of its assertions
+        frames = []
+        while tb is not None:
+            if "__unittest" not in tb.tb_frame.f_globals:
+                frames.append(tb)
+            tb = tb.tb_next
+        for frame in reversed(frames):
+            tb = _pt_types.TracebackType(tb, frame.tb_frame, frame.tb_lasti, frame.tb_lineno)
+        self._set_outcome(exception.with_traceback(tb))
+
+    def addError(self, test, err):
+        self._failed(err)
+
+    def addFailure(self, test, err):
+        self._failed(err)
+
+    def addSubTest(self, test, subtest, err):
+        if err is not None:
+            self._failed(err)
+
+    def addSkip(self, test, reason):
+        self._set_outcome(_pt_unittest.SkipTest(reason))
+
+    def addExpectedFailure(self, test, err):
+        self._set_outcome(_PtExpectedFailure())
+
+    def addUnexpectedSuccess(self, test):
+        self._set_outcome(AssertionError("Unexpected success"))
+
+
+def _pt_set_up_class(cls):
+    # `(exception, traceback)` if `setUpClass` fails, raised for each of the class's tests
+    if getattr(cls, "__unittest_skip__", False):
+        return None
+    try:
+        cls.setUpClass()
+    except Exception as exc:
+        cls.doClassCleanups()
+        return exc, exc.__traceback__.tb_next
+    return None
+
+
+def _pt_tear_down_class(cls, class_error):
+    if class_error is None and not getattr(cls, "__unittest_skip__", False):
+        try:
+            cls.tearDownClass()
+        finally:
+            cls.doClassCleanups()
+
+
+def _pt_run_unittest(test, class_error):
+    if class_error is not None:
+        exception, tb = class_error
+        return exception.with_traceback(tb)
+    result = _PtTestResult()
+    test.run(result)
+    return result.outcome"#;
+
+/// Whether `class` inherits `unittest.TestCase` (or `IsolatedAsyncioTestCase`), directly or
+/// through a class for which `test_case(name)` is true: one defined earlier in the module.
+pub(crate) fn is_test_case(class: &StmtClassDef, test_case: impl Fn(&str) -> bool) -> bool {
+    const BASES: [&str; 2] = ["TestCase", "IsolatedAsyncioTestCase"];
+    class.bases().iter().any(|base| match base {
+        Expr::Name(base) => {
+            BASES.contains(&base.id.as_str()) || test_case(base.id.as_str())
+        }
+        Expr::Attribute(base) => {
+            BASES.contains(&base.attr.as_str())
+                && matches!(&*base.value, Expr::Name(unittest) if unittest.id.as_str() == "unittest")
+        }
+        _ => false,
+    })
+}
+
+/// Whether unittest collects method `name`: its default `testMethodPrefix`, rather than
+/// pytest's `python_functions`.
+pub(crate) fn is_test_method(name: &str) -> bool {
+    name.starts_with("test")
+}
+
+/// Push the definitions needed to run unittest tests, once before any tests, after
+/// `push_outcome_definition()`.
+pub(crate) fn push_unittest_definitions(runner: &mut String, indent: usize) {
+    runner.push_python_line(
+        indent,
+        ["import types as _pt_types, unittest as _pt_unittest"],
+    );
+    push_definition(runner, indent, UNITTEST);
+}
@@ -0,0 +1,227 @@
+//! Warnings emitted while tests run, as pytest records them for its "warnings summary"
+
+use indexmap::IndexMap;
+
+use crate::{Error, fixtures::push_definition, module::python_str, multiline::MultilineMut};
+
+/// Records the warnings emitted by each test, with pytest's filters, and outputs them as a
+/// `<id> <testname> WARNING` section each.
+const WARNINGS: &str = r#"def _pt_warning_filter(arg, escape):
+    parts = [part.strip() for part in arg.split(":")]
+    if len(parts) > 5:
+        raise ValueError(f"too many fields (max 5) in warnings filter: {arg!r}")
+    action, message, category, module, lineno = parts + [""] * (5 - len(parts))
+    action = _pt_warnings_module._getaction(action)
+    if category:
+        module_name, _, name = category.rpartition(".")
+        category = getattr(_pt_importlib.import_module(module_name or "builtins"), name)
+        if not issubclass(category, Warning):
+            raise ValueError(f"{category!r} is not a Warning subclass")
+    else:
+        category = Warning
+    if escape:
+        message = _pt_re.escape(message)
+        module = _pt_re.escape(module) + r"\Z" if module else module
+    return action, message, category, module, int(lineno) if lineno else 0
+
+
+class _PtWarnings:
+    def __init__(self, filters):
+        # `(filter, escape)`: the `filterwarnings` ini option, then `-W`
+        self._filters = filters
+
+    def start(self, marks):
+        self._catcher = _pt_warnings_module.catch_warnings(record=True)
+        self._recorded = self._catcher.__enter__()
+        if not sys.warnoptions:
+            _pt_warnings_module.filterwarnings("always", category=DeprecationWarning)
+            _pt_warnings_module.filterwarnings("always", category=PendingDeprecationWarning)
+        for arg, escape in self._filters:
+            _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, escape))
+        for arg in marks:
+            _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, False))
+
+    def stop(self, prefix):
+        self._catcher.__exit__(None, None, None)
+        for warning in self._recorded:
+            print(prefix, "WARNING")
+            text = _pt_warnings_module.formatwarning(
+                str(warning.message),
+                warning.category,
+                warning.filename,
+                warning.lineno,
+                warning.line,
+            )
+            print(text, end="" if text.endswith("\n") else "\n")"#;
+
+/// Push the definitions needed to record warnings, once before any tests, with the filters from
+/// the `filterwarnings` ini option and `-W`, in `pythonwarnings`.
+pub(crate) fn push_warnings_definitions(
+    runner: &mut String,
+    indent: usize,
+    filterwarnings: &[String],
+    pythonwarnings: &[String],
+) {
+    runner.push_python_line(
+        indent,
+        ["import importlib as _pt_importlib, re as _pt_re, warnings as _pt_warnings_module"],
+    );
+    push_definition(runner, indent, WARNINGS);
+    // as pytest, `-W` filters are escaped, those in the ini file are regular expressions
+    let filters: Vec<String> = filterwarnings
+        .iter()
+        .map(|filter| format!("({}, False)", python_str(filter)))
+        .chain(
+            pythonwarnings
+                .iter()
+                .map(|filter| format!("({}, True)", python_str(filter))),
+        )
+        .collect();
+    runner.push_python_line(
+        indent,
+        ["_pt_warnings = _PtWarnings([", &filters.join(", "), "])"],
+    );
+}
+
+/// Check a warnings filter, `action:message:category:module:lineno`, from `option`: `-W` or the
+/// `filterwarnings` ini option. As python, the action may be abbreviated. Whether the category
+/// exists is only known once python imports it.
+pub fn check_filter(option: &str, filter: &str) -> Result<(), Error> {
+    const ACTIONS: [&str; 7] = [
+        "default", "always", "all", "ignore", "module", "once", "error",
+    ];
+    let invalid = |reason: String| {
+        Error::InvalidArgument(format!(
+            "{option}: invalid warnings filter '{filter}': {reason}"
+        ))
+    };
+    let parts: Vec<&str> = filter.split(':').map(str::trim).collect();
+    if parts.len() > 5 {
+        return Err(invalid("too many fields (max 5)".to_string()));
+    }
+    let field = |n: usize| parts.get(n).copied().unwrap_or_default();
+    let action = field(0);
+    if !ACTIONS.iter().any(|known| known.starts_with(action)) {
+        return Err(invalid(format!("invalid action: '{action}'")));
+    }
+    let category = field(2);
+    let identifier = |part: &str| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    if !category.is_empty() && !category.split('.').all(identifier) {
+        return Err(invalid(format!("invalid category: '{category}'")));
+    }
+    let lineno = field(4);
+    if !lineno.is_empty() && lineno.parse::<usize>().is_err() {
+        return Err(invalid(format!("invalid lineno: '{lineno}'")));
+    }
+    Ok(())
+}
+
+/// pytest's "warnings summary" for the warnings tests emitted, as `(nodeid, warning)`. Each
+/// distinct warning follows the tests which emitted it, or a count per file if 10 or more did.
+pub fn warnings_summary<'a>(recorded: impl IntoIterator<Item = (String, &'a str)>) -> String {
+    let mut grouped: IndexMap<&str, Vec<String>> = IndexMap::new();
+    for (nodeid, warning) in recorded {
+        grouped.entry(warning).or_default().push(nodeid);
+    }
+    let mut summary = String::new();
+    for (warning, nodeids) in grouped {
+        if nodeids.len() < 10 {
+            nodeids
+                .iter()
+                .for_each(|nodeid| summary.push_line(0, [nodeid.as_str()]));
+        } else {
+            let mut counts: IndexMap<&str, usize> = IndexMap::new();
+            for nodeid in &nodeids {
+                let file = nodeid
+                    .split_once("::")
+                    .map_or(nodeid.as_str(), |(file, _)| file);
+                *counts.entry(file).or_default() += 1;
+            }
+            for (file, count) in counts {
+                let plural = if count == 1 { "" } else { "s" };
+                summary.push_line(0, [file, ": ", &count.to_string(), " warning", plural]);
+            }
+        }
+        warning
+            .lines()
+            .for_each(|line| summary.push_line(2, [line]));
+        summary.push_newline();
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        for filter in [
+            "error",
+            "ign::DeprecationWarning",
+            "default:msg:Warning:mod:3",
+            "",
+        ] {
+            assert!(check_filter("-W", filter).is_ok(), "{filter}");
+        }
+        let reason = |filter| match check_filter("-W", filter) {
+            Err(Error::InvalidArgument(msg)) => msg,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            "-W: invalid warnings filter 'bogus': invalid action: 'bogus'",
+            reason("bogus")
+        );
+        assert_eq!(
+            "-W: invalid warnings filter 'error::no such': invalid category: 'no such'",
+            reason("error::no such")
+        );
+        assert_eq!(
+            "-W: invalid warnings filter 'error::::x': invalid lineno: 'x'",
+            reason("error::::x")
+        );
+        assert_eq!(
+            "-W: invalid warnings filter 'a:b:c:d:1:f': too many fields (max 5)",
+            reason("a:b:c:d:1:f")
+        );
+    }
+
+    #[test]
+    fn grouped_by_warning() {
+        let warning = "test_it.py:3: UserWarning: careful\n  warnings.warn(\"careful\")\n";
+        let other = "test_it.py:7: DeprecationWarning: old\n  old()\n";
+        let recorded = [
+            ("test_it.py::test_one".to_string(), warning),
+            ("test_it.py::test_two".to_string(), other),
+            ("test_it.py::test_three".to_string(), warning),
+        ];
+        let expected = "test_it.py::test_one
+test_it.py::test_three
+  test_it.py:3: UserWarning: careful
+    warnings.warn(\"careful\")
+
+test_it.py::test_two
+  test_it.py:7: DeprecationWarning: old
+    old()
+
+";
+        assert_eq!(expected, warnings_summary(recorded));
+
+        let recorded = (0..12).map(|n| {
+            let file = if n < 11 {
+                "test_it.py"
+            } else {
+                "test_other.py"
+            };
+            (format!("{file}::test_{n}"), other)
+        });
+        assert!(warnings_summary(recorded).starts_with(
+            "test_it.py: 11 warnings\ntest_other.py: 1 warning\n  test_it.py:7: Deprecation"
+        ));
+    }
+}
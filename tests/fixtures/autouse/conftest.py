@@ -0,0 +1,18 @@
+CALLS = []
+
+
+def fixture(function=None, **kwargs):
+    # stands in for `pytest.fixture`, so pytest need not be installed
+    return function if function else lambda function: function
+
+
+@fixture(autouse=True)
+def record_test(request):
+    CALLS.append(("conftest", request.node.name))
+    yield
+    CALLS.append(("teardown", request.node.name))
+
+
+@fixture
+def database():
+    return {"users": ["alice"]}
@@ -0,0 +1,44 @@
+import types
+
+from conftest import CALLS, fixture
+
+mark = types.SimpleNamespace(usefixtures=lambda *names: lambda function: function)
+
+pytestmark = mark.usefixtures("clean")
+
+
+@fixture
+def clean():
+    CALLS.append(("clean", None))
+
+
+@fixture(autouse=True)
+def module_autouse():
+    CALLS.append(("module", None))
+
+
+def test_first():
+    assert CALLS == [("conftest", "test_first"), ("module", None), ("clean", None)]
+
+
+def test_conftest_fixture(database):
+    assert database == {"users": ["alice"]}
+    assert CALLS[-3:] == [("conftest", "test_conftest_fixture"), ("module", None), ("clean", None)]
+
+
+@mark.usefixtures("marked")
+class TestClass:
+    @fixture(autouse=True)
+    def setup(self):
+        self.value = 42
+
+    @fixture
+    def marked(self):
+        CALLS.append(("marked", self.value))
+
+    def test_instance(self):
+        assert self.value == 42
+        assert CALLS[-2:] == [("marked", 42), ("clean", None)]
+
+    def test_teardown(self):
+        assert ("teardown", "test_instance") in CALLS
@@ -0,0 +1,497 @@
+import logging
+import os
+import sys
+
+
+def test_capsys(capsys):
+    print("hello")
+    print("oops", file=sys.stderr)
+    assert capsys.readouterr() == ("hello\n", "oops\n")
+    print("again")
+    assert capsys.readouterr().out == "again\n"
+
+
+def test_capfd(capfd):
+    os.write(1, b"from fd\n")
+    print("from print")
+    out, err = capfd.readouterr()
+    assert out == "from fd\nfrom print\n"
+    assert err == ""
+
+
+def test_caplog(caplog):
+    logger = logging.getLogger("pt.example")
+    logger.info("not recorded")
+    logger.warning("recorded %d", 1)
+    with caplog.at_level(logging.INFO):
+        logger.info("at info")
+    caplog.set_level(logging.DEBUG, logger="pt.example")
+    logger.debug("at debug")
+    assert caplog.messages == ["recorded 1", "at info", "at debug"]
+    assert caplog.record_tuples[0] == ("pt.example", logging.WARNING, "recorded 1")
+    assert "WARNING  pt.example:src.py:" in caplog.text
+
+
+def test_prints_then_fails():
+    print("printed before failing")
+    print("to stderr", file=sys.stderr)
+    assert False
+
+
+def test_level_restored(caplog):
+    assert logging.getLogger("pt.example").level == logging.NOTSET
+    assert not caplog.records
+
+
+if __name__ == "__main__":
+    from traceback import TracebackException
+    import sys
+    class _PtExpectedFailure(Exception):
+        pass
+
+
+    def _pt_outcome(name):
+        # a test can only raise unittest's skip once unittest is imported
+        skip = getattr(sys.modules.get("unittest"), "SkipTest", None)
+        exceptions = {"skip": (skip,) if skip else (), "xfail": (_PtExpectedFailure,)}.get(name, ())
+        exception = getattr(getattr(sys.modules.get("pytest"), name, None), "Exception", None)
+        return exceptions if exception is None else (exception, *exceptions)
+    def _pt_print_sources(output, exc, module_file):
+        import linecache
+
+        files, pending, seen = [], [exc], set()
+        while pending:
+            exc = pending.pop()
+            if exc is None or id(exc) in seen:
+                continue
+            seen.add(id(exc))
+            tb = exc.__traceback__
+            while tb is not None:
+                file = tb.tb_frame.f_code.co_filename
+                # `<string>` is the runner, when it imports the test module
+                if file not in (module_file, "<string>", "<pt pytest>", *files):
+                    files.append(file)
+                tb = tb.tb_next
+            pending.extend((exc.__cause__, exc.__context__, *getattr(exc, "exceptions", ())))
+        for file in files:
+            print(output + " SOURCE")
+            print(file)
+            for line in linecache.getlines(file):
+                print(line.rstrip("\n"))
+    import collections as _pt_collections, contextlib as _pt_contextlib, io as _pt_io, os as _pt_os, tempfile as _pt_tempfile
+    _PtCaptureResult = _pt_collections.namedtuple("CaptureResult", ["out", "err"])
+
+
+    class _PtTee(_pt_io.StringIO):
+        def __init__(self, stream):
+            super().__init__()
+            self._stream = stream
+
+        def write(self, text):
+            self._stream.write(text)
+            return super().write(text)
+
+
+    class _PtCapture:
+        def __init__(self, method):
+            self._method = method
+
+        def start(self):
+            if self._method == "fd":
+                self._files = [_pt_tempfile.TemporaryFile(buffering=0) for _ in range(2)]
+                self._streams = [
+                    _pt_io.TextIOWrapper(
+                        _pt_io.FileIO(fd, "w", closefd=False),
+                        encoding="utf-8",
+                        errors="replace",
+                        write_through=True,
+                    )
+                    for fd in (1, 2)
+                ]
+            elif self._method == "tee-sys":
+                self._streams = [_PtTee(sys.stdout), _PtTee(sys.stderr)]
+            else:
+                self._streams = [_pt_io.StringIO(), _pt_io.StringIO()]
+            self._resume()
+
+        def _resume(self):
+            sys.stdout.flush()
+            sys.stderr.flush()
+            self._saved = (sys.stdout, sys.stderr)
+            if self._method == "fd":
+                self._saved_fds = [_pt_os.dup(1), _pt_os.dup(2)]
+                for fd, file in zip((1, 2), self._files):
+                    _pt_os.dup2(file.fileno(), fd)
+            sys.stdout, sys.stderr = self._streams
+
+        def _suspend(self):
+            for stream in self._streams:
+                stream.flush()
+            sys.stdout, sys.stderr = self._saved
+            if self._method == "fd":
+                for fd, saved in zip((1, 2), self._saved_fds):
+                    _pt_os.dup2(saved, fd)
+                    _pt_os.close(saved)
+
+        def readouterr(self):
+            captured = []
+            if self._method == "fd":
+                for stream, file in zip(self._streams, self._files):
+                    stream.flush()
+                    file.seek(0)
+                    captured.append(file.read().decode("utf-8", "replace"))
+                    file.seek(0)
+                    file.truncate()
+            else:
+                for stream in self._streams:
+                    captured.append(stream.getvalue())
+                    stream.seek(0)
+                    stream.truncate()
+            return _PtCaptureResult(*captured)
+
+        @_pt_contextlib.contextmanager
+        def disabled(self):
+            self._suspend()
+            try:
+                yield
+            finally:
+                self._resume()
+
+        def stop(self):
+            captured = self.readouterr()
+            self._suspend()
+            if self._method == "fd":
+                for file in self._files:
+                    file.close()
+            return captured
+
+        def close(self):
+            # output the test did not read is passed on, to be reported if the test fails
+            out, err = self.stop()
+            sys.stdout.write(out)
+            sys.stderr.write(err)
+
+
+    def _pt_print_captured(prefix, captured):
+        for section, text in zip(("stdout", "stderr"), captured):
+            if text:
+                print(prefix, "CAPTURED", section)
+                print(text, end="" if text.endswith("\n") else "\n")
+    _pt_capture = _PtCapture("fd")
+    import importlib as _pt_importlib, re as _pt_re, warnings as _pt_warnings_module
+    def _pt_warning_filter(arg, escape):
+        parts = [part.strip() for part in arg.split(":")]
+        if len(parts) > 5:
+            raise ValueError(f"too many fields (max 5) in warnings filter: {arg!r}")
+        action, message, category, module, lineno = parts + [""] * (5 - len(parts))
+        action = _pt_warnings_module._getaction(action)
+        if category:
+            module_name, _, name = category.rpartition(".")
+            category = getattr(_pt_importlib.import_module(module_name or "builtins"), name)
+            if not issubclass(category, Warning):
+                raise ValueError(f"{category!r} is not a Warning subclass")
+        else:
+            category = Warning
+        if escape:
+            message = _pt_re.escape(message)
+            module = _pt_re.escape(module) + r"\Z" if module else module
+        return action, message, category, module, int(lineno) if lineno else 0
+
+
+    class _PtWarnings:
+        def __init__(self, filters):
+            # `(filter, escape)`: the `filterwarnings` ini option, then `-W`
+            self._filters = filters
+
+        def start(self, marks):
+            self._catcher = _pt_warnings_module.catch_warnings(record=True)
+            self._recorded = self._catcher.__enter__()
+            if not sys.warnoptions:
+                _pt_warnings_module.filterwarnings("always", category=DeprecationWarning)
+                _pt_warnings_module.filterwarnings("always", category=PendingDeprecationWarning)
+            for arg, escape in self._filters:
+                _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, escape))
+            for arg in marks:
+                _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, False))
+
+        def stop(self, prefix):
+            self._catcher.__exit__(None, None, None)
+            for warning in self._recorded:
+                print(prefix, "WARNING")
+                text = _pt_warnings_module.formatwarning(
+                    str(warning.message),
+                    warning.category,
+                    warning.filename,
+                    warning.lineno,
+                    warning.line,
+                )
+                print(text, end="" if text.endswith("\n") else "\n")
+    _pt_warnings = _PtWarnings([])
+    import contextlib as _pt_contextlib, io as _pt_io, logging as _pt_logging
+    class _PtLogCaptureHandler(_pt_logging.Handler):
+        def __init__(self):
+            super().__init__()
+            self.setFormatter(
+                _pt_logging.Formatter("%(levelname)-8s %(name)s:%(filename)s:%(lineno)d %(message)s")
+            )
+            self.reset()
+
+        def emit(self, record):
+            self.records.append(record)
+            self.stream.write(self.format(record) + "\n")
+
+        def reset(self):
+            self.records = []
+            self.stream = _pt_io.StringIO()
+
+
+    class _PtLogCaptureFixture:
+        def __init__(self):
+            self.handler = _PtLogCaptureHandler()
+            self._initial_levels = {}
+            _pt_logging.getLogger().addHandler(self.handler)
+
+        @property
+        def records(self):
+            return self.handler.records
+
+        @property
+        def text(self):
+            return self.handler.stream.getvalue()
+
+        @property
+        def record_tuples(self):
+            return [(record.name, record.levelno, record.getMessage()) for record in self.records]
+
+        @property
+        def messages(self):
+            return [record.getMessage() for record in self.records]
+
+        def clear(self):
+            self.handler.reset()
+
+        def set_level(self, level, logger=None):
+            logger_obj = _pt_logging.getLogger(logger)
+            self._initial_levels.setdefault(logger, logger_obj.level)
+            logger_obj.setLevel(level)
+            self.handler.setLevel(level)
+
+        @_pt_contextlib.contextmanager
+        def at_level(self, level, logger=None):
+            logger_obj = _pt_logging.getLogger(logger)
+            logger_level, handler_level = logger_obj.level, self.handler.level
+            logger_obj.setLevel(level)
+            self.handler.setLevel(level)
+            try:
+                yield
+            finally:
+                logger_obj.setLevel(logger_level)
+                self.handler.setLevel(handler_level)
+
+        def close(self):
+            _pt_logging.getLogger().removeHandler(self.handler)
+            for logger, level in self._initial_levels.items():
+                _pt_logging.getLogger(logger).setLevel(level)
+
+    print("UID test_capsys RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_capsys = _PtCapture("sys")
+        _pt_capsys.start()
+        try:
+            test_capsys(capsys=_pt_capsys)
+        finally:
+            _pt_capsys.close()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capsys REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_capsys")
+        print("UID test_capsys SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capsys REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_capsys")
+        print("UID test_capsys XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_capsys REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_capsys", sys.exception(), __file__)
+        _pt_print_captured("UID test_capsys", _pt_captured)
+        _pt_warnings.stop("UID test_capsys")
+        print("UID test_capsys FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capsys REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_capsys INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_capsys")
+        print("UID test_capsys PASS")
+
+    print("UID test_capfd RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_capfd = _PtCapture("fd")
+        _pt_capfd.start()
+        try:
+            test_capfd(capfd=_pt_capfd)
+        finally:
+            _pt_capfd.close()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capfd REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_capfd")
+        print("UID test_capfd SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capfd REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_capfd")
+        print("UID test_capfd XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_capfd REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_capfd", sys.exception(), __file__)
+        _pt_print_captured("UID test_capfd", _pt_captured)
+        _pt_warnings.stop("UID test_capfd")
+        print("UID test_capfd FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_capfd REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_capfd INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_capfd")
+        print("UID test_capfd PASS")
+
+    print("UID test_caplog RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_caplog = _PtLogCaptureFixture()
+        try:
+            test_caplog(caplog=_pt_caplog)
+        finally:
+            _pt_caplog.close()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_caplog REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_caplog")
+        print("UID test_caplog SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_caplog REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_caplog")
+        print("UID test_caplog XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_caplog REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_caplog", sys.exception(), __file__)
+        _pt_print_captured("UID test_caplog", _pt_captured)
+        _pt_warnings.stop("UID test_caplog")
+        print("UID test_caplog FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_caplog REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_caplog INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_caplog")
+        print("UID test_caplog PASS")
+
+    print("UID test_prints_then_fails RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        test_prints_then_fails()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_prints_then_fails REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_prints_then_fails")
+        print("UID test_prints_then_fails SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_prints_then_fails REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_prints_then_fails")
+        print("UID test_prints_then_fails XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_prints_then_fails REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_prints_then_fails", sys.exception(), __file__)
+        _pt_print_captured("UID test_prints_then_fails", _pt_captured)
+        _pt_warnings.stop("UID test_prints_then_fails")
+        print("UID test_prints_then_fails FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_prints_then_fails REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_prints_then_fails INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_prints_then_fails")
+        print("UID test_prints_then_fails PASS")
+
+    print("UID test_level_restored RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_caplog = _PtLogCaptureFixture()
+        try:
+            test_level_restored(caplog=_pt_caplog)
+        finally:
+            _pt_caplog.close()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_level_restored REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_level_restored")
+        print("UID test_level_restored SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_level_restored REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_level_restored")
+        print("UID test_level_restored XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_level_restored REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_level_restored", sys.exception(), __file__)
+        _pt_print_captured("UID test_level_restored", _pt_captured)
+        _pt_warnings.stop("UID test_level_restored")
+        print("UID test_level_restored FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_level_restored REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_level_restored INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_level_restored")
+        print("UID test_level_restored PASS")
@@ -0,0 +1,43 @@
+import logging
+import os
+import sys
+
+
+def test_capsys(capsys):
+    print("hello")
+    print("oops", file=sys.stderr)
+    assert capsys.readouterr() == ("hello\n", "oops\n")
+    print("again")
+    assert capsys.readouterr().out == "again\n"
+
+
+def test_capfd(capfd):
+    os.write(1, b"from fd\n")
+    print("from print")
+    out, err = capfd.readouterr()
+    assert out == "from fd\nfrom print\n"
+    assert err == ""
+
+
+def test_caplog(caplog):
+    logger = logging.getLogger("pt.example")
+    logger.info("not recorded")
+    logger.warning("recorded %d", 1)
+    with caplog.at_level(logging.INFO):
+        logger.info("at info")
+    caplog.set_level(logging.DEBUG, logger="pt.example")
+    logger.debug("at debug")
+    assert caplog.messages == ["recorded 1", "at info", "at debug"]
+    assert caplog.record_tuples[0] == ("pt.example", logging.WARNING, "recorded 1")
+    assert "WARNING  pt.example:src.py:" in caplog.text
+
+
+def test_prints_then_fails():
+    print("printed before failing")
+    print("to stderr", file=sys.stderr)
+    assert False
+
+
+def test_level_restored(caplog):
+    assert logging.getLogger("pt.example").level == logging.NOTSET
+    assert not caplog.records
@@ -0,0 +1,9 @@
+def parse(text):
+    return int(text)
+
+
+def test_chained():
+    try:
+        parse("seven")
+    except ValueError:
+        assert False
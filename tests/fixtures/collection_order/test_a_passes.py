@@ -0,0 +1,2 @@
+def test_runs_first():
+    assert True
@@ -0,0 +1,9 @@
+"""Imports a missing module, after a module whose tests would run first"""
+
+import os
+
+from no_such_package import helper
+
+
+def test_never_runs():
+    assert helper(os.sep)
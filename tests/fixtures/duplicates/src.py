@@ -0,0 +1,13 @@
+def test_one():
+    assert False
+
+
+def test_one():
+    assert True
+
+
+def test_two():
+    def test_inner():
+        assert False
+
+    assert test_inner
@@ -0,0 +1,9 @@
+def test_local_exception():
+    class LocalError(Exception):
+        pass
+
+    raise LocalError("defined inside the test")
+
+
+def test_passes():
+    assert True
@@ -0,0 +1,6 @@
+def check_seven(value):
+    assert value == 7
+
+
+def test_helper_fails():
+    check_seven(6)
@@ -0,0 +1,3 @@
+def check_seven(value):
+    doubled = value * 2
+    assert doubled == 14
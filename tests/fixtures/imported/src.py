@@ -0,0 +1,5 @@
+from checks import check_seven
+
+
+def test_imported_helper_fails():
+    check_seven(6)
@@ -0,0 +1,18 @@
+import sys
+
+import pytest
+
+pytestmark = pytest.mark.network
+
+SETTING = "original"
+
+
+def test_request_module(request):
+    assert request.module is sys.modules[__name__]
+    assert request.module.__file__ == __file__
+    assert request.module.SETTING == "original"
+
+
+def test_module_markers(request):
+    assert [mark.name for mark in request.node.iter_markers()] == ["network"]
+    assert request.node.get_closest_marker("network") is not None
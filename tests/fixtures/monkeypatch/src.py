@@ -0,0 +1,22 @@
+import os
+import sys
+
+SETTING = "original"
+
+
+def test_patched_then_fails(monkeypatch, tmp_path):
+    monkeypatch.setattr(sys.modules[__name__], "SETTING", "patched")
+    monkeypatch.setattr("os.getcwd", lambda: "/patched")
+    monkeypatch.setenv("PT_MONKEYPATCH", "1")
+    monkeypatch.delenv("PT_NO_SUCH_VARIABLE", raising=False)
+    monkeypatch.syspath_prepend(tmp_path)
+    assert SETTING == "patched"
+    assert os.getcwd() == "/patched"
+    assert False
+
+
+def test_undone():
+    assert SETTING == "original"
+    assert os.getcwd() != "/patched"
+    assert "PT_MONKEYPATCH" not in os.environ
+    assert not sys.path[0].endswith("test_patched_then_fails0")
@@ -0,0 +1,10 @@
+from mypkg import helpers
+from .helpers import seven
+
+
+def test_relative_import():
+    assert seven() == 7
+
+
+def test_absolute_import():
+    assert helpers.seven() == 7
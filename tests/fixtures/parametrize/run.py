@@ -0,0 +1,1030 @@
+import types
+
+
+# stand in for `pytest.fixture`, `pytest.mark` & `pytest.param`, so pytest need not be installed
+def fixture(function=None, **kwargs):
+    return function if function else lambda function: function
+
+
+mark = types.SimpleNamespace(
+    parametrize=lambda *args, **kwargs: lambda function: function, slow=None
+)
+
+
+def param(*values, **kwargs):
+    return values
+
+
+@fixture(params=["sqlite", "postgres"])
+def backend(request):
+    return request.param
+
+
+@fixture(params=[1, 2], ids=["one", "two"])
+def size(request):
+    yield request.param * 10
+
+
+@fixture
+def doubled(request):
+    return request.param * 2
+
+
+def test_backend(backend):
+    assert backend in ("sqlite", "postgres")
+
+
+@mark.parametrize("a, b", [(1, 2), param(2, 3, id="larger"), (3, 3)])
+def test_less(a, b):
+    assert a < b
+
+
+@mark.parametrize("doubled", [1, 5], indirect=True)
+def test_indirect(doubled, size):
+    assert doubled in (2, 10)
+
+
+@mark.parametrize("size", [7])
+def test_overridden(size):
+    assert size == 7
+
+
+@mark.parametrize("words", [["a", "b"], ()])
+class TestWords:
+    def test_words(self, words):
+        assert len(words) == 2
+
+
+CASES = [1, 2]
+
+
+@mark.parametrize("n", CASES)
+def test_from_variable(n):
+    assert n
+
+
+@mark.parametrize("n", [1, param(2, marks=mark.slow)])
+def test_marked(n):
+    assert n
+
+
+if __name__ == "__main__":
+    from traceback import TracebackException
+    import sys
+    class _PtExpectedFailure(Exception):
+        pass
+
+
+    def _pt_outcome(name):
+        # a test can only raise unittest's skip once unittest is imported
+        skip = getattr(sys.modules.get("unittest"), "SkipTest", None)
+        exceptions = {"skip": (skip,) if skip else (), "xfail": (_PtExpectedFailure,)}.get(name, ())
+        exception = getattr(getattr(sys.modules.get("pytest"), name, None), "Exception", None)
+        return exceptions if exception is None else (exception, *exceptions)
+    def _pt_print_sources(output, exc, module_file):
+        import linecache
+
+        files, pending, seen = [], [exc], set()
+        while pending:
+            exc = pending.pop()
+            if exc is None or id(exc) in seen:
+                continue
+            seen.add(id(exc))
+            tb = exc.__traceback__
+            while tb is not None:
+                file = tb.tb_frame.f_code.co_filename
+                # `<string>` is the runner, when it imports the test module
+                if file not in (module_file, "<string>", "<pt pytest>", *files):
+                    files.append(file)
+                tb = tb.tb_next
+            pending.extend((exc.__cause__, exc.__context__, *getattr(exc, "exceptions", ())))
+        for file in files:
+            print(output + " SOURCE")
+            print(file)
+            for line in linecache.getlines(file):
+                print(line.rstrip("\n"))
+    import collections as _pt_collections, contextlib as _pt_contextlib, io as _pt_io, os as _pt_os, tempfile as _pt_tempfile
+    _PtCaptureResult = _pt_collections.namedtuple("CaptureResult", ["out", "err"])
+
+
+    class _PtTee(_pt_io.StringIO):
+        def __init__(self, stream):
+            super().__init__()
+            self._stream = stream
+
+        def write(self, text):
+            self._stream.write(text)
+            return super().write(text)
+
+
+    class _PtCapture:
+        def __init__(self, method):
+            self._method = method
+
+        def start(self):
+            if self._method == "fd":
+                self._files = [_pt_tempfile.TemporaryFile(buffering=0) for _ in range(2)]
+                self._streams = [
+                    _pt_io.TextIOWrapper(
+                        _pt_io.FileIO(fd, "w", closefd=False),
+                        encoding="utf-8",
+                        errors="replace",
+                        write_through=True,
+                    )
+                    for fd in (1, 2)
+                ]
+            elif self._method == "tee-sys":
+                self._streams = [_PtTee(sys.stdout), _PtTee(sys.stderr)]
+            else:
+                self._streams = [_pt_io.StringIO(), _pt_io.StringIO()]
+            self._resume()
+
+        def _resume(self):
+            sys.stdout.flush()
+            sys.stderr.flush()
+            self._saved = (sys.stdout, sys.stderr)
+            if self._method == "fd":
+                self._saved_fds = [_pt_os.dup(1), _pt_os.dup(2)]
+                for fd, file in zip((1, 2), self._files):
+                    _pt_os.dup2(file.fileno(), fd)
+            sys.stdout, sys.stderr = self._streams
+
+        def _suspend(self):
+            for stream in self._streams:
+                stream.flush()
+            sys.stdout, sys.stderr = self._saved
+            if self._method == "fd":
+                for fd, saved in zip((1, 2), self._saved_fds):
+                    _pt_os.dup2(saved, fd)
+                    _pt_os.close(saved)
+
+        def readouterr(self):
+            captured = []
+            if self._method == "fd":
+                for stream, file in zip(self._streams, self._files):
+                    stream.flush()
+                    file.seek(0)
+                    captured.append(file.read().decode("utf-8", "replace"))
+                    file.seek(0)
+                    file.truncate()
+            else:
+                for stream in self._streams:
+                    captured.append(stream.getvalue())
+                    stream.seek(0)
+                    stream.truncate()
+            return _PtCaptureResult(*captured)
+
+        @_pt_contextlib.contextmanager
+        def disabled(self):
+            self._suspend()
+            try:
+                yield
+            finally:
+                self._resume()
+
+        def stop(self):
+            captured = self.readouterr()
+            self._suspend()
+            if self._method == "fd":
+                for file in self._files:
+                    file.close()
+            return captured
+
+        def close(self):
+            # output the test did not read is passed on, to be reported if the test fails
+            out, err = self.stop()
+            sys.stdout.write(out)
+            sys.stderr.write(err)
+
+
+    def _pt_print_captured(prefix, captured):
+        for section, text in zip(("stdout", "stderr"), captured):
+            if text:
+                print(prefix, "CAPTURED", section)
+                print(text, end="" if text.endswith("\n") else "\n")
+    import atexit as _pt_atexit, inspect as _pt_inspect, types as _pt_types
+    class _PtConfig:
+        _NOTSET = object()
+
+        def __init__(self, options):
+            self.option = _pt_types.SimpleNamespace(**options)
+
+        def getoption(self, name, default=_NOTSET, skip=False):
+            dest = name.lstrip("-").replace("-", "_")
+            if hasattr(self.option, dest):
+                return getattr(self.option, dest)
+            if default is not self._NOTSET:
+                return default
+            raise ValueError(f"no option named {name!r}")
+
+
+    class _PtNode:
+        def __init__(self, name, nodeid, function, cls, markers):
+            self.name = name
+            self.originalname = name.partition("[")[0]
+            self.nodeid = nodeid
+            self.function = function
+            self.cls = cls
+            self.module = sys.modules.get(function.__module__)
+            self.config = _pt_config
+            self.keywords = dict.fromkeys([name, *markers], True)
+            # fixture functions by name, for fixtures pt could not find before the test ran
+            self.fixtures = {}
+            self._values = {}
+
+        def iter_markers(self, name=None):
+            for owner in (self.function, self.cls, self.module):
+                marks = getattr(owner, "pytestmark", [])
+                for mark in marks if isinstance(marks, list) else [marks]:
+                    if name is None or mark.name == name:
+                        yield mark
+
+        def get_closest_marker(self, name, default=None):
+            return next(self.iter_markers(name), default)
+
+
+    class _PtFixtureRequest:
+        _NOTSET = object()
+
+        def __init__(self, node, fixturename, fixturenames, param=_NOTSET, scope="function"):
+            if param is not self._NOTSET:
+                self.param = param
+            self.node = node
+            self.fixturename = fixturename
+            self.fixturenames = fixturenames
+            self.config = node.config
+            self.function = node.function
+            self.cls = node.cls
+            self.module = node.module
+            self.scope = scope
+            self._finalizers = []
+
+        def addfinalizer(self, finalizer):
+            self._finalizers.append(finalizer)
+
+        def getfixturevalue(self, argname):
+            if argname == "request":
+                return self
+            if argname in self.fixturenames:
+                return globals()["_pt_" + argname]
+            if argname in self.node._values:
+                return self.node._values[argname]
+            function = self.node.fixtures.get(argname)
+            if function is None:
+                raise LookupError(f"fixture {argname!r} not found")
+            # set up now, with the fixtures it requests, and torn down with this request
+            request = _PtFixtureRequest(self.node, argname, self.fixturenames)
+            self.addfinalizer(request._finish)
+            parameters = _pt_inspect.signature(self._unwrap(function)).parameters.values()
+            kwargs = {
+                parameter.name: request.getfixturevalue(parameter.name)
+                for parameter in parameters
+                if parameter.default is parameter.empty
+                and parameter.kind in (parameter.POSITIONAL_OR_KEYWORD, parameter.KEYWORD_ONLY)
+            }
+            value = self.node._values[argname] = request._execute(function, **kwargs)
+            return value
+
+        @staticmethod
+        def _unwrap(function):
+            # pytest wraps fixture functions so they cannot be called directly
+            if hasattr(function, "_get_wrapped_function"):
+                function = function._get_wrapped_function()
+            wrapped = getattr(function, "__pytest_wrapped__", None)
+            if wrapped is not None:
+                function = wrapped.obj
+            return function
+
+        def _execute(self, function, **kwargs):
+            function = self._unwrap(function)
+            if not _pt_inspect.isgeneratorfunction(function):
+                return function(**kwargs)
+            generator = function(**kwargs)
+            value = next(generator)
+            self.addfinalizer(lambda: self._teardown(generator))
+            return value
+
+        @staticmethod
+        def _teardown(generator):
+            try:
+                next(generator)
+            except StopIteration:
+                return
+            raise ValueError("fixture function has more than one 'yield'")
+
+        def _finish(self):
+            # every finalizer runs, the first exception is raised afterwards
+            exceptions = []
+            while self._finalizers:
+                try:
+                    self._finalizers.pop()()
+                except BaseException as exc:
+                    exceptions.append(exc)
+            if exceptions:
+                raise exceptions[0]
+
+
+    class _PtFixtureCache:
+        # values of fixtures with a wider scope than "function", kept until the last test using them
+        def __init__(self):
+            self._values = {}
+            # those whose last test did not run, e.g. as it was skipped
+            _pt_atexit.register(self._finish_all)
+
+        def get(self, key, request, execute):
+            if key not in self._values:
+                try:
+                    self._values[key] = (request, execute(), None)
+                except Exception as exc:
+                    # as pytest, each test using the value fails with the same error
+                    self._values[key] = (request, None, (exc, exc.__traceback__))
+            request, value, error = self._values[key]
+            if error is not None:
+                exc, traceback = error
+                raise exc.with_traceback(traceback)
+            return value
+
+        def finish(self, key):
+            entry = self._values.pop(key, None)
+            if entry is not None:
+                entry[0]._finish()
+
+        def _finish_all(self):
+            while self._values:
+                self.finish(next(reversed(self._values)))
+    _pt_fixture_cache = _PtFixtureCache()
+    _pt_config = _PtConfig({})
+    _pt_capture = _PtCapture("fd")
+    import importlib as _pt_importlib, re as _pt_re, warnings as _pt_warnings_module
+    def _pt_warning_filter(arg, escape):
+        parts = [part.strip() for part in arg.split(":")]
+        if len(parts) > 5:
+            raise ValueError(f"too many fields (max 5) in warnings filter: {arg!r}")
+        action, message, category, module, lineno = parts + [""] * (5 - len(parts))
+        action = _pt_warnings_module._getaction(action)
+        if category:
+            module_name, _, name = category.rpartition(".")
+            category = getattr(_pt_importlib.import_module(module_name or "builtins"), name)
+            if not issubclass(category, Warning):
+                raise ValueError(f"{category!r} is not a Warning subclass")
+        else:
+            category = Warning
+        if escape:
+            message = _pt_re.escape(message)
+            module = _pt_re.escape(module) + r"\Z" if module else module
+        return action, message, category, module, int(lineno) if lineno else 0
+
+
+    class _PtWarnings:
+        def __init__(self, filters):
+            # `(filter, escape)`: the `filterwarnings` ini option, then `-W`
+            self._filters = filters
+
+        def start(self, marks):
+            self._catcher = _pt_warnings_module.catch_warnings(record=True)
+            self._recorded = self._catcher.__enter__()
+            if not sys.warnoptions:
+                _pt_warnings_module.filterwarnings("always", category=DeprecationWarning)
+                _pt_warnings_module.filterwarnings("always", category=PendingDeprecationWarning)
+            for arg, escape in self._filters:
+                _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, escape))
+            for arg in marks:
+                _pt_warnings_module.filterwarnings(*_pt_warning_filter(arg, False))
+
+        def stop(self, prefix):
+            self._catcher.__exit__(None, None, None)
+            for warning in self._recorded:
+                print(prefix, "WARNING")
+                text = _pt_warnings_module.formatwarning(
+                    str(warning.message),
+                    warning.category,
+                    warning.filename,
+                    warning.lineno,
+                    warning.line,
+                )
+                print(text, end="" if text.endswith("\n") else "\n")
+    _pt_warnings = _PtWarnings([])
+
+    print("UID test_backend[sqlite] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_backend[sqlite]", "test_backend[sqlite]", test_backend, None, [])
+        _ptrequest_backend = _PtFixtureRequest(_pt_node, "backend", [], param="sqlite")
+        try:
+            _pt_backend = _ptrequest_backend._execute(backend, request=_ptrequest_backend)
+            test_backend(backend=_pt_backend)
+        finally:
+            _ptrequest_backend._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[sqlite] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_backend[sqlite]")
+        print("UID test_backend[sqlite] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[sqlite] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_backend[sqlite]")
+        print("UID test_backend[sqlite] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_backend[sqlite] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_backend[sqlite]", sys.exception(), __file__)
+        _pt_print_captured("UID test_backend[sqlite]", _pt_captured)
+        _pt_warnings.stop("UID test_backend[sqlite]")
+        print("UID test_backend[sqlite] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[sqlite] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_backend[sqlite] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_backend[sqlite]")
+        print("UID test_backend[sqlite] PASS")
+
+    print("UID test_backend[postgres] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_backend[postgres]", "test_backend[postgres]", test_backend, None, [])
+        _ptrequest_backend = _PtFixtureRequest(_pt_node, "backend", [], param="postgres")
+        try:
+            _pt_backend = _ptrequest_backend._execute(backend, request=_ptrequest_backend)
+            test_backend(backend=_pt_backend)
+        finally:
+            _ptrequest_backend._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[postgres] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_backend[postgres]")
+        print("UID test_backend[postgres] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[postgres] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_backend[postgres]")
+        print("UID test_backend[postgres] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_backend[postgres] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_backend[postgres]", sys.exception(), __file__)
+        _pt_print_captured("UID test_backend[postgres]", _pt_captured)
+        _pt_warnings.stop("UID test_backend[postgres]")
+        print("UID test_backend[postgres] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_backend[postgres] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_backend[postgres] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_backend[postgres]")
+        print("UID test_backend[postgres] PASS")
+
+    print("UID test_less[1-2] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_a = 1
+        _pt_b = 2
+        test_less(a=_pt_a, b=_pt_b)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[1-2] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[1-2]")
+        print("UID test_less[1-2] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[1-2] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[1-2]")
+        print("UID test_less[1-2] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_less[1-2] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_less[1-2]", sys.exception(), __file__)
+        _pt_print_captured("UID test_less[1-2]", _pt_captured)
+        _pt_warnings.stop("UID test_less[1-2]")
+        print("UID test_less[1-2] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[1-2] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_less[1-2] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_less[1-2]")
+        print("UID test_less[1-2] PASS")
+
+    print("UID test_less[larger] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_a = 2
+        _pt_b = 3
+        test_less(a=_pt_a, b=_pt_b)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[larger] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[larger]")
+        print("UID test_less[larger] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[larger] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[larger]")
+        print("UID test_less[larger] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_less[larger] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_less[larger]", sys.exception(), __file__)
+        _pt_print_captured("UID test_less[larger]", _pt_captured)
+        _pt_warnings.stop("UID test_less[larger]")
+        print("UID test_less[larger] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[larger] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_less[larger] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_less[larger]")
+        print("UID test_less[larger] PASS")
+
+    print("UID test_less[3-3] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_a = 3
+        _pt_b = 3
+        test_less(a=_pt_a, b=_pt_b)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[3-3] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[3-3]")
+        print("UID test_less[3-3] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[3-3] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_less[3-3]")
+        print("UID test_less[3-3] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_less[3-3] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_less[3-3]", sys.exception(), __file__)
+        _pt_print_captured("UID test_less[3-3]", _pt_captured)
+        _pt_warnings.stop("UID test_less[3-3]")
+        print("UID test_less[3-3] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_less[3-3] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_less[3-3] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_less[3-3]")
+        print("UID test_less[3-3] PASS")
+
+    print("UID test_indirect[one-1] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_indirect[one-1]", "test_indirect[one-1]", test_indirect, None, ["parametrize"])
+        _ptrequest_doubled = _PtFixtureRequest(_pt_node, "doubled", [], param=1)
+        try:
+            _pt_doubled = _ptrequest_doubled._execute(doubled, request=_ptrequest_doubled)
+            _ptrequest_size = _PtFixtureRequest(_pt_node, "size", ["doubled"], param=1)
+            try:
+                _pt_size = _ptrequest_size._execute(size, request=_ptrequest_size)
+                test_indirect(doubled=_pt_doubled, size=_pt_size)
+            finally:
+                _ptrequest_size._finish()
+        finally:
+            _ptrequest_doubled._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[one-1]")
+        print("UID test_indirect[one-1] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[one-1]")
+        print("UID test_indirect[one-1] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_indirect[one-1] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_indirect[one-1]", sys.exception(), __file__)
+        _pt_print_captured("UID test_indirect[one-1]", _pt_captured)
+        _pt_warnings.stop("UID test_indirect[one-1]")
+        print("UID test_indirect[one-1] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-1] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_indirect[one-1] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_indirect[one-1]")
+        print("UID test_indirect[one-1] PASS")
+
+    print("UID test_indirect[one-5] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_indirect[one-5]", "test_indirect[one-5]", test_indirect, None, ["parametrize"])
+        _ptrequest_doubled = _PtFixtureRequest(_pt_node, "doubled", [], param=5)
+        try:
+            _pt_doubled = _ptrequest_doubled._execute(doubled, request=_ptrequest_doubled)
+            _ptrequest_size = _PtFixtureRequest(_pt_node, "size", ["doubled"], param=1)
+            try:
+                _pt_size = _ptrequest_size._execute(size, request=_ptrequest_size)
+                test_indirect(doubled=_pt_doubled, size=_pt_size)
+            finally:
+                _ptrequest_size._finish()
+        finally:
+            _ptrequest_doubled._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-5] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[one-5]")
+        print("UID test_indirect[one-5] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-5] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[one-5]")
+        print("UID test_indirect[one-5] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_indirect[one-5] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_indirect[one-5]", sys.exception(), __file__)
+        _pt_print_captured("UID test_indirect[one-5]", _pt_captured)
+        _pt_warnings.stop("UID test_indirect[one-5]")
+        print("UID test_indirect[one-5] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[one-5] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_indirect[one-5] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_indirect[one-5]")
+        print("UID test_indirect[one-5] PASS")
+
+    print("UID test_indirect[two-1] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_indirect[two-1]", "test_indirect[two-1]", test_indirect, None, ["parametrize"])
+        _ptrequest_doubled = _PtFixtureRequest(_pt_node, "doubled", [], param=1)
+        try:
+            _pt_doubled = _ptrequest_doubled._execute(doubled, request=_ptrequest_doubled)
+            _ptrequest_size = _PtFixtureRequest(_pt_node, "size", ["doubled"], param=2)
+            try:
+                _pt_size = _ptrequest_size._execute(size, request=_ptrequest_size)
+                test_indirect(doubled=_pt_doubled, size=_pt_size)
+            finally:
+                _ptrequest_size._finish()
+        finally:
+            _ptrequest_doubled._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[two-1]")
+        print("UID test_indirect[two-1] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[two-1]")
+        print("UID test_indirect[two-1] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_indirect[two-1] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_indirect[two-1]", sys.exception(), __file__)
+        _pt_print_captured("UID test_indirect[two-1]", _pt_captured)
+        _pt_warnings.stop("UID test_indirect[two-1]")
+        print("UID test_indirect[two-1] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-1] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_indirect[two-1] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_indirect[two-1]")
+        print("UID test_indirect[two-1] PASS")
+
+    print("UID test_indirect[two-5] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_node = _PtNode("test_indirect[two-5]", "test_indirect[two-5]", test_indirect, None, ["parametrize"])
+        _ptrequest_doubled = _PtFixtureRequest(_pt_node, "doubled", [], param=5)
+        try:
+            _pt_doubled = _ptrequest_doubled._execute(doubled, request=_ptrequest_doubled)
+            _ptrequest_size = _PtFixtureRequest(_pt_node, "size", ["doubled"], param=2)
+            try:
+                _pt_size = _ptrequest_size._execute(size, request=_ptrequest_size)
+                test_indirect(doubled=_pt_doubled, size=_pt_size)
+            finally:
+                _ptrequest_size._finish()
+        finally:
+            _ptrequest_doubled._finish()
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-5] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[two-5]")
+        print("UID test_indirect[two-5] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-5] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_indirect[two-5]")
+        print("UID test_indirect[two-5] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_indirect[two-5] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_indirect[two-5]", sys.exception(), __file__)
+        _pt_print_captured("UID test_indirect[two-5]", _pt_captured)
+        _pt_warnings.stop("UID test_indirect[two-5]")
+        print("UID test_indirect[two-5] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_indirect[two-5] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_indirect[two-5] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_indirect[two-5]")
+        print("UID test_indirect[two-5] PASS")
+
+    print("UID test_overridden[7] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_size = 7
+        test_overridden(size=_pt_size)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_overridden[7] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_overridden[7]")
+        print("UID test_overridden[7] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_overridden[7] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_overridden[7]")
+        print("UID test_overridden[7] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_overridden[7] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_overridden[7]", sys.exception(), __file__)
+        _pt_print_captured("UID test_overridden[7]", _pt_captured)
+        _pt_warnings.stop("UID test_overridden[7]")
+        print("UID test_overridden[7] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_overridden[7] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_overridden[7] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_overridden[7]")
+        print("UID test_overridden[7] PASS")
+
+    print("UID TestWords::test_words[words0] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_self = TestWords()
+        _pt_words = ["a", "b"]
+        _pt_self.test_words(words=_pt_words)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words0] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID TestWords::test_words[words0]")
+        print("UID TestWords::test_words[words0] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words0] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID TestWords::test_words[words0]")
+        print("UID TestWords::test_words[words0] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID TestWords::test_words[words0] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID TestWords::test_words[words0]", sys.exception(), __file__)
+        _pt_print_captured("UID TestWords::test_words[words0]", _pt_captured)
+        _pt_warnings.stop("UID TestWords::test_words[words0]")
+        print("UID TestWords::test_words[words0] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words0] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID TestWords::test_words[words0] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID TestWords::test_words[words0]")
+        print("UID TestWords::test_words[words0] PASS")
+
+    print("UID TestWords::test_words[words1] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_self = TestWords()
+        _pt_words = ()
+        _pt_self.test_words(words=_pt_words)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID TestWords::test_words[words1]")
+        print("UID TestWords::test_words[words1] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID TestWords::test_words[words1]")
+        print("UID TestWords::test_words[words1] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID TestWords::test_words[words1] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID TestWords::test_words[words1]", sys.exception(), __file__)
+        _pt_print_captured("UID TestWords::test_words[words1]", _pt_captured)
+        _pt_warnings.stop("UID TestWords::test_words[words1]")
+        print("UID TestWords::test_words[words1] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID TestWords::test_words[words1] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID TestWords::test_words[words1] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID TestWords::test_words[words1]")
+        print("UID TestWords::test_words[words1] PASS")
+
+    print("UID test_from_variable RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        raise NotImplementedError("In test_from_variable: pt cannot expand the values CASES, as it requires a list or tuple")
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_from_variable REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_from_variable")
+        print("UID test_from_variable SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_from_variable REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_from_variable")
+        print("UID test_from_variable XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_from_variable REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_from_variable", sys.exception(), __file__)
+        _pt_print_captured("UID test_from_variable", _pt_captured)
+        _pt_warnings.stop("UID test_from_variable")
+        print("UID test_from_variable FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_from_variable REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_from_variable INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_from_variable")
+        print("UID test_from_variable PASS")
+
+    print("UID test_marked[1] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_n = 1
+        test_marked(n=_pt_n)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_marked[1]")
+        print("UID test_marked[1] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[1] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_marked[1]")
+        print("UID test_marked[1] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_marked[1] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_marked[1]", sys.exception(), __file__)
+        _pt_print_captured("UID test_marked[1]", _pt_captured)
+        _pt_warnings.stop("UID test_marked[1]")
+        print("UID test_marked[1] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[1] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_marked[1] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_marked[1]")
+        print("UID test_marked[1] PASS")
+
+    print("UID test_marked[2] RUNNING")
+    _pt_capture.start()
+    try:
+        _pt_warnings.start([])
+        _pt_n = 2
+        test_marked(n=_pt_n)
+    except _pt_outcome("skip") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[2] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_marked[2]")
+        print("UID test_marked[2] SKIP")
+    except _pt_outcome("xfail") as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[2] REPORT")
+        print(_pt_exc)
+        _pt_warnings.stop("UID test_marked[2]")
+        print("UID test_marked[2] XFAIL")
+    except (Exception, *_pt_outcome("fail")):
+        _pt_captured = _pt_capture.stop()
+        print("UID test_marked[2] REPORT")
+        TracebackException.from_exception(sys.exception(), capture_locals=True).print(file=sys.stdout)
+        _pt_print_sources("UID test_marked[2]", sys.exception(), __file__)
+        _pt_print_captured("UID test_marked[2]", _pt_captured)
+        _pt_warnings.stop("UID test_marked[2]")
+        print("UID test_marked[2] FAIL")
+    except KeyboardInterrupt as _pt_exc:
+        _pt_capture.stop()
+        print("UID test_marked[2] REPORT")
+        _pt_frame = TracebackException.from_exception(_pt_exc).stack[-1]
+        print(f"{_pt_frame.filename}:{_pt_frame.lineno}")
+        print("UID test_marked[2] INTERRUPTED")
+        sys.exit()
+    else:
+        _pt_capture.stop()
+        _pt_warnings.stop("UID test_marked[2]")
+        print("UID test_marked[2] PASS")
@@ -0,0 +1,68 @@
+import types
+
+
+# stand in for `pytest.fixture`, `pytest.mark` & `pytest.param`, so pytest need not be installed
+def fixture(function=None, **kwargs):
+    return function if function else lambda function: function
+
+
+mark = types.SimpleNamespace(
+    parametrize=lambda *args, **kwargs: lambda function: function, slow=None
+)
+
+
+def param(*values, **kwargs):
+    return values
+
+
+@fixture(params=["sqlite", "postgres"])
+def backend(request):
+    return request.param
+
+
+@fixture(params=[1, 2], ids=["one", "two"])
+def size(request):
+    yield request.param * 10
+
+
+@fixture
+def doubled(request):
+    return request.param * 2
+
+
+def test_backend(backend):
+    assert backend in ("sqlite", "postgres")
+
+
+@mark.parametrize("a, b", [(1, 2), param(2, 3, id="larger"), (3, 3)])
+def test_less(a, b):
+    assert a < b
+
+
+@mark.parametrize("doubled", [1, 5], indirect=True)
+def test_indirect(doubled, size):
+    assert doubled in (2, 10)
+
+
+@mark.parametrize("size", [7])
+def test_overridden(size):
+    assert size == 7
+
+
+@mark.parametrize("words", [["a", "b"], ()])
+class TestWords:
+    def test_words(self, words):
+        assert len(words) == 2
+
+
+CASES = [1, 2]
+
+
+@mark.parametrize("n", CASES)
+def test_from_variable(n):
+    assert n
+
+
+@mark.parametrize("n", [1, param(2, marks=mark.slow)])
+def test_marked(n):
+    assert n
@@ -0,0 +1,8 @@
+import types
+
+mark = types.SimpleNamespace(parametrize=lambda *args, **kwargs: lambda function: function)
+
+
+@mark.parametrize("x", [1, 2])
+def test_no_argument():
+    pass
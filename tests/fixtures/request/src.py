@@ -0,0 +1,80 @@
+import pytest
+
+
+class TornDown(list):
+    # names in the order they were torn down, checked against `expected` if the test sets it
+    expected = None
+
+
+@pytest.fixture
+def torn_down():
+    # set up before the fixtures which record their teardown in it, so torn down after them
+    names = TornDown()
+    yield names
+    if names.expected is not None:
+        assert names == names.expected
+
+
+@pytest.fixture
+def resource(request, torn_down):
+    request.addfinalizer(lambda: torn_down.append(request.node.name))
+    return f"resource for {request.node.name}"
+
+
+@pytest.fixture(name="database")
+def database_fixture(resource, torn_down):
+    yield {"resource": resource}
+    torn_down.append("database")
+
+
+@pytest.fixture
+def broken():
+    raise RuntimeError("fixture failed")
+
+
+@pytest.fixture
+def settings(torn_down):
+    yield {"debug": True}
+    torn_down.append("settings")
+
+
+def test_node_name(resource):
+    assert resource == "resource for test_node_name"
+
+
+def test_finalized(torn_down, resource):
+    torn_down.expected = ["test_finalized"]
+
+
+def test_yield_fixture(torn_down, database):
+    assert database == {"resource": "resource for test_yield_fixture"}
+    torn_down.expected = ["database", "test_yield_fixture"]
+
+
+def test_getfixturevalue(request):
+    assert request.fixturename is None
+    assert request.getfixturevalue("resource") == "resource for test_getfixturevalue"
+
+
+def test_getfixturevalue_by_variable(request, torn_down):
+    values = [request.getfixturevalue(name) for name in ["database", "settings", "database"]]
+    assert values[0] == {"resource": "resource for test_getfixturevalue_by_variable"}
+    assert values[1:] == [{"debug": True}, values[0]]
+    torn_down.expected = ["settings", "database", "test_getfixturevalue_by_variable"]
+
+
+def test_getoption(request):
+    assert request.config.getoption("verbose") == -1  # run with `-q`
+    assert request.config.getoption("--maxfail") == 0
+    assert request.config.getoption("no_such_option", default="default") == "default"
+    assert request.node.nodeid.endswith("src.py::test_getoption")
+
+
+def test_broken_fixture(broken):
+    pass
+
+
+class TestClass:
+    def test_method(self, request):
+        assert request.node.name == "test_method"
+        assert request.cls is TestClass
@@ -0,0 +1,75 @@
+import pytest
+
+SET_UP = []
+TORN_DOWN = []
+CONNECTIONS = []
+
+
+@pytest.fixture(scope="module")
+def connection():
+    SET_UP.append("connection")
+    yield object()
+    TORN_DOWN.append("connection")
+
+
+@pytest.fixture(scope="class")
+def per_class(request):
+    SET_UP.append(request.cls.__name__)
+    yield request.cls.__name__
+    TORN_DOWN.append(request.cls.__name__)
+
+
+@pytest.fixture(scope="module")
+def broken():
+    SET_UP.append("broken")
+    raise RuntimeError("module fixture failed")
+
+
+@pytest.fixture(scope="module")
+def mismatched(monkeypatch):
+    return monkeypatch
+
+
+def test_first(connection):
+    CONNECTIONS.append(connection)
+    assert SET_UP == ["connection"]
+
+
+class TestOne:
+    def test_a(self, connection, per_class):
+        CONNECTIONS.append(connection)
+        assert per_class == "TestOne"
+
+    def test_b(self, per_class):
+        assert SET_UP == ["connection", "TestOne"]
+        assert TORN_DOWN == []
+
+
+class TestTwo:
+    def test_a(self, per_class):
+        assert per_class == "TestTwo"
+        assert TORN_DOWN == ["TestOne"]
+
+
+def test_last(connection):
+    assert connection is CONNECTIONS[0] is CONNECTIONS[1]
+
+
+def test_torn_down():
+    assert TORN_DOWN == ["TestOne", "TestTwo", "connection"]
+
+
+def test_broken(broken):
+    pass
+
+
+def test_broken_again(broken):
+    pass
+
+
+def test_set_up_once():
+    assert SET_UP.count("broken") == 1
+
+
+def test_mismatched(mismatched):
+    pass
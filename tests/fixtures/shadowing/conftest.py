@@ -0,0 +1 @@
+"""Imported before the test module, as pytest."""
@@ -0,0 +1,23 @@
+from unittest import TestCase
+
+import pytest
+
+# names the runner imports or defines for itself, bound to something else
+CaptureResult = collections = contextlib = importlib = inspect = io = logging = "not the module"
+os = pathlib = re = tempfile = types = unittest = warnings = "not the module"
+NAMES = ["CaptureResult", "collections", "contextlib", "importlib", "inspect", "io", "logging"]
+NAMES += ["os", "pathlib", "re", "tempfile", "types", "unittest", "warnings"]
+
+
+@pytest.fixture
+def value(request):
+    return request.node.name
+
+
+def test_globals(capsys, caplog, tmp_path_factory, monkeypatch, value):
+    assert [name for name in NAMES if globals()[name] != "not the module"] == []
+
+
+class TestUnittest(TestCase):
+    def test_globals(self):
+        self.assertEqual([name for name in NAMES if globals()[name] != "not the module"], [])
@@ -0,0 +1,3 @@
+import pytest
+
+pytest.skip("helper is not available", allow_module_level=True)
@@ -0,0 +1,7 @@
+import pytest
+
+pytest.skip("skip outside of a test")
+
+
+def test_never_collected():
+    pass
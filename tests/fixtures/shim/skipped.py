@@ -0,0 +1,11 @@
+import pytest
+
+nosuch = pytest.importorskip("nosuch_module")
+
+
+def test_uses_module():
+    assert nosuch
+
+
+def test_also_skipped():
+    pass
@@ -0,0 +1,5 @@
+from helper import VALUE
+
+
+def test_uses_helper():
+    assert VALUE
@@ -0,0 +1,92 @@
+import warnings
+
+import pytest
+
+
+@pytest.fixture
+def numbers():
+    return [0.1 + 0.2, 2.0]
+
+
+def test_raises():
+    with pytest.raises(ValueError, match="invalid") as excinfo:
+        int("invalid")
+    assert excinfo.type is ValueError
+    assert excinfo.typename == "ValueError"
+    pytest.raises(ZeroDivisionError, lambda: 1 / 0)
+
+
+def test_warns():
+    with pytest.warns(UserWarning, match="careful") as record:
+        warnings.warn("be careful")
+    assert len(record) == 1
+    with pytest.deprecated_call():
+        warnings.warn("old", DeprecationWarning)
+    pytest.warns(UserWarning, warnings.warn, "be careful", match="careful")
+
+
+def test_warns_no_match():
+    pytest.warns(UserWarning, warnings.warn, "be careful", match="reckless")
+
+
+def test_approx(numbers):
+    assert numbers == pytest.approx([0.3, 2])
+    assert {"a": 0.1 + 0.2} == pytest.approx({"a": 0.3})
+    assert 1.1 != pytest.approx(1)
+
+
+@pytest.mark.slow
+@pytest.mark.parametrize("value", [1, pytest.param(2, id="two")])
+def test_marked(value):
+    assert value in (1, 2)
+
+
+def test_skip():
+    pytest.skip("not today")
+
+
+def test_importorskip():
+    pytest.importorskip("no_such_module")
+
+
+def test_xfail():
+    pytest.xfail("known bug")
+
+
+@pytest.mark.skip(reason="not today")
+def test_skip_mark():
+    pytest.fail("skipped tests are not run")
+
+
+@pytest.mark.skipif("sys.platform != 'nowhere'", reason="not here")
+def test_skipif_mark():
+    pytest.fail("skipped tests are not run")
+
+
+@pytest.mark.skipif(False, reason="never")
+def test_skipif_false():
+    pass
+
+
+@pytest.mark.xfail(reason="known bug")
+def test_xfail_mark():
+    assert False
+
+
+@pytest.mark.xfail(raises=KeyError)
+def test_xfail_raises():
+    raise ValueError("not the expected failure")
+
+
+@pytest.mark.xfail(reason="fixed", strict=True)
+def test_xfail_strict():
+    pass
+
+
+def test_did_not_raise():
+    with pytest.raises(KeyError):
+        pass
+
+
+def test_fail():
+    pytest.fail("explicitly")
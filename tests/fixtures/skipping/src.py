@@ -0,0 +1,71 @@
+import sys
+
+import pytest
+
+pytestmark = pytest.mark.filterwarnings("ignore")
+
+
+@pytest.mark.skip
+def test_skip():
+    assert False
+
+
+@pytest.mark.skip(reason="not today")
+def test_skip_reason():
+    assert False
+
+
+@pytest.mark.skipif(sys.version_info > (3,), reason="python 3")
+def test_skipif():
+    assert False
+
+
+@pytest.mark.skipif("sys.platform == 'never'")
+def test_skipif_false():
+    pass
+
+
+@pytest.mark.skipif("len(LIMITS) > 1")
+def test_skipif_string():
+    assert False
+
+
+@pytest.mark.xfail(reason="known bug")
+def test_xfail():
+    assert 1 == 2
+
+
+@pytest.mark.xfail(raises=KeyError)
+def test_xfail_other_error():
+    raise ValueError("not a KeyError")
+
+
+@pytest.mark.xfail
+def test_xpass():
+    pass
+
+
+@pytest.mark.xfail(strict=True, reason="should fail")
+def test_xpass_strict():
+    pass
+
+
+@pytest.mark.xfail(run=False, reason="crashes")
+def test_not_run():
+    assert False
+
+
+@pytest.mark.parametrize(
+    "value", [1, pytest.param(2, marks=pytest.mark.xfail(reason="two"))]
+)
+def test_param(value):
+    assert value == 1
+
+
+@pytest.mark.skip(reason="whole class")
+class TestSkipped:
+    def test_method(self):
+        assert False
+
+
+LIMITS = [1, 2]
@@ -0,0 +1,11 @@
+import pytest
+
+
+@pytest.mark.xfail(reason="fixed since")
+def test_xpass_strict():
+    pass
+
+
+@pytest.mark.xfail(strict=False)
+def test_xpass_not_strict():
+    pass
@@ -0,0 +1,17 @@
+import os
+
+
+def test_tmp_path(tmp_path):
+    (tmp_path / "hello.txt").write_text("hello")
+    assert (tmp_path / "hello.txt").read_text() == "hello"
+
+
+def test_factory(tmp_path_factory):
+    path = tmp_path_factory.mktemp("data")
+    assert path.parent == tmp_path_factory.getbasetemp()
+
+
+class TestClass:
+    def test_tmpdir(self, tmpdir, tmp_path):
+        assert os.path.isdir(tmpdir)
+        assert str(tmpdir) == str(tmp_path)
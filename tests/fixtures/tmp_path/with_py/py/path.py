@@ -0,0 +1,2 @@
+# stands in for `py.path.local`, so py need not be installed
+from pathlib import Path as local
@@ -0,0 +1,2 @@
+# hides any installed pytest
+raise ImportError("No module named '_pytest'")
@@ -0,0 +1,2 @@
+# hides any installed py
+raise ImportError("No module named 'py'")
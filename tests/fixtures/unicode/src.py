@@ -0,0 +1,11 @@
+def check(value):
+    assert value == "brûlée"
+
+
+def test_accents():
+    café = "crème"
+    assert café == "brûlée"
+
+
+def test_helper():
+    résultat = check("crème")
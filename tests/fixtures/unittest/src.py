@@ -0,0 +1,79 @@
+import unittest
+
+EVENTS = []
+
+
+class Lifecycle(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        EVENTS.append("setUpClass")
+
+    @classmethod
+    def tearDownClass(cls):
+        EVENTS.append("tearDownClass")
+
+    def setUp(self):
+        self.value = 1
+        EVENTS.append("setUp")
+
+    def tearDown(self):
+        EVENTS.append("tearDown")
+
+    def helper(self):
+        return self.value
+
+    def test_value(self):
+        self.assertEqual(1, self.helper())
+
+    def test_events(self):
+        self.assertEqual(["setUpClass", "setUp", "tearDown", "setUp"], EVENTS)
+
+    def test_fails(self):
+        self.assertEqual(2, self.value)
+
+    def test_subtests(self):
+        for n in range(3):
+            with self.subTest(n=n):
+                self.assertLess(n, 2)
+
+    def test_skips(self):
+        self.skipTest("not now")
+
+    @unittest.skip("skipped by decorator")
+    def test_decorated_skip(self):
+        pass
+
+    @unittest.expectedFailure
+    def test_expected_failure(self):
+        self.assertTrue(False)
+
+
+class BrokenSetup(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        raise RuntimeError("no database")
+
+    def test_needs_database(self):
+        pass
+
+
+class Finished(unittest.TestCase):
+    def test_torn_down(self):
+        self.assertEqual("tearDownClass", EVENTS[-1])
+
+
+class Shared(unittest.TestCase):
+    kind = "shared"
+
+    def test_kind(self):
+        self.assertIn(self.kind, ["shared", "specific"])
+
+    def test_overridden(self):
+        self.assertEqual("shared", self.kind)
+
+
+class Specific(Shared):
+    kind = "specific"
+
+    def test_overridden(self):
+        self.assertEqual("specific", self.kind)
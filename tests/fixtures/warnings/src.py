@@ -0,0 +1,20 @@
+import warnings
+
+import pytest
+
+
+def test_warns():
+    warnings.warn("be careful")
+
+
+def test_deprecated():
+    warnings.warn("old api", DeprecationWarning)
+
+
+@pytest.mark.filterwarnings("ignore:be careful")
+def test_ignored():
+    warnings.warn("be careful")
+
+
+def test_quiet():
+    pass
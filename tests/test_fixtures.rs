@@ -8,7 +8,14 @@ use std::{
 use assert_cmd::cargo::*;
 use predicates::ord::eq;
 
-use pt::{Exception, TestStatus, TestSuite, Traceback};
+use pt::{PythonVersion, ReportOptions, TestStatus, TestSuite, Traceback, TracebackStyle};
+
+/// `pt`, with the cache disabled so that runs do not write a `.pytest_cache` into the fixtures
+fn pt() -> assert_cmd::Command {
+    let mut pt_cmd = cargo_bin_cmd!("pt");
+    pt_cmd.args(["-p", "no:cacheprovider"]);
+    pt_cmd
+}
 
 fn load_src(directory: &Path) -> TestSuite {
     let src = fs::read_to_string(directory.join("src.py")).unwrap();
@@ -50,7 +57,7 @@ mod basic {
         let tf_status = &suite.test("test_fails").unwrap().status;
         assert!(
             matches!(tf_status, TestStatus::Fail(err, tb)
-                if matches!(err, Exception::AssertionError)
+                if err.name == "AssertionError"
                 && tb == &expect_tb
             ),
             "{tf_status:?}"
@@ -79,12 +86,27 @@ mod basic {
 
     #[test]
     fn cli() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
-        pt_cmd.arg(FIXTURES.join("src.py").as_os_str());
+        let mut pt_cmd = pt();
+        // `-rN`, the short test summary is not part of the suite's summary report
+        pt_cmd
+            .args(["-q", "-rN"])
+            .arg(FIXTURES.join("src.py").as_os_str());
         let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
         pt_cmd.assert().stdout(eq(expected_stdout));
         pt_cmd.assert().code(1);
     }
+
+    #[test]
+    fn cli_short_summary() {
+        // as pytest, failures are summarised by default (`-rfE`)
+        let mut pt_cmd = pt();
+        pt_cmd.arg("./tests/fixtures/basic/src.py");
+        pt_cmd.assert().code(1);
+        pt_cmd.assert().stdout(predicates::str::contains(
+            "short test summary info ============================\n\
+             FAILED ./tests/fixtures/basic/src.py::test_fails - AssertionError\n",
+        ));
+    }
 }
 
 mod complex {
@@ -125,7 +147,7 @@ mod complex {
             let tf_status = &suite.test(failed_test).unwrap().status;
             assert!(
                 matches!(tf_status, TestStatus::Fail(err, tb)
-                    if matches!(err, Exception::AssertionError)
+                    if err.name == "AssertionError"
                     && tb == &expect_tb
                 ),
                 "{failed_test}: {tf_status:?}"
@@ -143,6 +165,53 @@ mod complex {
         assert_eq!(expect_rpt, report);
     }
 
+    #[test]
+    fn traceback_styles() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        let test = suite.test("test_seven_is_six").unwrap();
+        let report = |tb| {
+            test.report_with(&ReportOptions {
+                tb,
+                showlocals: false,
+                python: None,
+                rootdir: None,
+            })
+        };
+        assert_eq!(
+            Some(
+                "/workspaces/pt/tests/fixtures/complex/run.py:18: in test_seven_is_six\n    assert s == 6\n           ^^^^^^\nAssertionError\n"
+                    .to_string()
+            ),
+            report(TracebackStyle::Short)
+        );
+        assert_eq!(
+            Some("/workspaces/pt/tests/fixtures/complex/run.py:18: AssertionError\n".to_string()),
+            report(TracebackStyle::Line)
+        );
+        assert_eq!(None, report(TracebackStyle::No));
+    }
+
+    #[test]
+    fn showlocals() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        let test = suite.test("test_seven_is_six").unwrap();
+        let report = |showlocals| {
+            test.report_with(&ReportOptions {
+                tb: TracebackStyle::Auto,
+                showlocals,
+                python: None,
+                rootdir: None,
+            })
+            .unwrap()
+        };
+        assert!(report(true).contains("\n        s = 7\n"));
+        assert!(!report(false).contains("s = 7"));
+    }
+
     #[test]
     fn summary_report() {
         let mut suite = load_src(&FIXTURES);
@@ -155,12 +224,26 @@ mod complex {
 
     #[test]
     fn cli() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
-        pt_cmd.arg(FIXTURES.join("src.py").as_os_str());
+        let mut pt_cmd = pt();
+        // `-rN`, the short test summary is not part of the suite's summary report
+        pt_cmd
+            .args(["-q", "-rN"])
+            .arg(FIXTURES.join("src.py").as_os_str());
         let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
         pt_cmd.assert().stdout(eq(expected_stdout));
         pt_cmd.assert().code(1);
     }
+
+    #[test]
+    fn cli_exitfirst() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "-x"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("exitfirst.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
 }
 
 mod pass {
@@ -215,32 +298,786 @@ mod pass {
 
     #[test]
     fn cli() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
-        pt_cmd.arg(FIXTURES.join("src.py").as_os_str());
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+}
+
+mod helpers {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static ID: &str = "UID";
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/helpers"));
+
+    #[test]
+    fn summary_report() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        let report = suite.summary_report();
+        let expect_rpt = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        assert_eq!(expect_rpt, report);
+    }
+
+    #[test]
+    fn cli() {
+        let mut pt_cmd = pt();
+        // `-rN`, the short test summary is not part of the suite's summary report
+        pt_cmd
+            .args(["-q", "-rN"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod exceptions {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/exceptions"));
+
+    #[test]
+    fn cli() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn cli_short_summary() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-rf", "./tests/fixtures/exceptions/src.py"]);
+        pt_cmd.assert().code(1);
+        pt_cmd.assert().stdout(predicates::str::contains(
+            "FAILED ./tests/fixtures/exceptions/src.py::test_local_exception - \
+             test_local_exception.<locals>.LocalError: defined inside the test\n",
+        ));
+    }
+}
+
+mod imported {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/imported"));
+
+    #[test]
+    fn cli() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod package {
+    use std::{env, sync::LazyLock};
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/package"));
+
+    #[test]
+    fn cli() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .arg("-q")
+            .arg(FIXTURES.join("mypkg/test_imports.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+
+    #[test]
+    fn cli_import_runner() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .arg("-q")
+            .arg("--runner=import")
+            .arg(FIXTURES.join("mypkg/test_imports.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+
+    #[test]
+    fn large_module() {
+        // larger than a single command line argument may be, 128KiB on linux
+        let dir = env::temp_dir().join(format!("pt-fixtures-large-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test_large.py");
+        let padding = format!("# {}\n", "x".repeat(77)).repeat(2000);
+        fs::write(&path, format!("{padding}\ndef test_large():\n    pass\n")).unwrap();
+        for runner in ["--runner=inline", "--runner=import"] {
+            let mut pt_cmd = pt();
+            pt_cmd.args(["-q", runner]).arg(&path);
+            pt_cmd.assert().stdout(eq("test_large PASS\n"));
+            pt_cmd.assert().code(0);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+mod chained {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static ID: &str = "UID";
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/chained"));
+
+    #[test]
+    fn summary_report() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        let report = suite.summary_report();
+        let expect_rpt = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        assert_eq!(expect_rpt, report);
+    }
+}
+
+mod unicode {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static ID: &str = "UID";
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/unicode"));
+
+    #[test]
+    fn legacy_python_carets() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        let report = suite.summary_report_with(&ReportOptions {
+            python: Some(PythonVersion::new(3, 10)),
+            ..ReportOptions::default()
+        });
+        let expect_rpt = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        assert_eq!(expect_rpt, report);
+    }
+}
+
+mod duplicates {
+    use std::sync::LazyLock;
+
+    use predicates::str::contains;
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/duplicates"));
+
+    #[test]
+    fn cli() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+
+    #[test]
+    fn strict() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .arg("--strict")
+            .arg(FIXTURES.join("src.py").as_os_str());
+        pt_cmd.assert().code(2);
+        pt_cmd.assert().stdout(contains(
+            "==== ERROR collecting ./tests/fixtures/duplicates/src.py ====\n\
+             ./tests/fixtures/duplicates/src.py:5: PtCollectionWarning: test_one is redefined",
+        ));
+    }
+}
+
+mod tmp_path {
+    use std::{env, sync::LazyLock};
+
+    use predicates::str::contains;
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/tmp_path"));
+
+    #[test]
+    fn cli() {
+        let basetemp = env::temp_dir().join(format!("pt-fixtures-tmp_path-{}", std::process::id()));
+        let mut pt_cmd = pt();
+        pt_cmd
+            .env("PYTHONPATH", FIXTURES.join("with_py"))
+            .arg("-q")
+            .arg("--basetemp")
+            .arg(&basetemp)
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+        assert_eq!(
+            "hello",
+            fs::read_to_string(basetemp.join("test_tmp_path0/hello.txt")).unwrap()
+        );
+        assert!(basetemp.join("data0").is_dir());
+        assert!(basetemp.join("test_tmpdir0").is_dir());
+        fs::remove_dir_all(basetemp).unwrap();
+    }
+
+    #[test]
+    fn tmpdir_without_py() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .env("PYTHONPATH", FIXTURES.join("without_py"))
+            .args(["-q", "-k", "tmpdir"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        pt_cmd.assert().stdout(contains(
+            "==== TestClass::test_tmpdir ====\n\
+             ImportError: tmpdir is a py.path.local: install py to use it, or request tmp_path\n",
+        ));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod monkeypatch {
+    use super::*;
+
+    #[test]
+    fn undone_after_failure() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/monkeypatch/src.py"]);
+        let expected_stdout =
+            fs::read_to_string("./tests/fixtures/monkeypatch/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod capture {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static ID: &str = "UID";
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/capture"));
+
+    #[test]
+    fn runner() {
+        let suite = load_src(&FIXTURES);
+        let expected_runner = fs::read_to_string(FIXTURES.join("run.py")).unwrap();
+        assert_eq!(expected_runner, suite.runner(ID));
+    }
+
+    #[test]
+    fn fixtures_and_failure_report() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn capture_sys() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "--capture=sys"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn no_capture() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "-s"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("no_capture.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn capture_tee_sys() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "--capture=tee-sys"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod request {
+    use super::*;
+
+    #[test]
+    fn user_fixtures() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/request/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/request/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn module_context() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/module_context/src.py"]);
+        pt_cmd
+            .assert()
+            .stdout(eq("test_request_module PASS\ntest_module_markers PASS\n"));
+        pt_cmd.assert().code(0);
+    }
+}
+
+mod scope {
+    use super::*;
+
+    #[test]
+    fn kept_for_class_and_module() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/scope/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/scope/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod autouse {
+    use super::*;
+
+    #[test]
+    fn conftest_module_and_class() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/autouse/test_autouse.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/autouse/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+}
+
+mod shadowing {
+    use super::*;
+
+    #[test]
+    fn runner_keeps_module_globals() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/shadowing/test_shadowing.py"]);
+        pt_cmd
+            .assert()
+            .stdout(eq("test_globals PASS\nTestUnittest::test_globals PASS\n"));
+        pt_cmd.assert().code(0);
+    }
+}
+
+mod parametrize {
+    use std::sync::LazyLock;
+
+    use super::*;
+    static ID: &str = "UID";
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/parametrize"));
+
+    #[test]
+    fn runner() {
+        let suite = load_src(&FIXTURES);
+        let expected_runner = fs::read_to_string(FIXTURES.join("run.py")).unwrap();
+        assert_eq!(expected_runner, suite.runner(ID));
+    }
+
+    #[test]
+    fn summary_report() {
+        let mut suite = load_src(&FIXTURES);
+        let stdout = fs::read_to_string(FIXTURES.join("stdout.out")).unwrap();
+        suite.update_status(ID, &stdout).unwrap();
+        // followed by the warnings summary
+        let expect_rpt = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
+        let report = suite.summary_report();
+        assert!(expect_rpt.starts_with(&report), "{report}");
+    }
+
+    #[test]
+    fn expanded_ids() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("-q").arg(FIXTURES.join("src.py").as_os_str());
         let expected_stdout = fs::read_to_string(FIXTURES.join("summary.rpt")).unwrap();
         pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn select_by_id() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "-k", "larger or sqlite"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("selected.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+
+    #[test]
+    fn select_by_param_mark() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "-m", "slow"])
+            .arg(FIXTURES.join("src.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("marked.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(0);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args(["-q", "-rE"])
+            .arg(FIXTURES.join("test_invalid.py").as_os_str());
+        let expected_stdout = fs::read_to_string(FIXTURES.join("invalid.rpt")).unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(2);
+    }
+
+    #[test]
+    fn maxfail_stop_precedes_summary_line() {
+        let mut pt_cmd = pt();
+        pt_cmd
+            .args([
+                "--continue-on-collection-errors",
+                "--maxfail=1",
+                "-k",
+                "no_such_test",
+            ])
+            .arg(FIXTURES.join("test_invalid.py").as_os_str());
+        pt_cmd.assert().code(1);
+        pt_cmd.assert().stdout(predicates::str::ends_with(
+            "\n!!!!!!!!!!!!!!!!!!!! stopping after 1 failures !!!!!!!!!!!!!!!!!!!!\nno tests ran\n",
+        ));
+    }
+}
+
+mod shim {
+    use predicates::str::contains;
+
+    use super::*;
+
+    #[test]
+    fn pytest_api() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "--pytest-shim", "./tests/fixtures/shim/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/shim/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn skipped_module() {
+        for runner in ["inline", "import"] {
+            let mut pt_cmd = pt();
+            pt_cmd.args([
+                "-q",
+                "--pytest-shim",
+                "--runner",
+                runner,
+                "./tests/fixtures/shim/skipped.py",
+                "./tests/fixtures/shim/skipped_by_import.py",
+            ]);
+            let expected_stdout = "test_uses_module SKIP
+test_also_skipped SKIP
+test_uses_helper SKIP
+";
+            pt_cmd.assert().stdout(eq(expected_stdout));
+            pt_cmd.assert().code(0);
+        }
+    }
+
+    #[test]
+    fn skip_not_module_level() {
+        let mut pt_cmd = pt();
+        pt_cmd.args([
+            "-q",
+            "--pytest-shim",
+            "./tests/fixtures/shim/not_module_level.py",
+        ]);
+        pt_cmd
+            .assert()
+            .stdout(contains("Skipped: skip outside of a test\n"));
+        pt_cmd.assert().code(2);
+    }
+}
+
+mod warnings {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/warnings/src.py"]);
         pt_cmd.assert().code(0);
+        // as pytest, the location of each warning is the absolute path of the file
+        let stdout = String::from_utf8(pt_cmd.output().unwrap().stdout).unwrap();
+        let fixtures = fs::canonicalize("./tests/fixtures/warnings").unwrap();
+        let stdout = stdout.replace(&fixtures.display().to_string(), "tests/fixtures/warnings");
+        let expected_stdout = fs::read_to_string("./tests/fixtures/warnings/summary.rpt").unwrap();
+        assert_eq!(expected_stdout, stdout);
+    }
+
+    #[test]
+    fn error() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "-W", "error", "./tests/fixtures/warnings/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/warnings/error.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn invalid_filter() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "-W", "bogus", "./tests/fixtures/warnings/src.py"]);
+        pt_cmd.assert().stdout(eq(""));
+        pt_cmd.assert().code(4);
+    }
+}
+
+mod unittest {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/unittest/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/unittest/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod skipping {
+    use super::*;
+
+    #[test]
+    fn marks() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/skipping/src.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/skipping/summary.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+
+    #[test]
+    fn xfail_strict() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-q", "./tests/fixtures/skipping/strict/test_strict.py"]);
+        let expected_stdout = fs::read_to_string("./tests/fixtures/skipping/strict.rpt").unwrap();
+        pt_cmd.assert().stdout(eq(expected_stdout));
+        pt_cmd.assert().code(1);
+    }
+}
+
+mod collection {
+    use std::sync::LazyLock;
+
+    use predicates::{boolean::PredicateBooleanExt, str::contains};
+
+    use super::*;
+    static FIXTURES: LazyLock<PathBuf> =
+        LazyLock::new(|| PathBuf::from("./tests/fixtures/collection"));
+
+    #[test]
+    fn import_error() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-rE", FIXTURES.to_str().unwrap()]);
+        pt_cmd.assert().code(2);
+        pt_cmd.assert().stdout(contains(
+            "test_broken_import.py:1: in <module>\n    import no_such_module\n\
+             ModuleNotFoundError: No module named 'no_such_module'\n",
+        ));
+        pt_cmd.assert().stdout(contains(
+            "ERROR ./tests/fixtures/collection/test_broken_import.py - ModuleNotFoundError",
+        ));
+        pt_cmd.assert().stdout(contains("test_passes PASS").not());
+    }
+
+    #[test]
+    fn continue_on_collection_errors() {
+        let mut pt_cmd = pt();
+        pt_cmd.args([
+            "--continue-on-collection-errors",
+            FIXTURES.to_str().unwrap(),
+        ]);
+        pt_cmd.assert().code(1);
+        pt_cmd.assert().stdout(contains("test_passes PASS\n"));
+        pt_cmd.assert().stdout(contains(
+            "==== ERROR collecting ./tests/fixtures/collection/test_broken_import.py ====\n",
+        ));
+    }
+
+    #[test]
+    fn import_errors_before_any_test_runs() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("./tests/fixtures/collection_order");
+        pt_cmd.assert().code(2);
+        pt_cmd.assert().stdout(contains("test_runs_first").not());
+        pt_cmd.assert().stdout(contains(
+            "test_b_broken_import.py:5: in <module>\n    from no_such_package import helper\n\
+             ModuleNotFoundError: No module named 'no_such_package'\n",
+        ));
+    }
+
+    #[test]
+    fn errors_count_towards_maxfail() {
+        let mut pt_cmd = pt();
+        pt_cmd.args([
+            "--continue-on-collection-errors",
+            "--maxfail=1",
+            FIXTURES.to_str().unwrap(),
+        ]);
+        pt_cmd.assert().code(1);
+        pt_cmd.assert().stdout(contains("test_passes PASS").not());
+        pt_cmd
+            .assert()
+            .stdout(contains("stopping after 1 failures"));
+    }
+}
+
+mod cache {
+    use std::env;
+
+    use super::*;
+
+    /// A rootdir where `test_a.py::test_fails` fails, run once to fill its cache
+    fn project(name: &str) -> PathBuf {
+        let rootdir = env::temp_dir().join(format!("pt-fixtures-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&rootdir);
+        fs::create_dir_all(&rootdir).unwrap();
+        fs::write(rootdir.join("pytest.ini"), "[pytest]\n").unwrap();
+        fs::write(
+            rootdir.join("test_a.py"),
+            "def test_passes():\n    pass\n\n\ndef test_fails():\n    assert False\n",
+        )
+        .unwrap();
+        fs::write(rootdir.join("test_b.py"), "def test_b():\n    pass\n").unwrap();
+        cargo_bin_cmd!("pt").arg(&rootdir).assert().code(1);
+        rootdir
+    }
+
+    /// `pt` with the cache, listing only the tests' statuses
+    fn run(rootdir: &Path, args: &[&str]) -> assert_cmd::Command {
+        let mut pt_cmd = cargo_bin_cmd!("pt");
+        pt_cmd
+            .args(["-q", "-rN", "--tb=no"])
+            .args(args)
+            .arg(rootdir);
+        pt_cmd
+    }
+
+    #[test]
+    fn last_failed() {
+        let rootdir = project("last-failed");
+        run(&rootdir, &["--lf"]).assert().code(1).stdout(eq(
+            "run-last-failure: rerun previous 1 failures\ntest_fails FAIL\n",
+        ));
+        fs::remove_dir_all(&rootdir).unwrap();
+    }
+
+    #[test]
+    fn failed_first() {
+        let rootdir = project("failed-first");
+        run(&rootdir, &["--ff"])
+            .assert()
+            .code(1)
+            .stdout(eq("test_fails FAIL\ntest_passes PASS\ntest_b PASS\n"));
+        fs::remove_dir_all(&rootdir).unwrap();
+    }
+
+    #[test]
+    fn new_first() {
+        let rootdir = project("new-first");
+        fs::write(
+            rootdir.join("test_b.py"),
+            "def test_b():\n    pass\n\n\ndef test_new():\n    pass\n",
+        )
+        .unwrap();
+        run(&rootdir, &["--nf"]).assert().code(1).stdout(eq(
+            "test_passes PASS\ntest_fails FAIL\ntest_new PASS\ntest_b PASS\n",
+        ));
+        fs::remove_dir_all(&rootdir).unwrap();
+    }
+
+    #[test]
+    fn cache_show() {
+        let rootdir = project("cache-show");
+        let expected = format!(
+            "cachedir: {}\n\
+             --------------------- cache values for 'cache/lastfailed' ---------------------\n\
+             cache/lastfailed contains:\n  {{\n    \"test_a.py::test_fails\": true\n  }}\n",
+            rootdir.join(".pytest_cache").display()
+        );
+        run(&rootdir, &["--cache-show=cache/lastfailed"])
+            .assert()
+            .code(0)
+            .stdout(eq(expected));
+        fs::remove_dir_all(&rootdir).unwrap();
+    }
+
+    #[test]
+    fn cache_clear() {
+        let rootdir = project("cache-clear");
+        run(&rootdir, &["--lf", "--cache-clear"])
+            .assert()
+            .code(1)
+            .stdout(eq(
+                "run-last-failure: no previously failed tests, not deselecting items.\n\
+             test_passes PASS\ntest_fails FAIL\ntest_b PASS\n",
+            ));
+        // the cache is saved again after the run
+        run(&rootdir, &["--lf"])
+            .assert()
+            .stdout(predicates::str::starts_with(
+                "run-last-failure: rerun previous 1 failures\n",
+            ));
+        fs::remove_dir_all(&rootdir).unwrap();
     }
 }
 
 mod exitcodes {
+    use std::env;
+
     use predicates::str::contains;
 
     use super::*;
 
     #[test]
     fn invalid_src() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
+        let mut pt_cmd = pt();
         pt_cmd.arg(PathBuf::from("./tests/fixtures/basic/stdout.out"));
-        pt_cmd.assert().code(3);
-        pt_cmd.assert().stderr(contains(
-            "Error parsing \"./tests/fixtures/basic/stdout.out\": ",
+        pt_cmd.assert().code(2);
+        pt_cmd.assert().stdout(contains(
+            "==== ERROR collecting ./tests/fixtures/basic/stdout.out ====\n1: ",
         ));
+        pt_cmd
+            .assert()
+            .stdout(contains("Interrupted: 1 error during collection"));
     }
 
     #[test]
     fn file_not_found() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
+        let mut pt_cmd = pt();
         pt_cmd.arg(PathBuf::from("./tests/fixtures/no"));
         pt_cmd.assert().code(3);
         pt_cmd.assert().stderr(contains("./tests/fixtures/no"));
@@ -251,10 +1088,122 @@ mod exitcodes {
 
     #[test]
     fn no_file_specified() {
-        let mut pt_cmd = cargo_bin_cmd!("pt");
+        let mut pt_cmd = pt();
+        pt_cmd.assert().code(5);
+        pt_cmd.assert().stdout(contains("no tests ran"));
+    }
+
+    #[test]
+    fn unknown_flag() {
+        let mut pt_cmd = pt();
+        pt_cmd.arg("--no-such-flag");
+        pt_cmd.assert().code(4);
+        pt_cmd
+            .assert()
+            .stderr(contains("unrecognized arguments: --no-such-flag"));
+    }
+
+    #[test]
+    fn no_tests_collected() {
+        let mut pt_cmd = pt();
+        pt_cmd.args(["-k", "no_such_test", "./tests/fixtures/basic/src.py"]);
+        pt_cmd.assert().code(5);
+        pt_cmd.assert().stdout(contains("no tests ran"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn python_falls_back_to_python3() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // a `python` which fails, as a pyenv shim with no version selected
+        let bin = env::temp_dir().join(format!("pt-fixtures-python-{}", std::process::id()));
+        fs::create_dir_all(&bin).unwrap();
+        let python = bin.join("python");
+        fs::write(&python, "#!/bin/sh\nexit 127\n").unwrap();
+        fs::set_permissions(&python, fs::Permissions::from_mode(0o755)).unwrap();
+        let python3 = std::process::Command::new("python3")
+            .args(["-c", "import sys; print(sys.executable)"])
+            .output()
+            .unwrap();
+        let python3 = String::from_utf8(python3.stdout).unwrap();
+        let _ = fs::remove_file(bin.join("python3"));
+        std::os::unix::fs::symlink(python3.trim(), bin.join("python3")).unwrap();
+
+        let mut pt_cmd = pt();
+        pt_cmd
+            .env("PATH", &bin)
+            .env_remove("VIRTUAL_ENV")
+            .arg("./tests/fixtures/pass/src.py");
+        pt_cmd.assert().code(0);
+        pt_cmd
+            .assert()
+            .stdout(contains("python: python3 (Python 3."));
+        fs::remove_dir_all(&bin).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ctrl_c() {
+        use std::{
+            process::{Command, Stdio},
+            thread,
+            time::Duration,
+        };
+
+        let ready = env::temp_dir().join(format!("pt-fixtures-interrupt-{}", std::process::id()));
+        let _ = fs::remove_file(&ready);
+        let pt = Command::new(cargo_bin!("pt"))
+            .args([
+                "-p",
+                "no:cacheprovider",
+                "-q",
+                "./tests/fixtures/interrupt/src.py",
+            ])
+            .env("PT_INTERRUPT_READY", &ready)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        for _ in 0..300 {
+            if ready.exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        assert!(ready.exists(), "test_interrupted did not start");
+        // SAFETY: `kill` does not access any memory owned by this process
+        unsafe { libc::kill(pt.id() as libc::pid_t, libc::SIGINT) };
+        let output = pt.wait_with_output().unwrap();
+        fs::remove_file(&ready).unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(Some(2), output.status.code(), "{stdout}");
+        assert!(
+            stdout.starts_with("test_interrupted INTERRUPTED\ntest_not_run NO RUN\n"),
+            "{stdout}"
+        );
+        // as pytest, the location is the interrupted frame's file, which python has as absolute
+        let (_, location) = stdout
+            .split_once(
+                "!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!! KeyboardInterrupt !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!\n",
+            )
+            .unwrap();
+        assert!(
+            location.ends_with("tests/fixtures/interrupt/src.py:8: KeyboardInterrupt\n"),
+            "{stdout}"
+        );
+    }
+
+    #[test]
+    fn missing_python() {
+        let mut pt_cmd = pt();
+        pt_cmd.args([
+            "--python",
+            "./no/such/python",
+            "./tests/fixtures/basic/src.py",
+        ]);
         pt_cmd.assert().code(4);
         pt_cmd
             .assert()
-            .stderr(contains("Please provide a file to test"));
+            .stderr(contains("Python interpreter ./no/such/python not found"));
     }
 }